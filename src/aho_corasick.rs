@@ -0,0 +1,488 @@
+// Aho-Corasick automaton: finds every occurrence of many byte-string patterns in one pass.
+
+use std::collections::VecDeque;
+
+const ROOT: u32 = 0;
+
+/// Which matches are reported when occurrences of the patterns overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// Every occurrence of every pattern, in order of their end.
+    #[allow(dead_code)]
+    Overlapping,
+    /// Non-overlapping matches from left to right. Of the matches starting at the leftmost
+    /// position, the one whose pattern comes first in the pattern list wins.
+    #[allow(dead_code)]
+    LeftmostFirst,
+    /// Like `LeftmostFirst`, but the longest of the matches at the leftmost position wins.
+    LeftmostLongest,
+}
+
+impl MatchKind {
+    /// Whether `a` beats `b` under leftmost semantics.
+    fn prefers(self, a: Match, b: Match) -> bool {
+        match self {
+            MatchKind::Overlapping => false,
+            MatchKind::LeftmostFirst => (a.start, a.pattern) < (b.start, b.pattern),
+            MatchKind::LeftmostLongest => (a.start, b.end, a.pattern) < (b.start, a.end, b.pattern),
+        }
+    }
+}
+
+/// Occurrence of pattern number `pattern` at `text[start..end]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Node of the trie of the patterns, plus the links that turn the trie into an automaton.
+struct State {
+    // trie edges, sorted by byte
+    children: Vec<(u8, u32)>,
+    // state spelling the longest proper suffix of this one's string
+    fail: u32,
+    // nearest state on the failure chain that ends a pattern
+    output: Option<u32>,
+    // pattern ending exactly here; for duplicate patterns, the first of them
+    pattern: Option<u32>,
+    depth: usize,
+}
+
+impl State {
+    fn new(depth: usize) -> Self {
+        State {
+            children: Vec::new(),
+            fail: ROOT,
+            output: None,
+            pattern: None,
+            depth,
+        }
+    }
+
+    fn child(&self, byte: u8) -> Option<u32> {
+        let index = self
+            .children
+            .binary_search_by_key(&byte, |&(b, _)| b)
+            .ok()?;
+        Some(self.children[index].1)
+    }
+}
+
+/// State reached from `state` by reading `byte`, following failure links until some
+/// suffix of the string read so far can be extended by `byte`.
+fn transition(states: &[State], mut state: u32, byte: u8) -> u32 {
+    loop {
+        if let Some(next) = states[state as usize].child(byte) {
+            return next;
+        }
+        if state == ROOT {
+            return ROOT;
+        }
+        state = states[state as usize].fail;
+    }
+}
+
+/// Multi-pattern matcher. The states are the nodes of a trie of the patterns; a failure link
+/// sends a state to the longest suffix of its string that is also in the trie, and an output
+/// link to the nearest such suffix that is a whole pattern, so a text is scanned once
+/// whatever the number of patterns.
+pub struct AhoCorasick {
+    states: Vec<State>,
+    pattern_lens: Vec<usize>,
+    max_len: usize,
+    kind: MatchKind,
+}
+
+impl AhoCorasick {
+    /// Automaton reporting overlapping matches of `patterns`. Panics if a pattern is empty.
+    #[allow(dead_code)]
+    pub fn new<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        Self::with_match_kind(patterns, MatchKind::Overlapping)
+    }
+
+    /// Automaton reporting matches of `patterns` picked by `kind`. Panics if a pattern is empty.
+    pub fn with_match_kind<I, P>(patterns: I, kind: MatchKind) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let mut states = vec![State::new(0)];
+        let mut pattern_lens = Vec::new();
+        for (id, pattern) in patterns.into_iter().enumerate() {
+            let pattern = pattern.as_ref();
+            assert!(!pattern.is_empty(), "pattern {} is empty", id);
+            let mut state = ROOT as usize;
+            for (depth, &byte) in pattern.iter().enumerate() {
+                let children = &states[state].children;
+                state = match children.binary_search_by_key(&byte, |&(b, _)| b) {
+                    Ok(index) => children[index].1 as usize,
+                    Err(index) => {
+                        let next = states.len();
+                        states.push(State::new(depth + 1));
+                        states[state].children.insert(index, (byte, next as u32));
+                        next
+                    }
+                };
+            }
+            states[state].pattern.get_or_insert(id as u32);
+            pattern_lens.push(pattern.len());
+        }
+
+        // Breadth first, so the failure link of a state, which is shallower, is set before it
+        // is needed. The root's children fail to the root, which they already do.
+        let mut queue: VecDeque<u32> = states[0].children.iter().map(|&(_, s)| s).collect();
+        while let Some(state) = queue.pop_front() {
+            let fail = states[state as usize].fail;
+            for index in 0..states[state as usize].children.len() {
+                let (byte, child) = states[state as usize].children[index];
+                let child_fail = transition(&states, fail, byte);
+                let target = &states[child_fail as usize];
+                let output = match target.pattern {
+                    Some(_) => Some(child_fail),
+                    None => target.output,
+                };
+                let child_state = &mut states[child as usize];
+                child_state.fail = child_fail;
+                child_state.output = output;
+                queue.push_back(child);
+            }
+        }
+
+        AhoCorasick {
+            states,
+            max_len: pattern_lens.iter().copied().max().unwrap_or(0),
+            pattern_lens,
+            kind,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn match_kind(&self) -> MatchKind {
+        self.kind
+    }
+
+    #[allow(dead_code)]
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_lens.len()
+    }
+
+    /// Patterns ending at `state`, longest first.
+    fn outputs(&self, state: u32) -> impl Iterator<Item = usize> + '_ {
+        let first = match self.states[state as usize].pattern {
+            Some(_) => Some(state),
+            None => self.states[state as usize].output,
+        };
+        std::iter::successors(first, |&s| self.states[s as usize].output)
+            .filter_map(|s| self.states[s as usize].pattern)
+            .map(|pattern| pattern as usize)
+    }
+
+    /// Matches in `text`, picked by the automaton's match kind.
+    #[allow(dead_code)]
+    pub fn find_iter<'a, 't>(&'a self, text: &'t [u8]) -> FindIter<'a, 't> {
+        FindIter {
+            stream: Some(self.stream()),
+            text,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        let mut state = ROOT;
+        text.iter().any(|&byte| {
+            state = transition(&self.states, state, byte);
+            self.outputs(state).next().is_some()
+        })
+    }
+
+    /// Matcher for a text that arrives in chunks. Positions count from the first byte fed.
+    pub fn stream(&self) -> Stream<'_> {
+        Stream {
+            automaton: self,
+            state: ROOT,
+            position: 0,
+            recent: VecDeque::with_capacity(self.max_len),
+            candidate: None,
+        }
+    }
+}
+
+/// Search state carried from one chunk of a text to the next.
+pub struct Stream<'a> {
+    automaton: &'a AhoCorasick,
+    state: u32,
+    // bytes read so far
+    position: usize,
+    // the last `max_len` bytes read, to rescan after a leftmost match
+    recent: VecDeque<u8>,
+    // best leftmost match so far, held back while a better one may still show up
+    candidate: Option<Match>,
+}
+
+impl Stream<'_> {
+    /// Reads the next chunk, returning the matches that became certain. Under leftmost
+    /// semantics a match may be reported only when a later chunk rules out better ones.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Match> {
+        let mut found = Vec::new();
+        for &byte in chunk {
+            self.step(byte, &mut found);
+        }
+        found
+    }
+
+    /// Ends the text, returning the matches held back so far.
+    pub fn finish(mut self) -> Vec<Match> {
+        let mut found = Vec::new();
+        while let Some(candidate) = self.candidate.take() {
+            self.report(candidate, &mut found);
+        }
+        found
+    }
+
+    #[allow(dead_code)]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn step(&mut self, byte: u8, found: &mut Vec<Match>) {
+        let automaton = self.automaton;
+        // without patterns there is never a match to rescan after
+        if automaton.max_len > 0 {
+            if self.recent.len() == automaton.max_len {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(byte);
+        }
+        self.state = transition(&automaton.states, self.state, byte);
+        self.position += 1;
+
+        let end = self.position;
+        for pattern in automaton.outputs(self.state) {
+            let candidate = Match {
+                pattern,
+                start: end - automaton.pattern_lens[pattern],
+                end,
+            };
+            match automaton.kind {
+                MatchKind::Overlapping => found.push(candidate),
+                kind => {
+                    if self
+                        .candidate
+                        .is_none_or(|best| kind.prefers(candidate, best))
+                    {
+                        self.candidate = Some(candidate);
+                    }
+                }
+            }
+        }
+
+        // Every later match extends a suffix of the string the current state spells,
+        // so none can start before it and the candidate can't be beaten any more.
+        let earliest_start = end - automaton.states[self.state as usize].depth;
+        if let Some(candidate) = self.candidate {
+            if candidate.start < earliest_start {
+                self.candidate = None;
+                self.report(candidate, found);
+            }
+        }
+    }
+
+    /// Reports a leftmost match and rescans the bytes read past its end from the root,
+    /// as the next match can't overlap it. The candidate's start is at most `max_len`
+    /// bytes back, so those bytes are still in `recent`.
+    fn report(&mut self, candidate: Match, found: &mut Vec<Match>) {
+        found.push(candidate);
+        let past_end = self.position - candidate.end;
+        let rescan: Vec<u8> = self.recent.drain(self.recent.len() - past_end..).collect();
+        self.state = ROOT;
+        self.position = candidate.end;
+        for byte in rescan {
+            self.step(byte, found);
+        }
+    }
+}
+
+/// Matches in a text, found lazily by feeding it through a `Stream` a byte at a time.
+#[allow(dead_code)]
+pub struct FindIter<'a, 't> {
+    stream: Option<Stream<'a>>,
+    text: &'t [u8],
+    pending: std::vec::IntoIter<Match>,
+}
+
+impl Iterator for FindIter<'_, '_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(found) = self.pending.next() {
+                return Some(found);
+            }
+            let stream = self.stream.as_mut()?;
+            let found = match self.text.get(stream.position()) {
+                Some(&byte) => {
+                    let mut found = Vec::new();
+                    stream.step(byte, &mut found);
+                    found
+                }
+                None => self.stream.take()?.finish(),
+            };
+            self.pending = found.into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Every match, found by comparing each pattern at each position,
+    /// keeping only the first of duplicate patterns.
+    fn brute_force(patterns: &[Vec<u8>], text: &[u8]) -> Vec<Match> {
+        let mut found = Vec::new();
+        for end in 1..=text.len() {
+            for (pattern, bytes) in patterns.iter().enumerate() {
+                let first = patterns.iter().position(|p| p == bytes) == Some(pattern);
+                if first && text[..end].ends_with(bytes) {
+                    found.push(Match {
+                        pattern,
+                        start: end - bytes.len(),
+                        end,
+                    });
+                }
+            }
+        }
+        found
+    }
+
+    /// Picks non-overlapping matches from `all` left to right, the best by `kind` each time.
+    fn leftmost(all: &[Match], kind: MatchKind) -> Vec<Match> {
+        let mut picked = Vec::new();
+        let mut position = 0;
+        while let Some(&best) = all
+            .iter()
+            .filter(|m| m.start >= position)
+            .reduce(|best, m| if kind.prefers(*m, *best) { m } else { best })
+        {
+            picked.push(best);
+            position = best.end;
+        }
+        picked
+    }
+
+    fn sorted(matches: Vec<Match>) -> Vec<(usize, usize, usize)> {
+        let mut tuples: Vec<_> = matches
+            .into_iter()
+            .map(|m| (m.end, m.start, m.pattern))
+            .collect();
+        tuples.sort_unstable();
+        tuples
+    }
+
+    #[test]
+    fn match_kinds() {
+        let text = b"Samwise Gamgee";
+        let find = |patterns: &[&str], kind| {
+            AhoCorasick::with_match_kind(patterns, kind)
+                .find_iter(text)
+                .map(|m| (m.pattern, m.start, m.end))
+                .collect::<Vec<_>>()
+        };
+        let patterns = ["Sam", "Samwise", "wise", "Gam", "amgee"];
+        assert_eq!(
+            find(&patterns, MatchKind::Overlapping),
+            vec![(0, 0, 3), (1, 0, 7), (2, 3, 7), (3, 8, 11), (4, 9, 14)]
+        );
+        assert_eq!(
+            find(&patterns, MatchKind::LeftmostFirst),
+            vec![(0, 0, 3), (2, 3, 7), (3, 8, 11)]
+        );
+        assert_eq!(
+            find(&patterns, MatchKind::LeftmostLongest),
+            vec![(1, 0, 7), (3, 8, 11)]
+        );
+        // a match further left wins even if it is found later
+        assert_eq!(
+            find(&["ise G", "amwise Gamgee"], MatchKind::LeftmostFirst),
+            vec![(1, 1, 14)]
+        );
+
+        let automaton = AhoCorasick::new(["needle", "haystack"]);
+        assert!(automaton.is_match(b"a needle in a"));
+        assert!(!automaton.is_match(b"needl haystac"));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let patterns: Vec<Vec<u8>> = (0..rng.gen_range(1..12))
+                .map(|_| {
+                    let len = rng.gen_range(1..5);
+                    (0..len).map(|_| rng.gen_range(b'a'..b'd')).collect()
+                })
+                .collect();
+            let text: Vec<u8> = (0..200).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            let all = brute_force(&patterns, &text);
+
+            let overlapping = AhoCorasick::new(&patterns).find_iter(&text).collect();
+            assert_eq!(sorted(overlapping), sorted(all.clone()));
+            for kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+                let automaton = AhoCorasick::with_match_kind(&patterns, kind);
+                let found: Vec<_> = automaton.find_iter(&text).collect();
+                assert_eq!(found, leftmost(&all, kind), "{:?} {:?}", kind, patterns);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_matches_one_pass() {
+        let mut rng = rand::thread_rng();
+        let patterns = ["ERROR", "ERR", "disk full", "timeout", "time", "out"];
+        let text = b"12:00 ERROR disk full; 12:01 WARN timeout; 12:02 ERR timeouts".repeat(5);
+        for kind in [
+            MatchKind::Overlapping,
+            MatchKind::LeftmostFirst,
+            MatchKind::LeftmostLongest,
+        ] {
+            let automaton = AhoCorasick::with_match_kind(patterns, kind);
+            let expected: Vec<_> = automaton.find_iter(&text).collect();
+            for _ in 0..20 {
+                let mut stream = automaton.stream();
+                let mut found = Vec::new();
+                let mut rest = &text[..];
+                while !rest.is_empty() {
+                    let (chunk, tail) = rest.split_at(rng.gen_range(1..=rest.len().min(8)));
+                    found.extend(stream.feed(chunk));
+                    rest = tail;
+                }
+                assert_eq!(stream.position(), text.len());
+                found.extend(stream.finish());
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn streams_without_patterns() {
+        for kind in [MatchKind::Overlapping, MatchKind::LeftmostFirst] {
+            let automaton = AhoCorasick::with_match_kind(Vec::<&[u8]>::new(), kind);
+            let mut stream = automaton.stream();
+            for _ in 0..100 {
+                assert!(stream.feed(b"no patterns to find").is_empty());
+            }
+            assert!(stream.recent.is_empty());
+            assert_eq!(stream.position(), 1_900);
+            assert!(stream.finish().is_empty());
+            assert_eq!(automaton.find_iter(b"text").count(), 0);
+        }
+    }
+}
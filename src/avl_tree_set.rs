@@ -1,22 +1,39 @@
 use core::iter::Peekable;
 use std::cmp::Ordering;
+use std::fmt::Debug;
 use std::iter::FromIterator;
 use std::mem::replace;
 
-#[derive(Debug, PartialEq, Clone)]
+mod tree_render;
+use tree_render::{TreeShape, TreeView};
+
+#[derive(Debug, Clone)]
 pub struct AvlTreeSet<T: Ord> {
     root: AvlTree<T>,
+    rotations: usize,
 }
 
 impl<'a, T: 'a + Ord> Default for AvlTreeSet<T> {
     fn default() -> Self {
-        Self { root: None }
+        Self {
+            root: None,
+            rotations: 0,
+        }
+    }
+}
+
+impl<T: Ord> PartialEq for AvlTreeSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root
     }
 }
 
 impl<'a, T: 'a + Ord> AvlTreeSet<T> {
     pub fn new() -> Self {
-        Self { root: None }
+        Self {
+            root: None,
+            rotations: 0,
+        }
     }
 
     pub fn insert(&mut self, value: T) -> bool {
@@ -45,7 +62,7 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
         for node_ptr in prev_ptrs.into_iter().rev() {
             let node = unsafe { &mut *node_ptr };
             node.update_height();
-            node.rebalance();
+            self.rotations += node.rebalance_counting();
         }
 
         true
@@ -94,7 +111,7 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
                 };
 
                 prev_node.update_height();
-                prev_node.rebalance();
+                self.rotations += prev_node.rebalance_counting();
 
                 inner_value
             } else {
@@ -110,7 +127,7 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
                 target_node.right = right_node.right.take();
 
                 target_node.update_height();
-                target_node.rebalance();
+                self.rotations += target_node.rebalance_counting();
 
                 inner_value
             } else {
@@ -131,16 +148,16 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
                 parent_left_node.left = leftmost_node.right.take();
 
                 parent_left_node.update_height();
-                parent_left_node.rebalance();
+                self.rotations += parent_left_node.rebalance_counting();
 
                 for node_ptr in inner_ptrs.into_iter().rev() {
                     let node = unsafe { &mut *node_ptr };
                     node.update_height();
-                    node.rebalance();
+                    self.rotations += node.rebalance_counting();
                 }
 
                 target_node.update_height();
-                target_node.rebalance();
+                self.rotations += target_node.rebalance_counting();
 
                 inner_value
             }
@@ -149,7 +166,7 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
         for node_ptr in prev_ptrs.into_iter().rev() {
             let node = unsafe { &mut *node_ptr };
             node.update_height();
-            node.rebalance();
+            self.rotations += node.rebalance_counting();
         }
 
         Some(taken_value)
@@ -225,6 +242,11 @@ impl<'a, T: 'a + Ord> AvlTreeSet<T> {
         }
     }
 
+    /// Rotations performed by inserts and removals since the set was created.
+    pub fn rotations(&self) -> usize {
+        self.rotations
+    }
+
     pub fn clear(&mut self) {
         self.root.take();
     }
@@ -449,35 +471,70 @@ impl<'a, T: 'a + Ord> AvlNode<T> {
     }
 
     pub fn rebalance(&mut self) -> bool {
+        self.rebalance_counting() > 0
+    }
+
+    /// Restores the AVL invariant at this node and returns the number of rotations it took.
+    fn rebalance_counting(&mut self) -> usize {
         match self.balance_factor() {
             -2 => {
                 let right_node = self.right.as_mut().unwrap();
+                let mut rotations = 1;
 
                 if right_node.balance_factor() == 1 {
                     right_node.rotate_right();
+                    rotations += 1;
                 }
 
                 self.rotate_left();
 
-                true
+                rotations
             }
 
             2 => {
                 let left_node = self.left.as_mut().unwrap();
+                let mut rotations = 1;
 
                 if left_node.balance_factor() == -1 {
                     left_node.rotate_left();
+                    rotations += 1;
                 }
 
                 self.rotate_right();
 
-                true
+                rotations
             }
-            _ => false,
+            _ => 0,
         }
     }
 }
 
+impl<T: Ord + Debug> TreeView for AvlNode<T> {
+    fn label(&self) -> String {
+        format!("{:?}", self.value)
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<T: Ord + Debug> TreeShape for AvlTreeSet<T> {
+    type Node = AvlNode<T>;
+
+    fn root_node(&self) -> Option<&AvlNode<T>> {
+        self.root.as_deref()
+    }
+
+    fn rotations(&self) -> usize {
+        self.rotations
+    }
+}
+
 extern crate rand; // 0.8.5
 use rand::Rng;
 use std::time::Instant;
@@ -543,4 +600,6 @@ fn main() {
     println!("Search (Ascending Order): {:?}", ascending_search_duration);
     println!("Delete (Random Order): {:?}", random_delete_duration);
     println!("Delete (Ascending Order): {:?}", ascending_delete_duration);
+    println!("Shape (Random Order):\n{}", random_tree.stats());
+    println!("Shape (Ascending Order):\n{}", ascending_tree.stats());
 }
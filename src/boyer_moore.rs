@@ -1,133 +1,227 @@
 use std::time::Instant;
 
-fn brute_force_search(text: &str, pattern: &str) -> Option<usize> {
-    let text_chars: Vec<char> = text.chars().collect();
-    let pattern_chars: Vec<char> = pattern.chars().collect();
+mod matcher;
+use matcher::{prefix_function, Matcher, Symbol, SymbolTable};
+
+/// Tries every alignment, comparing from the left.
+pub struct BruteForce<T> {
+    pattern: Vec<T>,
+}
 
-    for i in 0..=text_chars.len() - pattern_chars.len() {
-        if text_chars[i..i + pattern_chars.len()] == pattern_chars {
-            return Some(i);
+impl<T: Eq + Clone> BruteForce<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        BruteForce {
+            pattern: pattern.to_vec(),
         }
     }
-    None
 }
 
-fn prefix_shift_search(text: &str, pattern: &str) -> Option<usize> {
-    let text_chars: Vec<char> = text.chars().collect();
-    let pattern_chars: Vec<char> = pattern.chars().collect();
+impl<T: Eq> Matcher<T> for BruteForce<T> {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
 
-    let mut i = 0;
-    while i <= text_chars.len() - pattern_chars.len() {
-        let mut j = pattern_chars.len() - 1;
-        while j > 0 && pattern_chars[j] == text_chars[i + j] {
-            j -= 1;
-        }
-        if j == 0 {
-            return Some(i);
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        let last = text.len().checked_sub(self.pattern.len())?;
+        (start..=last).find(|&i| text[i..i + self.pattern.len()] == self.pattern[..])
+    }
+}
+
+/// Compares from the left and, after matching `j` symbols, moves the pattern by the smallest
+/// amount that keeps a prefix of it lined up with those symbols: `j` minus their longest
+/// border. Unlike KMP, it compares again from the pattern's start after each move.
+pub struct PrefixShift<T> {
+    pattern: Vec<T>,
+    // shift after `j` matched symbols; `shift[len]` is the pattern's period
+    shift: Vec<usize>,
+}
+
+impl<T: Eq + Clone> PrefixShift<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        let prefix = prefix_function(pattern);
+        let shift = (0..=pattern.len())
+            .map(|matched| match matched {
+                0 => 1,
+                _ => matched - prefix[matched - 1],
+            })
+            .collect();
+        PrefixShift {
+            pattern: pattern.to_vec(),
+            shift,
         }
-        i += j;
     }
-    None
 }
 
-fn suffix_shift_search(text: &str, pattern: &str) -> Option<usize> {
-    let text_chars: Vec<char> = text.chars().collect();
-    let pattern_chars: Vec<char> = pattern.chars().collect();
+impl<T: Eq> Matcher<T> for PrefixShift<T> {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
 
-    let mut skip = vec![pattern_chars.len(); 256];
-    for (i, &c) in pattern_chars.iter().enumerate() {
-        skip[c as usize] = pattern_chars.len() - i - 1;
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        let mut i = start;
+        while i + self.pattern.len() <= text.len() {
+            let matched = self
+                .pattern
+                .iter()
+                .zip(&text[i..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if matched == self.pattern.len() {
+                return Some(i);
+            }
+            i += self.shift[matched];
+        }
+        None
     }
 
-    let mut i = pattern_chars.len() - 1;
-    while i < text_chars.len() {
-        let mut j = pattern_chars.len() - 1;
-        while j > 0 && pattern_chars[j] == text_chars[i] {
-            i -= 1;
-            j -= 1;
+    fn search_after(&self, text: &[T], previous: usize) -> Option<usize> {
+        self.find_at(text, previous + self.shift[self.pattern.len()])
+    }
+}
+
+/// Horspool's simplification of Boyer-Moore: checks the text symbol under the pattern's last
+/// symbol first, and moves the pattern until its rightmost other occurrence of that symbol
+/// lines up with it.
+pub struct SuffixShift<T: Symbol> {
+    pattern: Vec<T>,
+    skip: T::Table,
+}
+
+impl<T: Symbol> SuffixShift<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        let mut skip = T::Table::new(pattern.len());
+        for (i, symbol) in pattern
+            .iter()
+            .enumerate()
+            .take(pattern.len().saturating_sub(1))
+        {
+            skip.set(symbol, pattern.len() - 1 - i);
         }
-        if j == 0 {
-            return Some(i);
+        SuffixShift {
+            pattern: pattern.to_vec(),
+            skip,
         }
-        i += std::cmp::max(skip[text_chars[i] as usize], pattern_chars.len() - j);
     }
-    None
 }
 
-use std::cmp;
-
-fn boyer_moore_search(text: &str, pattern: &str) -> Option<usize> {
-    let text = text.as_bytes();
-    let pattern = pattern.as_bytes();
-    
-    if pattern.is_empty() { return Some(0); }
-    if text.len() < pattern.len() { return None; }
+impl<T: Symbol> Matcher<T> for SuffixShift<T> {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
 
-    // Bad Character Heuristic
-    let mut bad_char = [pattern.len(); 256];
-    for (i, &c) in pattern.iter().enumerate() {
-        bad_char[c as usize] = pattern.len() - 1 - i;
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        let m = self.pattern.len();
+        let mut i = start;
+        while i + m <= text.len() {
+            let last = &text[i + m - 1];
+            if *last == self.pattern[m - 1] && text[i..i + m - 1] == self.pattern[..m - 1] {
+                return Some(i);
+            }
+            i += self.skip.get(last);
+        }
+        None
     }
+}
+
+/// Compares from the right and, on a mismatch, moves the pattern by the larger of the
+/// bad-character shift, which lines up the mismatched text symbol with its rightmost
+/// occurrence in the pattern, and the good-suffix shift, which lines up the matched suffix
+/// with its next occurrence in the pattern, or the longest prefix of the pattern that ends it.
+pub struct BoyerMoore<T: Symbol> {
+    pattern: Vec<T>,
+    // one past the rightmost position of each symbol in the pattern, 0 if it doesn't occur
+    last: T::Table,
+    // shift when the last `len - j` symbols matched; `good_suffix[0]` is the pattern's period
+    good_suffix: Vec<usize>,
+}
 
-    // Good Suffix Heuristic
-    let mut good_suffix = vec![0; pattern.len() + 1];
-    let mut last_prefix_position = pattern.len();
+impl<T: Symbol> BoyerMoore<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        let m = pattern.len();
+        let mut last = T::Table::new(0);
+        for (i, symbol) in pattern.iter().enumerate() {
+            last.set(symbol, i + 1);
+        }
 
-    // Case 2: suffix matches prefix
-    for i in (0..pattern.len()).rev() {
-        if is_prefix(pattern, i + 1) {
-            last_prefix_position = i + 1;
+        // border[i]: start of the widest border of pattern[i..]
+        let mut good_suffix = vec![0; m + 1];
+        let mut border = vec![0; m + 1];
+        let (mut i, mut j) = (m, m + 1);
+        border[i] = j;
+        // case 1: the matched suffix occurs again, preceded by a different symbol
+        while i > 0 {
+            while j <= m && pattern[i - 1] != pattern[j - 1] {
+                if good_suffix[j] == 0 {
+                    good_suffix[j] = j - i;
+                }
+                j = border[j];
+            }
+            i -= 1;
+            j -= 1;
+            border[i] = j;
+        }
+        // case 2: only a prefix of the pattern lines up with the end of the matched suffix
+        j = border[0];
+        for (i, shift) in good_suffix.iter_mut().enumerate() {
+            if *shift == 0 {
+                *shift = j;
+            }
+            if i == j {
+                j = border[j];
+            }
+        }
+        BoyerMoore {
+            pattern: pattern.to_vec(),
+            last,
+            good_suffix,
         }
-        good_suffix[i] = last_prefix_position - i + pattern.len() - 1;
     }
+}
 
-    // Case 1: suffix matches somewhere else
-    for i in 0..pattern.len() - 1 {
-        let len = suffix_length(pattern, i);
-        if pattern[i - len] != pattern[pattern.len() - 1 - len] {
-            good_suffix[pattern.len() - 1 - len] = pattern.len() - 1 - i + len;
-        }
+impl<T: Symbol> Matcher<T> for BoyerMoore<T> {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
     }
 
-    let mut i = pattern.len() - 1;
-    unsafe {
-        while i < text.len() {
-            let mut j = pattern.len() - 1;
-            while j > 0 && *pattern.get_unchecked(j) == *text.get_unchecked(i) {
-                i -= 1;
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        let m = self.pattern.len();
+        let mut i = start;
+        while i + m <= text.len() {
+            // symbols of the pattern still unmatched, compared from the right
+            let mut j = m;
+            while j > 0 && self.pattern[j - 1] == text[i + j - 1] {
                 j -= 1;
             }
-            if j == 0 && *pattern.get_unchecked(0) == *text.get_unchecked(i) {
+            if j == 0 {
                 return Some(i);
-            } else {
-                let bad_char_shift = bad_char[*text.get_unchecked(i) as usize].saturating_sub(pattern.len() - 1 - j);
-                let good_suffix_shift = *good_suffix.get_unchecked(j);
-                i += cmp::max(bad_char_shift, good_suffix_shift);
             }
+            let bad_char = j.saturating_sub(self.last.get(&text[i + j - 1]));
+            i += cmp::max(bad_char, self.good_suffix[j]);
         }
+        None
     }
-    None
-}
 
-fn is_prefix(pattern: &[u8], p: usize) -> bool {
-    for i in p..pattern.len() {
-        if pattern[i] != pattern[i - p] {
-            return false;
-        }
+    fn search_after(&self, text: &[T], previous: usize) -> Option<usize> {
+        self.find_at(text, previous + self.good_suffix[0])
     }
-    true
 }
 
-fn suffix_length(pattern: &[u8], p: usize) -> usize {
-    let mut len = 0;
-    let mut i = p;
-    let mut j = pattern.len() - 1;
-    while i > 0 && pattern[i - 1] == pattern[j] {
-        len += 1;
-        i -= 1;
-        j -= 1;
-    }
-    len
+fn brute_force_search(text: &str, pattern: &str) -> Option<usize> {
+    BruteForce::new(pattern.as_bytes()).find(text.as_bytes())
+}
+
+fn prefix_shift_search(text: &str, pattern: &str) -> Option<usize> {
+    PrefixShift::new(pattern.as_bytes()).find(text.as_bytes())
+}
+
+fn suffix_shift_search(text: &str, pattern: &str) -> Option<usize> {
+    SuffixShift::new(pattern.as_bytes()).find(text.as_bytes())
+}
+
+use std::cmp;
+
+fn boyer_moore_search(text: &str, pattern: &str) -> Option<usize> {
+    BoyerMoore::new(pattern.as_bytes()).find(text.as_bytes())
 }
 
 fn main() {
@@ -152,6 +246,89 @@ fn main() {
             println!();
         }
     }
+
+    // Precompiled matchers: each pattern is preprocessed once and searched for in every text
+    let texts: Vec<&[u8]> = test_cases.iter().map(|(text, _)| text.as_bytes()).collect();
+    println!("\n| {:^7} | {:^7} | {:^11} | {:^26} |", "Pattern", "Matches", "Overlapping", "First matches in case 5");
+    println!("|{:-^9}|{:-^9}|{:-^13}|{:-^28}|", "", "", "", "");
+    for pattern in ["aa", "or", "ut", "lorem", "Lorem"] {
+        let matcher = BoyerMoore::new(pattern.as_bytes());
+        let matches: usize = texts.iter().map(|text| matcher.count(text)).sum();
+        let overlapping: usize = texts.iter().map(|text| matcher.count_overlapping(text)).sum();
+        let first: Vec<usize> = matcher.find_iter(texts[4]).take(4).collect();
+        println!("| {:<7} | {:>7} | {:>11} | {:<26} |", pattern, matches, overlapping, format!("{:?}", first));
+    }
+
+    let pattern = "ut";
+    let iterations = 100_000;
+    println!("\nCounting {:?} in all {} cases, {} iterations:", pattern, texts.len(), iterations);
+    println!("| {:^17} |  Brute Force | Prefix Shift | Suffix Shift |  Boyer-Moore |", "");
+    println!("|{:-^19}|--------------|--------------|--------------|--------------|", "");
+    let rebuilt = [
+        time_counting(BruteForce::new, pattern, &texts, iterations, false),
+        time_counting(PrefixShift::new, pattern, &texts, iterations, false),
+        time_counting(SuffixShift::new, pattern, &texts, iterations, false),
+        time_counting(BoyerMoore::new, pattern, &texts, iterations, false),
+    ];
+    let reused = [
+        time_counting(BruteForce::new, pattern, &texts, iterations, true),
+        time_counting(PrefixShift::new, pattern, &texts, iterations, true),
+        time_counting(SuffixShift::new, pattern, &texts, iterations, true),
+        time_counting(BoyerMoore::new, pattern, &texts, iterations, true),
+    ];
+    for (label, durations) in [("Built per text", rebuilt), ("Built once", reused)] {
+        print!("| {:<17} | ", label);
+        for duration in durations {
+            print!("{:>12} | ", format!("{:?}", duration));
+        }
+        println!();
+    }
+
+    // Binary files: the file given on the command line, read whole as print_bytes does, or else this program
+    let path = std::env::args().nth(1).map(std::path::PathBuf::from).unwrap_or_else(|| std::env::current_exe().expect("path of the running program"));
+    match std::fs::read(&path) {
+        Ok(bytes) => {
+            println!("\nSearching {} ({} bytes):", path.display(), bytes.len());
+            let patterns: [(&str, &[u8]); 3] = [("ELF magic", b"\x7fELF"), ("8 zero bytes", &[0; 8]), ("\"Boyer-Moore\"", b"Boyer-Moore")];
+            for (name, pattern) in patterns {
+                let matcher = BoyerMoore::new(pattern);
+                let start = Instant::now();
+                let count = matcher.count(&bytes);
+                let elapsed = start.elapsed();
+                let first: Vec<String> = matcher.find_iter(&bytes).take(3).map(|offset| format!("0x{:X}", offset)).collect();
+                println!("{:>14}: {:>7} match(es) in {:>10}, first at [{}]", name, count, format!("{:?}", elapsed), first.join(", "));
+            }
+        }
+        Err(error) => eprintln!("Can't read {}: {}", path.display(), error),
+    }
+
+    // Token sequences: the symbols are whole words of a log, with a hashed bad-character table
+    let lines = ["GET /index.html 200", "GET /login 200", "POST /login 401", "POST /login 200", "GET /about.html 304"];
+    let log: String = (0..100_000).map(|i| lines[i * 7 % lines.len()]).collect::<Vec<_>>().join(" ");
+    let tokens: Vec<&str> = log.split_whitespace().collect();
+    let failed_login = ["POST", "/login", "401"];
+    let start = Instant::now();
+    let token_count = BoyerMoore::new(&failed_login).count(&tokens);
+    let token_time = start.elapsed();
+    let start = Instant::now();
+    let byte_count = BoyerMoore::new(failed_login.join(" ").as_bytes()).count(log.as_bytes());
+    let byte_time = start.elapsed();
+    println!("\n{:?} in {} log tokens: {} match(es) in {:?}; as bytes: {} match(es) in {:?}", failed_login, tokens.len(), token_count, token_time, byte_count, byte_time);
+}
+
+/// Average time to count `pattern` in all `texts`, building the matcher once per text or
+/// once for all iterations.
+fn time_counting<M: Matcher<u8>>(build: fn(&[u8]) -> M, pattern: &str, texts: &[&[u8]], iterations: u32, reuse: bool) -> std::time::Duration {
+    let mut total = 0;
+    let start = Instant::now();
+    let matcher = build(pattern.as_bytes());
+    for _ in 0..iterations {
+        for text in texts {
+            total += if reuse { matcher.count(text) } else { build(pattern.as_bytes()).count(text) };
+        }
+    }
+    assert!(total > 0);
+    start.elapsed() / iterations
 }
 
 fn test_algorithms(text: &str, pattern: &str, iterations: u32) {
@@ -172,3 +349,80 @@ fn test_algorithms(text: &str, pattern: &str, iterations: u32) {
         print!("       {:?} | ", duration);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::check_matcher;
+    use rand::Rng;
+
+    #[test]
+    fn matchers_find_every_match() {
+        check_matcher(BruteForce::new);
+        check_matcher(PrefixShift::new);
+        check_matcher(SuffixShift::new);
+        check_matcher(BoyerMoore::new);
+    }
+
+    #[test]
+    fn counts_overlapping_matches() {
+        let matcher = BoyerMoore::new(b"aa");
+        assert_eq!(matcher.count(b"aaaaa"), 2);
+        assert_eq!(matcher.count_overlapping(b"aaaaa"), 4);
+        let matcher = PrefixShift::new(b"abab");
+        assert_eq!(
+            matcher
+                .find_overlapping_iter(b"abababab")
+                .collect::<Vec<_>>(),
+            vec![0, 2, 4]
+        );
+        assert_eq!(
+            matcher.find_iter(b"abababab").collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+        assert_eq!(boyer_moore_search("Hello, World!", "World"), Some(7));
+    }
+
+    #[test]
+    fn hashed_and_dense_tables_agree() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let text: Vec<u8> = (0..200).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            let pattern: Vec<u8> = (0..rng.gen_range(1..6))
+                .map(|_| rng.gen_range(b'a'..b'd'))
+                .collect();
+            let wide_text: Vec<u32> = text.iter().map(|&byte| byte as u32 * 1_000).collect();
+            let wide_pattern: Vec<u32> = pattern.iter().map(|&byte| byte as u32 * 1_000).collect();
+            let want: Vec<usize> = BoyerMoore::new(&pattern)
+                .find_overlapping_iter(&text)
+                .collect();
+            let wide: Vec<usize> = BoyerMoore::new(&wide_pattern)
+                .find_overlapping_iter(&wide_text)
+                .collect();
+            assert_eq!(wide, want);
+            let wide: Vec<usize> = SuffixShift::new(&wide_pattern)
+                .find_overlapping_iter(&wide_text)
+                .collect();
+            assert_eq!(wide, want);
+        }
+    }
+
+    #[test]
+    fn searches_token_sequences() {
+        let log = "GET /index.html 200 GET /login 401 POST /login 401 POST /login 200";
+        let tokens: Vec<&str> = log.split_whitespace().collect();
+        let failed_login = ["/login", "401"];
+        assert_eq!(
+            BoyerMoore::new(&failed_login)
+                .find_iter(&tokens)
+                .collect::<Vec<_>>(),
+            vec![4, 7]
+        );
+        assert_eq!(SuffixShift::new(&failed_login).count(&tokens), 2);
+        assert_eq!(PrefixShift::new(&["POST", "/login"]).count(&tokens), 2);
+        assert_eq!(BruteForce::new(&["GET"]).find_at(&tokens, 1), Some(3));
+
+        let accents: Vec<char> = "ééèé".chars().collect();
+        assert_eq!(BoyerMoore::new(&['é', 'è']).find(&accents), Some(1));
+    }
+}
@@ -1,3 +1,8 @@
+use std::fmt::Debug;
+
+mod tree_render;
+use tree_render::{TreeShape, TreeView};
+
 type NodePointer<T> = Option<Box<Node<T>>>;
 
 pub struct Node<T: Copy + PartialOrd> {
@@ -205,6 +210,28 @@ impl<T: Copy + PartialOrd> Tree<T> {
     
 }
 
+impl<T: Copy + PartialOrd + Debug> TreeView for Node<T> {
+    fn label(&self) -> String {
+        format!("{:?}", self.value)
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<T: Copy + PartialOrd + Debug> TreeShape for Tree<T> {
+    type Node = Node<T>;
+
+    fn root_node(&self) -> Option<&Node<T>> {
+        self.root.as_deref()
+    }
+}
+
 pub struct TreeIntoIterator<'a, T: Copy + PartialOrd> {
     stack: Vec<(bool, &'a Node<T>)>
 }
@@ -300,4 +327,6 @@ fn main() {
     println!("Search (Ascending Order): {:?}", ascending_search_duration);
     println!("Delete (Random Order): {:?}", random_delete_duration);
     println!("Delete (Ascending Order): {:?}", ascending_delete_duration);
+    println!("Shape (Random Order):\n{}", random_tree.stats());
+    println!("Shape (Ascending Order):\n{}", ascending_tree.stats());
 }
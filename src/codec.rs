@@ -0,0 +1,109 @@
+// Little-endian byte encoding shared by the serialised tables: the perfect hash map of the
+// hash and probabilistic binaries and the trie binary's dictionaries.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    BadMagic,
+    TrailingBytes,
+    Invalid(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "input ends in the middle of a value"),
+            DecodeError::BadMagic => write!(f, "input doesn't start with the expected format tag"),
+            DecodeError::TrailingBytes => write!(f, "input continues past the encoded data"),
+            DecodeError::Invalid(what) => write!(f, "invalid input: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Splits `count` bytes off the front of `bytes`.
+pub fn take<'a>(bytes: &mut &'a [u8], count: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < count {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (head, rest) = bytes.split_at(count);
+    *bytes = rest;
+    Ok(head)
+}
+
+/// Little-endian binary encoding for the keys and values of a serialised table.
+pub trait Encode: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+    /// Reads a value from the front of `bytes` and advances past it.
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+macro_rules! encode_int {
+    ($($int:ty),*) => {$(
+        impl Encode for $int {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+                let bytes = take(bytes, std::mem::size_of::<$int>())?;
+                Ok(<$int>::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        }
+    )*};
+}
+
+encode_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Encode for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        usize::try_from(u64::decode(bytes)?).map_err(|_| DecodeError::Invalid("usize overflow"))
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match u8::decode(bytes)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Invalid("bool out of range")),
+        }
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = usize::decode(bytes)?;
+        String::from_utf8(take(bytes, len)?.to_vec()).map_err(|_| DecodeError::Invalid("UTF-8"))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = usize::decode(bytes)?;
+        (0..len).map(|_| T::decode(bytes)).collect()
+    }
+}
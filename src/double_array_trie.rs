@@ -0,0 +1,364 @@
+// Double-array trie: a static byte trie packed into two integer arrays, built once from a
+// sorted key list and loaded from bytes without rebuilding. Used by the trie binary.
+
+use crate::codec::{take, DecodeError, Encode};
+
+const ROOT: u32 = 0;
+const FREE: u32 = u32::MAX;
+const MAGIC: &[u8; 4] = b"DAT1";
+
+/// Transition code of a byte. Code 0 is the end-of-key marker, so a key can be a prefix
+/// of another key.
+fn code(byte: u8) -> u32 {
+    byte as u32 + 1
+}
+
+/// Set of byte strings, each mapped to its position in the sorted key list it was built from.
+///
+/// State `s` has the transition on code `c` to `t = base[s] + c` when `check[t] == s`.
+/// A key ending at `s` has a leaf at `base[s] + 0`, whose `base` holds the key's id.
+pub struct DoubleArrayTrie {
+    base: Vec<u32>,
+    check: Vec<u32>,
+    len: usize,
+}
+
+impl DoubleArrayTrie {
+    /// Trie of `keys`, which must be sorted and free of duplicates. Nodes are placed depth
+    /// first: each gets the lowest `base` whose slots for all its children are free.
+    pub fn new<K: AsRef<[u8]>>(keys: &[K]) -> Self {
+        assert!(
+            keys.windows(2)
+                .all(|pair| pair[0].as_ref() < pair[1].as_ref()),
+            "keys must be sorted and unique"
+        );
+        let mut trie = DoubleArrayTrie {
+            base: vec![0],
+            check: vec![ROOT],
+            len: keys.len(),
+        };
+        // lowest slot that may be free, to start the search for a base from
+        let mut first_free = 1;
+        // (state, keys below it, depth)
+        let mut stack = vec![(ROOT, 0, keys.len(), 0)];
+        let mut codes = Vec::new();
+        while let Some((state, start, end, depth)) = stack.pop() {
+            if start == end {
+                continue;
+            }
+            // children of the state, one per distinct code at `depth`, with their key ranges
+            codes.clear();
+            for (index, key) in keys.iter().enumerate().take(end).skip(start) {
+                let key = key.as_ref();
+                let code = key.get(depth).map_or(0, |&byte| code(byte));
+                match codes.last_mut() {
+                    Some((last, _, range_end)) if *last == code => *range_end = index + 1,
+                    _ => codes.push((code, index, index + 1)),
+                }
+            }
+
+            let base = trie.find_base(&codes, &mut first_free);
+            trie.base[state as usize] = base;
+            for &(code, _, _) in &codes {
+                trie.check[(base + code) as usize] = state;
+            }
+            for &(code, range_start, range_end) in codes.iter().rev() {
+                let child = base + code;
+                if code == 0 {
+                    // only one key can end here, as keys are unique
+                    trie.base[child as usize] = range_start as u32;
+                } else {
+                    stack.push((child, range_start, range_end, depth + 1));
+                }
+            }
+        }
+        trie
+    }
+
+    /// Lowest base above 0 at which every code in `codes` lands on a free slot,
+    /// growing the arrays to fit.
+    fn find_base(&mut self, codes: &[(u32, usize, usize)], first_free: &mut usize) -> u32 {
+        while *first_free < self.check.len() && self.check[*first_free] != FREE {
+            *first_free += 1;
+        }
+        let lowest = codes[0].0;
+        let mut slot = *first_free;
+        loop {
+            // bases start at 1, so no transition leads back to the root
+            if slot > lowest as usize {
+                let base = slot as u32 - lowest;
+                let fits = codes.iter().all(|&(code, _, _)| {
+                    self.check
+                        .get((base + code) as usize)
+                        .is_none_or(|&owner| owner == FREE)
+                });
+                if fits {
+                    let needed = (base + codes[codes.len() - 1].0) as usize + 1;
+                    if needed > self.check.len() {
+                        self.base.resize(needed, 0);
+                        self.check.resize(needed, FREE);
+                    }
+                    return base;
+                }
+            }
+            slot += 1;
+            while slot < self.check.len() && self.check[slot] != FREE {
+                slot += 1;
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Length of the two arrays, leaves and unused slots included.
+    #[allow(dead_code)]
+    pub fn array_len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn child(&self, state: u32, code: u32) -> Option<u32> {
+        let next = self.base[state as usize].checked_add(code)?;
+        (self.check.get(next as usize) == Some(&state) && next != ROOT).then_some(next)
+    }
+
+    /// Id of the key ending at `state`, if one does.
+    fn id(&self, state: u32) -> Option<u32> {
+        let leaf = self.child(state, 0)?;
+        Some(self.base[leaf as usize])
+    }
+
+    fn find(&self, key: &[u8]) -> Option<u32> {
+        key.iter()
+            .try_fold(ROOT, |state, &byte| self.child(state, code(byte)))
+    }
+
+    /// Position of `key` in the key list the trie was built from.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<u32> {
+        self.id(self.find(key.as_ref())?)
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Keys starting with `prefix` and their ids, in key order.
+    pub fn prefix_search(&self, prefix: impl AsRef<[u8]>) -> Vec<(Vec<u8>, u32)> {
+        let prefix = prefix.as_ref();
+        let mut found = Vec::new();
+        let Some(state) = self.find(prefix) else {
+            return found;
+        };
+        let mut stack = vec![(prefix.to_vec(), state)];
+        while let Some((key, state)) = stack.pop() {
+            if let Some(id) = self.id(state) {
+                found.push((key.clone(), id));
+            }
+            for byte in (0..=u8::MAX).rev() {
+                if let Some(child) = self.child(state, code(byte)) {
+                    let mut child_key = key.clone();
+                    child_key.push(byte);
+                    stack.push((child_key, child));
+                }
+            }
+        }
+        found
+    }
+
+    /// Keys that are prefixes of `text`, shortest first, as (length, id) pairs.
+    pub fn common_prefix_search(&self, text: impl AsRef<[u8]>) -> Vec<(usize, u32)> {
+        let mut found: Vec<_> = self.id(ROOT).map(|id| (0, id)).into_iter().collect();
+        let mut state = ROOT;
+        for (depth, &byte) in text.as_ref().iter().enumerate() {
+            match self.child(state, code(byte)) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if let Some(id) = self.id(state) {
+                found.push((depth + 1, id));
+            }
+        }
+        found
+    }
+
+    /// The two arrays as is, so loading is a copy.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        self.len.encode(&mut bytes);
+        self.base.encode(&mut bytes);
+        self.check.encode(&mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        if take(&mut bytes, MAGIC.len())? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let len = usize::decode(&mut bytes)?;
+        let base = Vec::<u32>::decode(&mut bytes)?;
+        let check = Vec::<u32>::decode(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        if base.is_empty() || base.len() != check.len() {
+            return Err(DecodeError::Invalid("array lengths"));
+        }
+        if check
+            .iter()
+            .any(|&owner| owner != FREE && owner as usize >= check.len())
+        {
+            return Err(DecodeError::Invalid("check out of range"));
+        }
+        let trie = DoubleArrayTrie { base, check, len };
+
+        // `check` gives every slot a single parent and no transition leads to the root, so
+        // the states reachable from the root form a tree; a used slot the walk misses belongs
+        // to a cycle or hangs off a leaf
+        let mut reached = 1;
+        let mut leaves = 0;
+        let mut stack = vec![ROOT];
+        while let Some(state) = stack.pop() {
+            if trie.child(state, 0).is_some() {
+                reached += 1;
+                leaves += 1;
+            }
+            for code in 1..=u8::MAX as u32 + 1 {
+                if let Some(child) = trie.child(state, code) {
+                    reached += 1;
+                    stack.push(child);
+                }
+            }
+        }
+        if reached != trie.check.iter().filter(|&&owner| owner != FREE).count() {
+            return Err(DecodeError::Invalid("transitions don't form a tree"));
+        }
+        if leaves != trie.len {
+            return Err(DecodeError::Invalid("entry count"));
+        }
+        Ok(trie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn finds_every_key_and_nothing_else() {
+        let mut rng = rand::thread_rng();
+        let keys: BTreeSet<Vec<u8>> = (0..3_000)
+            .map(|_| {
+                let len = rng.gen_range(0..7);
+                (0..len).map(|_| rng.gen_range(b'a'..b'g')).collect()
+            })
+            .collect();
+        let keys: Vec<Vec<u8>> = keys.into_iter().collect();
+        let trie = DoubleArrayTrie::new(&keys);
+        assert_eq!(trie.len(), keys.len());
+        assert!(DoubleArrayTrie::from_bytes(&trie.to_bytes()).is_ok());
+        for (id, key) in keys.iter().enumerate() {
+            assert_eq!(trie.get(key), Some(id as u32));
+            let mut longer = key.clone();
+            longer.push(b'z');
+            assert_eq!(trie.get(&longer), None);
+        }
+
+        let all: Vec<_> = trie
+            .prefix_search([])
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(all, keys);
+        let under: Vec<_> = trie
+            .prefix_search(b"ab")
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        let want: Vec<_> = (0..keys.len() as u32)
+            .filter(|&id| keys[id as usize].starts_with(b"ab"))
+            .collect();
+        assert_eq!(under, want);
+
+        let text = b"abcabcf";
+        let want: Vec<_> = (0..=text.len())
+            .filter_map(|len| Some((len, trie.get(&text[..len])?)))
+            .collect();
+        assert_eq!(trie.common_prefix_search(text), want);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let keys = ["", "a", "ab", "abc", "b", "\u{e9}t\u{e9}"];
+        let trie = DoubleArrayTrie::new(&keys);
+        assert_eq!(trie.get(""), Some(0));
+        assert_eq!(trie.get("été"), Some(5));
+        let bytes = trie.to_bytes();
+        let loaded = DoubleArrayTrie::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.array_len(), trie.array_len());
+        for (id, key) in keys.iter().enumerate() {
+            assert_eq!(loaded.get(key), Some(id as u32));
+        }
+        assert_eq!(
+            loaded.common_prefix_search("abd"),
+            vec![(0, 0), (1, 1), (2, 2)]
+        );
+        assert_eq!(
+            DoubleArrayTrie::from_bytes(&bytes[..bytes.len() - 2]).err(),
+            Some(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn rejects_transitions_that_are_not_a_tree() {
+        let trie = DoubleArrayTrie::new(&["a", "ab", "b"]);
+        let (p, q) = (trie.array_len() as u32, trie.array_len() as u32 + 1);
+        let mut cyclic = DoubleArrayTrie {
+            base: trie.base.clone(),
+            check: trie.check.clone(),
+            len: trie.len,
+        };
+        // two extra states, each the other's child on code 1
+        cyclic.base.extend([q - 1, p - 1]);
+        cyclic.check.extend([q, p]);
+        assert_eq!(
+            DoubleArrayTrie::from_bytes(&cyclic.to_bytes()).err(),
+            Some(DecodeError::Invalid("transitions don't form a tree"))
+        );
+        assert!(DoubleArrayTrie::from_bytes(&trie.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_entry_count() {
+        let trie = DoubleArrayTrie::new(&["a", "ab", "b"]);
+        for len in [0, 2, 4] {
+            let wrong = DoubleArrayTrie {
+                base: trie.base.clone(),
+                check: trie.check.clone(),
+                len,
+            };
+            assert_eq!(
+                DoubleArrayTrie::from_bytes(&wrong.to_bytes()).err(),
+                Some(DecodeError::Invalid("entry count"))
+            );
+        }
+        let empty = DoubleArrayTrie::new::<&str>(&[]);
+        let loaded = DoubleArrayTrie::from_bytes(&empty.to_bytes()).unwrap();
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "sorted")]
+    fn rejects_unsorted_keys() {
+        DoubleArrayTrie::new(&["b", "a"]);
+    }
+}
@@ -1,138 +1,23 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::borrow::Borrow;
-
-const INITIAL_CAPACITY: usize = 16;
-const LOAD_FACTOR: f64 = 0.75;
-
-#[derive(Debug, Clone, PartialEq)]
-enum Entry<K, V> {
-    Occupied(K, V),
-    Deleted,
-    Empty,
-}
-
-pub struct HashTable<K, V> {
-    table: Vec<Entry<K, V>>,
-    size: usize,
-}
-
-impl<K, V> HashTable<K, V>
-where
-    K: Hash + Eq + Clone,
-    V: Clone,
-{
-    pub fn new() -> Self {
-        let table = vec![Entry::Empty; INITIAL_CAPACITY];
-        HashTable { table, size: 0 }
-    }
-
-    fn hash<Q>(&self, key: &Q) -> u64
-    where
-        K: Borrow<Q>,
-        Q: Hash + ?Sized,
-    {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        hasher.finish()
-    }
-
-    fn find_slot<Q>(&self, key: &Q) -> usize
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash(key);
-        let mut index = hash as usize % self.table.len();
-        let mut i = 0;
-
-        loop {
-            match &self.table[index] {
-                Entry::Empty => return index,
-                Entry::Occupied(ref k, _) if k.borrow() == key => return index,
-                Entry::Deleted => return index,
-                _ => {
-                    i += 1;
-                    index = (hash as usize + i) % self.table.len();
-                }
-            }
-        }
-    }
-
-    fn resize(&mut self) {
-        let new_capacity = self.table.len() * 2;
-        let mut new_table = vec![Entry::Empty; new_capacity];
-
-        let old_table = std::mem::replace(&mut self.table, Vec::new());
-        
-        for entry in old_table {
-            if let Entry::Occupied(key, value) = entry {
-                let mut index = self.hash(&key) as usize % new_capacity;
-                let mut _i = 0;
-
-                loop {
-                    if matches!(new_table[index], Entry::Empty) {
-                        new_table[index] = Entry::Occupied(key, value);
-                        break;
-                    }
-                    _i += 1;
-                    index = (index + 1) % new_capacity;
-                }
-            }
-        }
-
-        self.table = new_table;
-    }
-
-    pub fn insert(&mut self, key: K, value: V) {
-        if (self.size + 1) as f64 / self.table.len() as f64 >= LOAD_FACTOR {
-            self.resize();
-        }
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+mod hash_table;
+use hash_table::cache::{LfuCache, LruCache};
+use hash_table::hashers::{FibonacciBuildHasher, FnvBuildHasher, Mix64BuildHasher};
+use hash_table::multimap::HashMultiMap;
+use hash_table::open_addressing::{
+    DoubleHashing, HashTable, LinearProbing, ProbeStats, ProbeStrategy, QuadraticProbing, RobinHood,
+};
+use hash_table::set::HashSet;
+use hash_table::sharded::ShardedHashTable;
 
-        let index = self.find_slot(&key);
-        match self.table[index] {
-            Entry::Occupied(ref mut k, ref mut v) if *k == key => *v = value,
-            Entry::Occupied(_, _) | Entry::Empty | Entry::Deleted => {
-                self.table[index] = Entry::Occupied(key, value);
-                self.size += 1;
-            }
-        }
-    }
-
-    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let index = self.find_slot(key);
-        if let Entry::Occupied(k, _) = &self.table[index] {
-            if k.borrow() == key {
-                if let Entry::Occupied(_, value) = std::mem::replace(&mut self.table[index], Entry::Deleted) {
-                    self.size -= 1;
-                    return Some(value);
-                }
-            }
-        }
-        None
-    }
-
-    pub fn get<Q>(&self, key: &Q) -> Option<&V>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let index = self.find_slot(key);
-        if let Entry::Occupied(ref k, ref v) = self.table[index] {
-            if k.borrow() == key {
-                return Some(v);
-            }
-        }
-        None
-    }
-}
 fn main() {
     let mut table = HashTable::new();
-    
+
     // Insertion
     table.insert("key1".to_string(), "value1".to_string());
     table.insert("key2".to_string(), "value2".to_string());
@@ -163,4 +48,167 @@ fn main() {
     // Check insertion with value update
     table.insert("key1".to_string(), "value1_updated".to_string());
     println!("{:?}", table.get(&"key1".to_string())); // Some("value1_updated")
-}
\ No newline at end of file
+
+    // Entry API
+    let mut counts: HashTable<&str, usize> = HashTable::new();
+    for word in "the quick brown fox jumps over the lazy dog the end".split(' ') {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts.retain(|_, &mut count| count > 1);
+    println!("{:?}", counts); // {"the": 3}
+
+    // Sets and multimaps
+    let evens: HashSet<u32> = (0..10).step_by(2).collect();
+    let small: HashSet<u32> = (0..5).collect();
+    let mut common: Vec<_> = evens.intersection(&small).collect();
+    common.sort();
+    println!("{:?}", common); // [0, 2, 4]
+
+    let mut authors = HashMultiMap::new();
+    authors.insert("Knuth", "TAOCP vol. 1");
+    authors.insert("Sedgewick", "Algorithms");
+    authors.insert("Knuth", "TAOCP vol. 3");
+    println!("{:?}", authors.get_all("Knuth")); // ["TAOCP vol. 1", "TAOCP vol. 3"]
+
+    // Sharded table shared between threads
+    let letters = ShardedHashTable::new();
+    thread::scope(|scope| {
+        for word in ["hash", "table", "shard"] {
+            let letters = &letters;
+            scope.spawn(move || {
+                for letter in word.chars() {
+                    letters.upsert(letter, 0, |count| *count += 1);
+                }
+            });
+        }
+    });
+    println!("{:?}", letters.get(&'h')); // Some(3)
+
+    println!();
+    compare_strategies("sequential integers", &(0..12_000).collect::<Vec<u64>>());
+    let mut rng = rand::thread_rng();
+    compare_strategies(
+        "random integers",
+        &(0..12_000).map(|_| rng.gen::<u64>()).collect::<Vec<_>>(),
+    );
+    compare_strategies(
+        "strided integers",
+        &(0..12_000).map(|i| i * 1024).collect::<Vec<u64>>(),
+    );
+
+    println!();
+    let keys: Vec<u64> = (0..200_000).map(|_| rng.gen()).collect();
+    compare_hashers("random integers", &keys);
+    let keys: Vec<u64> = (0..200_000).map(|i| i << 20).collect();
+    compare_hashers("integers differing in high bits", &keys);
+    let keys: Vec<String> = (0..200_000).map(|i| format!("key{}", i)).collect();
+    compare_hashers("short strings", &keys);
+
+    println!();
+    compare_caches();
+
+    println!();
+    println!("longest single insert among 1,000,000:");
+    println!("  stop-the-world resize: {:?}", longest_insert(None));
+    println!("  incremental resize:    {:?}", longest_insert(Some(8)));
+}
+
+/// A scan over cold keys pushes some hot keys out of an LRU cache, but none out of an LFU one.
+fn compare_caches() {
+    let mut rng = rand::thread_rng();
+    let accesses: Vec<u32> = (0..100_000)
+        .map(|i| {
+            if rng.gen_bool(0.7) {
+                rng.gen_range(0..100)
+            } else {
+                1_000 + i % 5_000
+            }
+        })
+        .collect();
+
+    let mut lru = LruCache::new(200);
+    let mut lfu = LfuCache::new(200);
+    for &key in &accesses {
+        if lru.get(&key).is_none() {
+            lru.put(key, ());
+        }
+        if lfu.get(&key).is_none() {
+            lfu.put(key, ());
+        }
+    }
+    println!(
+        "200-entry caches, 70% of accesses to 100 hot keys, the rest scanning 5,000 cold ones:"
+    );
+    println!("  LRU: {}", lru.stats());
+    println!("  LFU: {}", lfu.stats());
+}
+
+fn longest_insert(migration_step: Option<usize>) -> Duration {
+    let mut table = HashTable::new();
+    table.set_incremental_resize(migration_step);
+    (0..1_000_000u64)
+        .map(|key| {
+            let start = Instant::now();
+            table.insert(key, key);
+            start.elapsed()
+        })
+        .max()
+        .unwrap()
+}
+
+fn time_hasher<K: Hash + Eq + Clone, S: BuildHasher>(keys: &[K], hasher: S) -> String {
+    let start = Instant::now();
+    let mut table = HashTable::with_capacity_and_hasher(keys.len(), hasher);
+    for key in keys {
+        table.insert(key.clone(), ());
+    }
+    for key in keys {
+        assert!(table.get(key).is_some());
+    }
+    let elapsed = start.elapsed();
+    let stats = table.probe_stats();
+    format!(
+        "{:>10.2?}, mean probe length: {:.3}, max: {}",
+        elapsed, stats.mean, stats.max
+    )
+}
+
+fn compare_hashers<K: Hash + Eq + Clone>(name: &str, keys: &[K]) {
+    println!("{} ({} inserts + lookups):", name, keys.len());
+    println!(
+        "  SipHash (RandomState): {}",
+        time_hasher(keys, RandomState::new())
+    );
+    println!(
+        "  FNV-1a:                {}",
+        time_hasher(keys, FnvBuildHasher::default())
+    );
+    println!(
+        "  Fibonacci:             {}",
+        time_hasher(keys, FibonacciBuildHasher::default())
+    );
+    println!(
+        "  Mix64 (seeded):        {}",
+        time_hasher(keys, Mix64BuildHasher::random())
+    );
+}
+
+fn probe_stats_for<P: ProbeStrategy>(strategy: P, keys: &[u64]) -> ProbeStats {
+    let mut table = HashTable::with_strategy_and_hasher(strategy, Mix64BuildHasher::random());
+    for &key in keys {
+        table.insert(key, ());
+    }
+    // delete a third of the keys so tombstones and backward shifting show up in the numbers
+    for key in keys.iter().step_by(3) {
+        table.remove(key);
+    }
+    table.probe_stats()
+}
+
+fn compare_strategies(name: &str, keys: &[u64]) {
+    println!("{} ({} keys, a third removed):", name, keys.len());
+    println!("  linear:      {}", probe_stats_for(LinearProbing, keys));
+    println!("  quadratic:   {}", probe_stats_for(QuadraticProbing, keys));
+    println!("  double hash: {}", probe_stats_for(DoubleHashing, keys));
+    println!("  robin hood:  {}", probe_stats_for(RobinHood, keys));
+}
@@ -0,0 +1,154 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// only the Map implementations are benchmarked, the rest of the module is unused here
+#[allow(dead_code)]
+mod hash_table;
+use hash_table::chained::ChainedHashTable;
+use hash_table::cuckoo::CuckooHashTable;
+use hash_table::hashers::Mix64BuildHasher;
+use hash_table::open_addressing::{
+    DoubleHashing, HashTable, LinearProbing, ProbeStrategy, QuadraticProbing, RobinHood,
+};
+use hash_table::Map;
+
+// every table ends up with this many slots (buckets for chaining) at every load factor
+const SLOTS: usize = 1 << 17;
+const LOAD_FACTORS: [f64; 8] = [0.25, 0.35, 0.45, 0.55, 0.65, 0.75, 0.85, 0.95];
+
+struct Measurement {
+    load: f64,
+    /// nanoseconds per operation
+    insert: f64,
+    hit: f64,
+    miss: f64,
+    remove: f64,
+    heap_bytes: usize,
+}
+
+fn per_op(start: Instant, ops: usize) -> f64 {
+    start.elapsed().as_nanos() as f64 / ops as f64
+}
+
+fn measure<M: Map<u64, u64>>(mut table: M, keys: &[u64], missing: &[u64]) -> Measurement {
+    let start = Instant::now();
+    for &key in keys {
+        table.insert(key, key);
+    }
+    let insert = per_op(start, keys.len());
+    assert_eq!(table.len(), keys.len());
+    let load = table.load_factor();
+    let heap_bytes = table.heap_size();
+
+    let mut lookups = keys.to_vec();
+    lookups.shuffle(&mut rand::thread_rng());
+    let start = Instant::now();
+    for key in &lookups {
+        black_box(table.get(key));
+    }
+    let hit = per_op(start, lookups.len());
+
+    let start = Instant::now();
+    for key in missing {
+        black_box(table.get(key));
+    }
+    let miss = per_op(start, missing.len());
+
+    let start = Instant::now();
+    for key in &lookups {
+        black_box(table.remove(key));
+    }
+    let remove = per_op(start, lookups.len());
+
+    Measurement {
+        load,
+        insert,
+        hit,
+        miss,
+        remove,
+        heap_bytes,
+    }
+}
+
+fn open_addressing<P: ProbeStrategy>(
+    strategy: P,
+    max_load: f64,
+) -> HashTable<u64, u64, P, Mix64BuildHasher> {
+    let mut table = HashTable::with_strategy_and_hasher(strategy, Mix64BuildHasher::random());
+    table.set_max_load_factor(max_load);
+    table
+}
+
+fn chained(max_load: f64) -> ChainedHashTable<u64, u64, Mix64BuildHasher> {
+    let mut table = ChainedHashTable::with_hasher(Mix64BuildHasher::random());
+    table.set_max_load_factor(max_load);
+    table
+}
+
+fn cuckoo(max_load: f64) -> CuckooHashTable<u64, u64, Mix64BuildHasher> {
+    let mut table = CuckooHashTable::with_hasher(Mix64BuildHasher::random());
+    table.set_max_load_factor(max_load);
+    table
+}
+
+fn print_table(name: &str, rows: &[(f64, Measurement)]) {
+    println!();
+    println!("{}", name);
+    println!(
+        "{:<8} | {:<8} | {:<10} | {:<10} | {:<10} | {:<10} | {:<10}",
+        "Target", "Load", "Insert ns", "Hit ns", "Miss ns", "Remove ns", "Heap KiB"
+    );
+    for (target, m) in rows {
+        println!(
+            "{:<8.2} | {:<8.3} | {:<10.1} | {:<10.1} | {:<10.1} | {:<10.1} | {:<10}",
+            target,
+            m.load,
+            m.insert,
+            m.hit,
+            m.miss,
+            m.remove,
+            m.heap_bytes / 1024
+        );
+    }
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    println!("{} slots, u64 keys and values, Mix64 hasher", SLOTS);
+
+    let mut results: Vec<(&str, Vec<(f64, Measurement)>)> = vec![
+        ("Linear probing", Vec::new()),
+        ("Quadratic probing", Vec::new()),
+        ("Double hashing", Vec::new()),
+        ("Robin Hood", Vec::new()),
+        ("Separate chaining", Vec::new()),
+        ("Cuckoo (2 tables, 4-way buckets)", Vec::new()),
+    ];
+
+    for &target in &LOAD_FACTORS {
+        let count = (target * SLOTS as f64) as usize;
+        let keys: Vec<u64> = (0..count).map(|_| rng.gen()).collect();
+        let missing: Vec<u64> = (0..count).map(|_| rng.gen()).collect();
+        // a little headroom so `count` entries fit in SLOTS slots without growing
+        let max_load = target + 0.01;
+
+        let measurements = [
+            measure(open_addressing(LinearProbing, max_load), &keys, &missing),
+            measure(open_addressing(QuadraticProbing, max_load), &keys, &missing),
+            measure(open_addressing(DoubleHashing, max_load), &keys, &missing),
+            measure(open_addressing(RobinHood, max_load), &keys, &missing),
+            measure(chained(max_load), &keys, &missing),
+            measure(cuckoo(max_load), &keys, &missing),
+        ];
+        for ((_, rows), measurement) in results.iter_mut().zip(measurements) {
+            rows.push((target, measurement));
+        }
+    }
+
+    for (name, rows) in &results {
+        print_table(name, rows);
+    }
+}
@@ -0,0 +1,865 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::Hash;
+
+use super::open_addressing::HashTable;
+
+const NIL: usize = usize::MAX;
+
+struct Node<T> {
+    item: T,
+    prev: usize,
+    next: usize,
+}
+
+/// Ends of a doubly-linked list threaded through `Nodes`, `NIL` when empty.
+#[derive(Debug, Clone, Copy)]
+struct List {
+    head: usize,
+    tail: usize,
+}
+
+impl List {
+    const EMPTY: List = List {
+        head: NIL,
+        tail: NIL,
+    };
+
+    fn is_empty(&self) -> bool {
+        self.head == NIL
+    }
+}
+
+/// Slab of list nodes linked by index rather than by pointer, so a node can be unlinked
+/// and relinked in O(1) given only its index. Freed slots are reused.
+struct Nodes<T> {
+    slots: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+}
+
+impl<T> Nodes<T> {
+    fn new() -> Self {
+        Nodes {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Stores `item` in an unlinked node and returns its index.
+    fn insert(&mut self, item: T) -> usize {
+        let node = Some(Node {
+            item,
+            prev: NIL,
+            next: NIL,
+        });
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = node;
+                index
+            }
+            None => {
+                self.slots.push(node);
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /// Frees the node at `index`, which must already be unlinked.
+    fn remove(&mut self, index: usize) -> T {
+        self.free.push(index);
+        self.slots[index].take().expect("node is live").item
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.slots[index].as_ref().expect("node is live")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.slots[index].as_mut().expect("node is live")
+    }
+
+    fn get(&self, index: usize) -> &T {
+        &self.node(index).item
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut T {
+        &mut self.node_mut(index).item
+    }
+
+    fn prev(&self, index: usize) -> usize {
+        self.node(index).prev
+    }
+
+    fn next(&self, index: usize) -> usize {
+        self.node(index).next
+    }
+
+    /// Links node `index` into `list` right after `after`, or at the front if `after` is `NIL`.
+    fn link_after(&mut self, list: &mut List, after: usize, index: usize) {
+        let next = if after == NIL {
+            list.head
+        } else {
+            self.next(after)
+        };
+        let node = self.node_mut(index);
+        node.prev = after;
+        node.next = next;
+
+        if after == NIL {
+            list.head = index;
+        } else {
+            self.node_mut(after).next = index;
+        }
+        if next == NIL {
+            list.tail = index;
+        } else {
+            self.node_mut(next).prev = index;
+        }
+    }
+
+    fn push_front(&mut self, list: &mut List, index: usize) {
+        self.link_after(list, NIL, index);
+    }
+
+    fn unlink(&mut self, list: &mut List, index: usize) {
+        let Node { prev, next, .. } = *self.node(index);
+        if prev == NIL {
+            list.head = next;
+        } else {
+            self.node_mut(prev).next = next;
+        }
+        if next == NIL {
+            list.tail = prev;
+        } else {
+            self.node_mut(next).prev = prev;
+        }
+    }
+
+    #[allow(dead_code)]
+    fn iter(&self, list: List) -> impl Iterator<Item = &T> {
+        std::iter::successors((list.head != NIL).then_some(list.head), |&index| {
+            let next = self.next(index);
+            (next != NIL).then_some(next)
+        })
+        .map(|index| self.get(index))
+    }
+}
+
+/// Lookups answered by a cache, counted by `get` and `get_mut` only.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// entries pushed out to make room, not counting explicit removals
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "hits: {}, misses: {}, hit rate: {:.1}%, evictions: {}",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0,
+            self.evictions
+        )
+    }
+}
+
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize>;
+type EvictionCallback<K, V> = Box<dyn FnMut(K, V)>;
+
+/// Capacity, weights and statistics, which both caches keep the same way.
+struct Budget<K, V> {
+    capacity: usize,
+    weight: usize,
+    weigher: Weigher<K, V>,
+    on_evict: Option<EvictionCallback<K, V>>,
+    stats: CacheStats,
+}
+
+impl<K, V> Budget<K, V> {
+    fn new(capacity: usize, weigher: Weigher<K, V>) -> Self {
+        Budget {
+            capacity,
+            weight: 0,
+            weigher,
+            on_evict: None,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn over(&self) -> bool {
+        self.weight > self.capacity
+    }
+
+    fn evicted(&mut self, key: K, value: V) {
+        self.stats.evictions += 1;
+        if let Some(on_evict) = &mut self.on_evict {
+            on_evict(key, value);
+        }
+    }
+}
+
+struct LruEntry<K, V> {
+    key: K,
+    value: V,
+    weight: usize,
+}
+
+/// Least-recently-used cache: a hash table from keys to nodes of a recency list, so
+/// lookups, updates and evictions are all O(1). Once the total weight of the entries
+/// exceeds the capacity, the entries used longest ago are evicted.
+pub struct LruCache<K, V> {
+    index: HashTable<K, usize>,
+    nodes: Nodes<LruEntry<K, V>>,
+    // most recently used first
+    order: List,
+    budget: Budget<K, V>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Cache holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Cache whose entries weigh `weigher(key, value)` each and at most `capacity` in total.
+    pub fn with_weigher(capacity: usize, weigher: impl Fn(&K, &V) -> usize + 'static) -> Self {
+        LruCache {
+            index: HashTable::new(),
+            nodes: Nodes::new(),
+            order: List::EMPTY,
+            budget: Budget::new(capacity, Box::new(weigher)),
+        }
+    }
+
+    /// Calls `on_evict` with every entry evicted to make room.
+    #[allow(dead_code)]
+    pub fn set_on_evict(&mut self, on_evict: impl FnMut(K, V) + 'static) {
+        self.budget.on_evict = Some(Box::new(on_evict));
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    #[allow(dead_code)]
+    pub fn capacity(&self) -> usize {
+        self.budget.capacity
+    }
+
+    /// Total weight of the cached entries.
+    #[allow(dead_code)]
+    pub fn weight(&self) -> usize {
+        self.budget.weight
+    }
+
+    /// Changes the capacity, evicting entries until the cache fits.
+    #[allow(dead_code)]
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.budget.capacity = capacity;
+        self.evict_to_fit();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.budget.stats
+    }
+
+    /// Looks `key` up and marks it as the most recently used entry.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(&index) = self.index.get(key) else {
+            self.budget.stats.misses += 1;
+            return None;
+        };
+        self.budget.stats.hits += 1;
+        self.touch(index);
+        Some(&mut self.nodes.get_mut(index).value)
+    }
+
+    /// Looks `key` up without changing its recency or the statistics.
+    #[allow(dead_code)]
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &index = self.index.get(key)?;
+        Some(&self.nodes.get(index).value)
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Caches `value` under `key` as the most recently used entry, evicting the least recently
+    /// used ones until the cache fits again. Returns the value it replaced. An entry heavier than
+    /// the whole capacity is not cached at all but goes straight to the eviction callback.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        let weight = (self.budget.weigher)(&key, &value);
+        if weight > self.budget.capacity {
+            let old = self.remove(&key);
+            self.budget.evicted(key, value);
+            return old;
+        }
+
+        let old = match self.index.get(&key) {
+            Some(&index) => {
+                let entry = self.nodes.get_mut(index);
+                self.budget.weight = self.budget.weight - entry.weight + weight;
+                entry.weight = weight;
+                let old = std::mem::replace(&mut entry.value, value);
+                self.touch(index);
+                Some(old)
+            }
+            None => {
+                let index = self.nodes.insert(LruEntry {
+                    key: key.clone(),
+                    value,
+                    weight,
+                });
+                self.nodes.push_front(&mut self.order, index);
+                self.index.insert(key, index);
+                self.budget.weight += weight;
+                None
+            }
+        };
+        self.evict_to_fit();
+        old
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.index.remove(key)?;
+        let entry = self.unlink(index);
+        Some(entry.value)
+    }
+
+    /// Entries from the most to the least recently used.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.nodes
+            .iter(self.order)
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    fn touch(&mut self, index: usize) {
+        self.nodes.unlink(&mut self.order, index);
+        self.nodes.push_front(&mut self.order, index);
+    }
+
+    fn unlink(&mut self, index: usize) -> LruEntry<K, V> {
+        self.nodes.unlink(&mut self.order, index);
+        let entry = self.nodes.remove(index);
+        self.budget.weight -= entry.weight;
+        entry
+    }
+
+    fn evict_to_fit(&mut self) {
+        while self.budget.over() {
+            let entry = self.unlink(self.order.tail);
+            self.index.remove(&entry.key);
+            self.budget.evicted(entry.key, entry.value);
+        }
+    }
+}
+
+struct LfuEntry<K, V> {
+    key: K,
+    value: V,
+    weight: usize,
+    bucket: usize,
+}
+
+/// The entries used `frequency` times.
+struct Bucket {
+    frequency: u64,
+    // most recently used first
+    entries: List,
+}
+
+/// Least-frequently-used cache in O(1) per operation (Shah, Mitra and Matani): entries sit
+/// in one bucket per use count, and the buckets form a list ordered by count, so a use
+/// moves an entry to the neighbouring bucket and the eviction victim is always at the tail
+/// of the first bucket. Ties between equally used entries go to the least recently used.
+pub struct LfuCache<K, V> {
+    index: HashTable<K, usize>,
+    entries: Nodes<LfuEntry<K, V>>,
+    buckets: Nodes<Bucket>,
+    // by increasing frequency
+    frequencies: List,
+    budget: Budget<K, V>,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    /// Cache holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Cache whose entries weigh `weigher(key, value)` each and at most `capacity` in total.
+    pub fn with_weigher(capacity: usize, weigher: impl Fn(&K, &V) -> usize + 'static) -> Self {
+        LfuCache {
+            index: HashTable::new(),
+            entries: Nodes::new(),
+            buckets: Nodes::new(),
+            frequencies: List::EMPTY,
+            budget: Budget::new(capacity, Box::new(weigher)),
+        }
+    }
+
+    /// Calls `on_evict` with every entry evicted to make room.
+    #[allow(dead_code)]
+    pub fn set_on_evict(&mut self, on_evict: impl FnMut(K, V) + 'static) {
+        self.budget.on_evict = Some(Box::new(on_evict));
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    #[allow(dead_code)]
+    pub fn capacity(&self) -> usize {
+        self.budget.capacity
+    }
+
+    /// Total weight of the cached entries.
+    #[allow(dead_code)]
+    pub fn weight(&self) -> usize {
+        self.budget.weight
+    }
+
+    /// Changes the capacity, evicting entries until the cache fits.
+    #[allow(dead_code)]
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.budget.capacity = capacity;
+        self.evict_to_fit(NIL);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.budget.stats
+    }
+
+    /// Times `key` was put or looked up since it entered the cache.
+    #[allow(dead_code)]
+    pub fn frequency<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &index = self.index.get(key)?;
+        Some(self.buckets.get(self.entries.get(index).bucket).frequency)
+    }
+
+    /// Looks `key` up and counts a use of it.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(&index) = self.index.get(key) else {
+            self.budget.stats.misses += 1;
+            return None;
+        };
+        self.budget.stats.hits += 1;
+        self.touch(index);
+        Some(&mut self.entries.get_mut(index).value)
+    }
+
+    /// Looks `key` up without counting a use or changing the statistics.
+    #[allow(dead_code)]
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &index = self.index.get(key)?;
+        Some(&self.entries.get(index).value)
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Caches `value` under `key`, counting a use if it was already there, and evicts the least
+    /// frequently used other entries until the cache fits again. Returns the value it replaced.
+    /// An entry heavier than the whole capacity is not cached at all but goes straight to the
+    /// eviction callback.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        let weight = (self.budget.weigher)(&key, &value);
+        if weight > self.budget.capacity {
+            let old = self.remove(&key);
+            self.budget.evicted(key, value);
+            return old;
+        }
+
+        let (index, old) = match self.index.get(&key) {
+            Some(&index) => {
+                let entry = self.entries.get_mut(index);
+                self.budget.weight = self.budget.weight - entry.weight + weight;
+                entry.weight = weight;
+                let old = std::mem::replace(&mut entry.value, value);
+                self.touch(index);
+                (index, Some(old))
+            }
+            None => {
+                let index = self.entries.insert(LfuEntry {
+                    key: key.clone(),
+                    value,
+                    weight,
+                    bucket: NIL,
+                });
+                let first = self.frequencies.head;
+                let bucket = if first != NIL && self.buckets.get(first).frequency == 1 {
+                    first
+                } else {
+                    let bucket = self.buckets.insert(Bucket {
+                        frequency: 1,
+                        entries: List::EMPTY,
+                    });
+                    self.buckets.push_front(&mut self.frequencies, bucket);
+                    bucket
+                };
+                self.attach(index, bucket);
+                self.index.insert(key, index);
+                self.budget.weight += weight;
+                (index, None)
+            }
+        };
+        self.evict_to_fit(index);
+        old
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.index.remove(key)?;
+        let entry = self.unlink(index);
+        Some(entry.value)
+    }
+
+    /// Entries from the most to the least frequently used.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut buckets: Vec<&Bucket> = self.buckets.iter(self.frequencies).collect();
+        buckets.reverse();
+        buckets.into_iter().flat_map(move |bucket| {
+            self.entries
+                .iter(bucket.entries)
+                .map(|entry| (&entry.key, &entry.value))
+        })
+    }
+
+    fn attach(&mut self, index: usize, bucket: usize) {
+        let mut entries = self.buckets.get(bucket).entries;
+        self.entries.push_front(&mut entries, index);
+        self.buckets.get_mut(bucket).entries = entries;
+        self.entries.get_mut(index).bucket = bucket;
+    }
+
+    /// Takes entry `index` out of its bucket, dropping the bucket if that empties it.
+    fn detach(&mut self, index: usize) {
+        let bucket = self.entries.get(index).bucket;
+        let mut entries = self.buckets.get(bucket).entries;
+        self.entries.unlink(&mut entries, index);
+        if entries.is_empty() {
+            self.buckets.unlink(&mut self.frequencies, bucket);
+            self.buckets.remove(bucket);
+        } else {
+            self.buckets.get_mut(bucket).entries = entries;
+        }
+    }
+
+    /// Moves entry `index` to the bucket one use higher, creating it if needed.
+    fn touch(&mut self, index: usize) {
+        let bucket = self.entries.get(index).bucket;
+        let frequency = self.buckets.get(bucket).frequency + 1;
+        let next = self.buckets.next(bucket);
+        let target = if next != NIL && self.buckets.get(next).frequency == frequency {
+            next
+        } else {
+            let target = self.buckets.insert(Bucket {
+                frequency,
+                entries: List::EMPTY,
+            });
+            self.buckets
+                .link_after(&mut self.frequencies, bucket, target);
+            target
+        };
+        self.detach(index);
+        self.attach(index, target);
+    }
+
+    fn unlink(&mut self, index: usize) -> LfuEntry<K, V> {
+        self.detach(index);
+        let entry = self.entries.remove(index);
+        self.budget.weight -= entry.weight;
+        entry
+    }
+
+    /// Least frequently used entry other than `keep`.
+    fn victim(&self, keep: usize) -> usize {
+        let first = self.frequencies.head;
+        let tail = self.buckets.get(first).entries.tail;
+        if tail != keep {
+            return tail;
+        }
+        match self.entries.prev(tail) {
+            NIL => self.buckets.get(self.buckets.next(first)).entries.tail,
+            prev => prev,
+        }
+    }
+
+    /// Evicts until the cache fits, sparing the entry `keep` that was just put.
+    /// That entry fits by itself, so it is never the only one left while over capacity.
+    fn evict_to_fit(&mut self, keep: usize) {
+        while self.budget.over() {
+            let entry = self.unlink(self.victim(keep));
+            self.index.remove(&entry.key);
+            self.budget.evicted(entry.key, entry.value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache = LruCache::new(3);
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&evicted);
+        cache.set_on_evict(move |key, value| log.borrow_mut().push((key, value)));
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        assert_eq!(cache.get("a"), Some(&1));
+        cache.put("d", 4);
+        assert_eq!(*RefCell::borrow(&evicted), vec![("b", 2)]);
+        assert_eq!(cache.peek("c"), Some(&3));
+        cache.put("e", 5);
+        assert_eq!(*RefCell::borrow(&evicted), vec![("b", 2), ("c", 3)]);
+        assert_eq!(
+            cache.iter().map(|(&key, _)| key).collect::<Vec<_>>(),
+            vec!["e", "d", "a"]
+        );
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 2
+            }
+        );
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key("e"));
+    }
+
+    #[test]
+    fn lfu_evicts_least_frequently_used() {
+        let mut cache = LfuCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        cache.get("a");
+        cache.get("a");
+        cache.get("b");
+        assert_eq!(cache.frequency("a"), Some(3));
+
+        // c is the least used, then b is older than d among the twice used
+        cache.put("d", 4);
+        assert!(!cache.contains_key("c"));
+        cache.get("d");
+        cache.put("e", 5);
+        assert!(!cache.contains_key("b"));
+        assert_eq!(
+            cache.iter().map(|(&key, _)| key).collect::<Vec<_>>(),
+            vec!["a", "d", "e"]
+        );
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn weighted_capacity() {
+        let mut cache = LruCache::with_weigher(10, |_: &u32, value: &String| value.len());
+        cache.put(1, "aaaa".to_string());
+        cache.put(2, "bbbb".to_string());
+        assert_eq!(cache.weight(), 8);
+        cache.put(3, "cc".to_string());
+        assert_eq!(cache.len(), 3);
+        cache.put(1, "aaaaaa".to_string());
+        assert_eq!(cache.weight(), 8);
+        assert!(!cache.contains_key(&2));
+
+        // too heavy to cache at all, so the old value leaves the cache too
+        assert_eq!(cache.put(3, "x".repeat(11)), Some("cc".to_string()));
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.weight(), 6);
+
+        let mut cache = LfuCache::with_weigher(10, |_: &u32, value: &String| value.len());
+        cache.put(1, "aaaa".to_string());
+        cache.get(&1);
+        cache.put(2, "bbbb".to_string());
+        cache.put(3, "cccccc".to_string());
+        assert!(cache.contains_key(&1) && cache.contains_key(&3));
+        assert_eq!(cache.weight(), 10);
+        // growing the most used entry pushes out everything else
+        cache.put(1, "aaaaaaaaa".to_string());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.frequency(&1), Some(3));
+    }
+
+    /// Replays random puts, gets and removes against a model that scans every entry.
+    /// The model evicts the entry with the smallest `(uses, last use)` for LFU
+    /// and the smallest last use for LRU.
+    fn check_against_model(lfu: bool) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let capacity = rng.gen_range(1..20);
+            let mut lru = LruCache::new(capacity);
+            let mut lfu_cache = LfuCache::new(capacity);
+            // key -> (value, uses, last use)
+            let mut model: HashMap<u32, (u32, u64, u64)> = HashMap::new();
+
+            for tick in 0..3_000u64 {
+                let key = rng.gen_range(0..capacity as u32 * 2);
+                match rng.gen_range(0..3) {
+                    0 => {
+                        let value = rng.gen();
+                        let old = if lfu {
+                            lfu_cache.put(key, value)
+                        } else {
+                            lru.put(key, value)
+                        };
+                        assert_eq!(old, model.get(&key).map(|entry| entry.0));
+                        let entry = model.entry(key).or_insert((value, 0, tick));
+                        *entry = (value, entry.1 + 1, tick);
+                        if model.len() > capacity {
+                            let (&victim, _) = model
+                                .iter()
+                                .filter(|(&k, _)| k != key)
+                                .min_by_key(
+                                    |(_, &(_, uses, last))| {
+                                        if lfu {
+                                            (uses, last)
+                                        } else {
+                                            (0, last)
+                                        }
+                                    },
+                                )
+                                .unwrap();
+                            model.remove(&victim);
+                        }
+                    }
+                    1 => {
+                        let got = if lfu {
+                            lfu_cache.get(&key).copied()
+                        } else {
+                            lru.get(&key).copied()
+                        };
+                        if let Some(entry) = model.get_mut(&key) {
+                            entry.1 += 1;
+                            entry.2 = tick;
+                        }
+                        assert_eq!(got, model.get(&key).map(|entry| entry.0));
+                    }
+                    _ => {
+                        let removed = if lfu {
+                            lfu_cache.remove(&key)
+                        } else {
+                            lru.remove(&key)
+                        };
+                        assert_eq!(removed, model.remove(&key).map(|entry| entry.0));
+                    }
+                }
+                let len = if lfu { lfu_cache.len() } else { lru.len() };
+                assert_eq!(len, model.len());
+            }
+            for (key, &(value, uses, _)) in &model {
+                if lfu {
+                    assert_eq!(lfu_cache.peek(key), Some(&value));
+                    assert_eq!(lfu_cache.frequency(key), Some(uses));
+                } else {
+                    assert_eq!(lru.peek(key), Some(&value));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lru_matches_model() {
+        check_against_model(false);
+    }
+
+    #[test]
+    fn lfu_matches_model() {
+        check_against_model(true);
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hash};
+use std::mem::size_of;
+
+use super::Map;
+
+const INITIAL_BUCKETS: usize = 16;
+const LOAD_FACTOR: f64 = 0.75;
+/// Chains longer than this turn into trees, as in Java's `HashMap`.
+const TREEIFY_THRESHOLD: usize = 8;
+
+/// The entries that landed in one bucket. A chain that grows past the treeify threshold is
+/// kept in a `BTreeMap` ordered by full hash, which makes a bucket full of colliding keys
+/// O(log n) to search without requiring `K: Ord`. It turns back into a list once it shrinks
+/// to half the threshold.
+enum Bucket<K, V> {
+    List(Vec<(u64, K, V)>),
+    /// entries grouped by full hash, plus their total count
+    Tree(BTreeMap<u64, Vec<(K, V)>>, usize),
+}
+
+impl<K: Eq, V> Bucket<K, V> {
+    fn len(&self) -> usize {
+        match self {
+            Bucket::List(entries) => entries.len(),
+            Bucket::Tree(_, len) => *len,
+        }
+    }
+
+    fn get(&self, hash: u64, key: &K) -> Option<&V> {
+        match self {
+            Bucket::List(entries) => entries
+                .iter()
+                .find(|(h, k, _)| *h == hash && k == key)
+                .map(|(_, _, v)| v),
+            Bucket::Tree(tree, _) => tree
+                .get(&hash)?
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v),
+        }
+    }
+
+    fn get_mut(&mut self, hash: u64, key: &K) -> Option<&mut V> {
+        match self {
+            Bucket::List(entries) => entries
+                .iter_mut()
+                .find(|(h, k, _)| *h == hash && k == key)
+                .map(|(_, _, v)| v),
+            Bucket::Tree(tree, _) => tree
+                .get_mut(&hash)?
+                .iter_mut()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v),
+        }
+    }
+
+    /// Adds an entry whose key is known to be absent.
+    fn push(&mut self, hash: u64, key: K, value: V, treeify_threshold: Option<usize>) {
+        match self {
+            Bucket::List(entries) => {
+                entries.push((hash, key, value));
+                if treeify_threshold.is_some_and(|threshold| entries.len() > threshold) {
+                    self.treeify();
+                }
+            }
+            Bucket::Tree(tree, len) => {
+                tree.entry(hash).or_default().push((key, value));
+                *len += 1;
+            }
+        }
+    }
+
+    fn remove(&mut self, hash: u64, key: &K, treeify_threshold: Option<usize>) -> Option<V> {
+        let value = match self {
+            Bucket::List(entries) => {
+                let index = entries
+                    .iter()
+                    .position(|(h, k, _)| *h == hash && k == key)?;
+                entries.swap_remove(index).2
+            }
+            Bucket::Tree(tree, len) => {
+                let same_hash = tree.get_mut(&hash)?;
+                let index = same_hash.iter().position(|(k, _)| k == key)?;
+                let (_, value) = same_hash.swap_remove(index);
+                if same_hash.is_empty() {
+                    tree.remove(&hash);
+                }
+                *len -= 1;
+                value
+            }
+        };
+
+        if let Bucket::Tree(_, len) = self {
+            if treeify_threshold.is_none_or(|threshold| *len <= threshold / 2) {
+                self.untreeify();
+            }
+        }
+        Some(value)
+    }
+
+    fn treeify(&mut self) {
+        if let Bucket::List(entries) = self {
+            let entries = std::mem::take(entries);
+            let len = entries.len();
+            let mut tree: BTreeMap<u64, Vec<(K, V)>> = BTreeMap::new();
+            for (hash, key, value) in entries {
+                tree.entry(hash).or_default().push((key, value));
+            }
+            *self = Bucket::Tree(tree, len);
+        }
+    }
+
+    fn untreeify(&mut self) {
+        if let Bucket::Tree(tree, _) = self {
+            let tree = std::mem::take(tree);
+            *self = Bucket::List(
+                tree.into_iter()
+                    .flat_map(|(hash, entries)| {
+                        entries
+                            .into_iter()
+                            .map(move |(key, value)| (hash, key, value))
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    fn into_entries(mut self) -> Vec<(u64, K, V)> {
+        self.untreeify();
+        match self {
+            Bucket::List(entries) => entries,
+            Bucket::Tree(_, _) => unreachable!(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
+        match self {
+            Bucket::List(entries) => Box::new(entries.iter().map(|(_, k, v)| (k, v))),
+            Bucket::Tree(tree, _) => Box::new(
+                tree.values()
+                    .flat_map(|entries| entries.iter().map(|(k, v)| (k, v))),
+            ),
+        }
+    }
+
+    /// Heap bytes held by the chain, not counting `BTreeMap` node overhead.
+    fn heap_size(&self) -> usize {
+        match self {
+            Bucket::List(entries) => entries.capacity() * size_of::<(u64, K, V)>(),
+            Bucket::Tree(tree, _) => tree
+                .values()
+                .map(|entries| {
+                    size_of::<(u64, Vec<(K, V)>)>() + entries.capacity() * size_of::<(K, V)>()
+                })
+                .sum(),
+        }
+    }
+}
+
+/// Separate chaining: every bucket holds the list of entries hashing to it.
+pub struct ChainedHashTable<K, V, S = RandomState> {
+    buckets: Vec<Bucket<K, V>>,
+    size: usize,
+    hasher: S,
+    // entries per bucket before the table grows, may exceed 1
+    max_load: f64,
+    treeify_threshold: Option<usize>,
+}
+
+fn empty_buckets<K, V>(count: usize) -> Vec<Bucket<K, V>> {
+    (0..count).map(|_| Bucket::List(Vec::new())).collect()
+}
+
+impl<K: Hash + Eq, V> ChainedHashTable<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> ChainedHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hasher: S) -> Self {
+        ChainedHashTable {
+            buckets: empty_buckets(INITIAL_BUCKETS),
+            size: 0,
+            hasher,
+            max_load: LOAD_FACTOR,
+            treeify_threshold: Some(TREEIFY_THRESHOLD),
+        }
+    }
+
+    /// Changes the number of entries per bucket that makes the table grow, 0.75 by default.
+    pub fn set_max_load_factor(&mut self, max_load: f64) {
+        assert!(max_load > 0.0, "load factor must be positive");
+        self.max_load = max_load;
+        while self.size as f64 > self.buckets.len() as f64 * self.max_load {
+            self.rehash(self.buckets.len() * 2);
+        }
+    }
+
+    /// Chains longer than `threshold` become trees; `None` keeps every chain a list.
+    pub fn set_treeify_threshold(&mut self, threshold: Option<usize>) {
+        self.treeify_threshold = threshold;
+        self.rehash(self.buckets.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn hash(&self, key: &K) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    fn bucket_index(&self, hash: u64) -> usize {
+        hash as usize & (self.buckets.len() - 1)
+    }
+
+    fn rehash(&mut self, bucket_count: usize) {
+        let old_buckets = std::mem::replace(&mut self.buckets, empty_buckets(bucket_count));
+        for bucket in old_buckets {
+            for (hash, key, value) in bucket.into_entries() {
+                let index = self.bucket_index(hash);
+                self.buckets[index].push(hash, key, value, self.treeify_threshold);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        let index = self.bucket_index(hash);
+        if let Some(old) = self.buckets[index].get_mut(hash, &key) {
+            return Some(std::mem::replace(old, value));
+        }
+
+        if (self.size + 1) as f64 > self.buckets.len() as f64 * self.max_load {
+            self.rehash(self.buckets.len() * 2);
+        }
+        let index = self.bucket_index(hash);
+        self.buckets[index].push(hash, key, value, self.treeify_threshold);
+        self.size += 1;
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = self.hash(key);
+        self.buckets[self.bucket_index(hash)].get(hash, key)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let hash = self.hash(key);
+        let index = self.bucket_index(hash);
+        self.buckets[index].get_mut(hash, key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = self.hash(key);
+        let index = self.bucket_index(hash);
+        let value = self.buckets[index].remove(hash, key, self.treeify_threshold)?;
+        self.size -= 1;
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets.iter().flat_map(|bucket| bucket.iter())
+    }
+
+    pub fn longest_chain(&self) -> usize {
+        self.buckets.iter().map(Bucket::len).max().unwrap_or(0)
+    }
+
+    pub fn treeified_buckets(&self) -> usize {
+        self.buckets
+            .iter()
+            .filter(|bucket| matches!(bucket, Bucket::Tree(_, _)))
+            .count()
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ChainedHashTable<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> Map<K, V> for ChainedHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        ChainedHashTable::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        ChainedHashTable::get(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        ChainedHashTable::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+
+    fn slots(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.buckets.capacity() * size_of::<Bucket<K, V>>()
+            + self.buckets.iter().map(Bucket::heap_size).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::check_map_against_std;
+    use super::*;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    /// Puts every `u32` key into bucket 0 of any table up to 2^32 buckets, with distinct full hashes.
+    #[derive(Default)]
+    struct HighBitsHasher(u64);
+
+    impl Hasher for HighBitsHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            let folded = bytes.iter().fold(self.0 >> 32, |h, &b| h.wrapping_mul(31) ^ b as u64);
+            self.0 = folded << 32;
+        }
+
+        fn write_u32(&mut self, i: u32) {
+            self.0 = (i as u64) << 32;
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn matches_std_hash_map() {
+        check_map_against_std(ChainedHashTable::new);
+        check_map_against_std(|| {
+            let mut table = ChainedHashTable::new();
+            table.set_treeify_threshold(None);
+            table.set_max_load_factor(4.0);
+            table
+        });
+        check_map_against_std(|| {
+            let mut table = ChainedHashTable::new();
+            table.set_treeify_threshold(Some(1));
+            table
+        });
+    }
+
+    #[test]
+    fn colliding_chains_are_treeified_and_restored() {
+        let mut table =
+            ChainedHashTable::with_hasher(BuildHasherDefault::<HighBitsHasher>::default());
+        let mut expected = HashMap::new();
+        for key in 0..200u32 {
+            table.insert(key, key);
+            expected.insert(key, key);
+        }
+        assert_eq!(table.longest_chain(), 200);
+        assert_eq!(table.treeified_buckets(), 1);
+        assert!(expected
+            .iter()
+            .all(|(key, value)| table.get(key) == Some(value)));
+
+        for key in 0..197 {
+            assert_eq!(table.remove(&key), Some(key));
+        }
+        assert_eq!(table.treeified_buckets(), 0);
+        let mut rest: Vec<_> = table.iter().map(|(&key, &value)| (key, value)).collect();
+        rest.sort_unstable();
+        assert_eq!(rest, vec![(197, 197), (198, 198), (199, 199)]);
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem::size_of;
+
+use rand::Rng;
+
+use super::hashers::fmix64;
+use super::Map;
+
+/// Slots per bucket. Four-way buckets let two tables run at loads above 90%,
+/// where one slot per bucket fails just past 50%.
+const BUCKET_SIZE: usize = 4;
+const INITIAL_BUCKETS: usize = 4;
+const LOAD_FACTOR: f64 = 0.9;
+/// Evictions tried before an insert gives up and the table is rebuilt with new seeds.
+const MAX_KICKS: usize = 500;
+/// Rebuilds at the same size before the table grows instead.
+const MAX_REHASHES: usize = 4;
+
+type Slot<K, V> = Option<(u64, K, V)>;
+
+/// Cuckoo hashing: every key has one bucket in each of two tables and lives in one of them,
+/// so a lookup inspects at most two buckets. An insert that finds both full evicts a resident
+/// to its other bucket, and so on, until a free slot turns up.
+pub struct CuckooHashTable<K, V, S = RandomState> {
+    // each table holds `buckets` buckets of BUCKET_SIZE consecutive slots
+    tables: [Vec<Slot<K, V>>; 2],
+    seeds: [u64; 2],
+    buckets: usize,
+    size: usize,
+    hasher: S,
+    max_load: f64,
+    rehashes: usize,
+}
+
+fn empty_slots<K, V>(count: usize) -> Vec<Slot<K, V>> {
+    (0..count).map(|_| None).collect()
+}
+
+impl<K: Hash + Eq, V> CuckooHashTable<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> CuckooHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hasher: S) -> Self {
+        let mut rng = rand::thread_rng();
+        CuckooHashTable {
+            tables: [
+                empty_slots(INITIAL_BUCKETS * BUCKET_SIZE),
+                empty_slots(INITIAL_BUCKETS * BUCKET_SIZE),
+            ],
+            seeds: [rng.gen(), rng.gen()],
+            buckets: INITIAL_BUCKETS,
+            size: 0,
+            hasher,
+            max_load: LOAD_FACTOR,
+            rehashes: 0,
+        }
+    }
+
+    /// Changes the share of slots that may be in use before the table grows, 0.9 by default.
+    /// Inserts that hit a cycle still rebuild or grow the table below this load.
+    pub fn set_max_load_factor(&mut self, max_load: f64) {
+        assert!(
+            max_load > 0.0 && max_load < 1.0,
+            "load factor must be in (0, 1)"
+        );
+        self.max_load = max_load;
+        if self.size as f64 > self.slot_count() as f64 * self.max_load {
+            self.rebuild(self.buckets * 2, Vec::new());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Times the table was rebuilt, to new seeds or a larger size.
+    pub fn rehashes(&self) -> usize {
+        self.rehashes
+    }
+
+    fn slot_count(&self) -> usize {
+        2 * self.buckets * BUCKET_SIZE
+    }
+
+    /// First slot of the bucket `hash` maps to in table `table`.
+    fn bucket_start(&self, table: usize, hash: u64) -> usize {
+        let bucket = fmix64(hash ^ self.seeds[table]) as usize & (self.buckets - 1);
+        bucket * BUCKET_SIZE
+    }
+
+    fn find(&self, hash: u64, key: &K) -> Option<(usize, usize)> {
+        (0..2).find_map(|table| {
+            let start = self.bucket_start(table, hash);
+            (start..start + BUCKET_SIZE)
+                .find(|&slot| {
+                    matches!(&self.tables[table][slot], Some((h, k, _)) if *h == hash && k == key)
+                })
+                .map(|slot| (table, slot))
+        })
+    }
+
+    fn free_slot(&self, table: usize, hash: u64) -> Option<usize> {
+        let start = self.bucket_start(table, hash);
+        (start..start + BUCKET_SIZE).find(|&slot| self.tables[table][slot].is_none())
+    }
+
+    /// Places an entry whose key is absent, evicting residents along a random walk.
+    /// Returns the entry left without a slot after MAX_KICKS evictions.
+    fn try_place(&mut self, entry: (u64, K, V)) -> Option<(u64, K, V)> {
+        let mut carried = entry;
+        let mut table = 0;
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..MAX_KICKS {
+            for candidate in 0..2 {
+                if let Some(slot) = self.free_slot(candidate, carried.0) {
+                    self.tables[candidate][slot] = Some(carried);
+                    return None;
+                }
+            }
+            let slot = self.bucket_start(table, carried.0) + rng.gen_range(0..BUCKET_SIZE);
+            carried = self.tables[table][slot]
+                .replace(carried)
+                .expect("bucket is full");
+            table = 1 - table;
+        }
+        Some(carried)
+    }
+
+    /// Rebuilds the tables with fresh seeds and `buckets` buckets each, plus the `pending`
+    /// entries. The size doubles whenever MAX_REHASHES seeds in a row fail to fit everything.
+    fn rebuild(&mut self, mut buckets: usize, mut pending: Vec<(u64, K, V)>) {
+        let mut attempts = 0;
+        loop {
+            for table in &mut self.tables {
+                pending.extend(table.drain(..).flatten());
+            }
+            if attempts == MAX_REHASHES {
+                buckets *= 2;
+                attempts = 0;
+            }
+            attempts += 1;
+            self.rehashes += 1;
+
+            let mut rng = rand::thread_rng();
+            self.seeds = [rng.gen(), rng.gen()];
+            self.buckets = buckets;
+            self.tables = [
+                empty_slots(buckets * BUCKET_SIZE),
+                empty_slots(buckets * BUCKET_SIZE),
+            ];
+
+            let mut homeless = None;
+            while let Some(entry) = pending.pop() {
+                homeless = self.try_place(entry);
+                if homeless.is_some() {
+                    break;
+                }
+            }
+            match homeless {
+                None => return,
+                Some(entry) => pending.push(entry),
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        if let Some((table, slot)) = self.find(hash, &key) {
+            let (_, _, old) = self.tables[table][slot].as_mut().unwrap();
+            return Some(std::mem::replace(old, value));
+        }
+
+        self.size += 1;
+        if self.size as f64 > self.slot_count() as f64 * self.max_load {
+            self.rebuild(self.buckets * 2, vec![(hash, key, value)]);
+        } else if let Some(homeless) = self.try_place((hash, key, value)) {
+            self.rebuild(self.buckets, vec![homeless]);
+        }
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = self.hasher.hash_one(key);
+        let (table, slot) = self.find(hash, key)?;
+        self.tables[table][slot].as_ref().map(|(_, _, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let hash = self.hasher.hash_one(key);
+        let (table, slot) = self.find(hash, key)?;
+        self.tables[table][slot].as_mut().map(|(_, _, value)| value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = self.hasher.hash_one(key);
+        let (table, slot) = self.find(hash, key)?;
+        self.size -= 1;
+        self.tables[table][slot].take().map(|(_, _, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.tables
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|(_, key, value)| (key, value))
+    }
+}
+
+impl<K: Hash + Eq, V> Default for CuckooHashTable<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> Map<K, V> for CuckooHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        CuckooHashTable::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        CuckooHashTable::get(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        CuckooHashTable::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+
+    fn slots(&self) -> usize {
+        self.slot_count()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.tables
+            .iter()
+            .map(|table| table.capacity() * size_of::<Slot<K, V>>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::check_map_against_std;
+    use super::*;
+
+    #[test]
+    fn matches_std_hash_map() {
+        check_map_against_std(CuckooHashTable::new);
+        check_map_against_std(|| {
+            let mut table = CuckooHashTable::new();
+            table.set_max_load_factor(0.97);
+            table
+        });
+    }
+
+    #[test]
+    fn every_key_sits_in_one_of_its_two_buckets() {
+        let mut table = CuckooHashTable::new();
+        table.set_max_load_factor(0.95);
+        for key in 0..10_000 {
+            table.insert(key, key);
+        }
+        assert_eq!(table.len(), 10_000);
+        assert_eq!(table.iter().count(), 10_000);
+        assert!(table.load_factor() > 0.45);
+
+        for (index, table_slots) in table.tables.iter().enumerate() {
+            for (slot, entry) in table_slots.iter().enumerate() {
+                if let Some((hash, _, _)) = entry {
+                    assert_eq!(
+                        table.bucket_start(index, *hash),
+                        slot / BUCKET_SIZE * BUCKET_SIZE
+                    );
+                }
+            }
+        }
+        assert!((0..10_000).all(|key| table.get(&key) == Some(&key)));
+    }
+}
@@ -0,0 +1,238 @@
+// Non-cryptographic hash functions shared by the hash table and the probabilistic structures.
+
+use std::hash::{BuildHasher, Hasher};
+
+use rand::Rng;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+/// 2^64 / golden ratio, the multiplier of Fibonacci hashing.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+const MURMUR_C1: u64 = 0x87c3_7b91_1142_53d5;
+const MURMUR_C2: u64 = 0x4cf5_ad43_2745_937f;
+
+/// FNV-1a, byte at a time. Good on short strings, weak on keys that differ only in high bits.
+#[derive(Debug, Clone)]
+pub struct Fnv1aHasher(u64);
+
+impl Hasher for Fnv1aHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Builds [`Fnv1aHasher`]s; a non-zero seed is folded into the offset basis.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FnvBuildHasher {
+    seed: u64,
+}
+
+impl FnvBuildHasher {
+    #[allow(dead_code)]
+    pub fn with_seed(seed: u64) -> Self {
+        FnvBuildHasher { seed }
+    }
+}
+
+impl BuildHasher for FnvBuildHasher {
+    type Hasher = Fnv1aHasher;
+
+    fn build_hasher(&self) -> Fnv1aHasher {
+        let mut hasher = Fnv1aHasher(FNV_OFFSET_BASIS);
+        if self.seed != 0 {
+            hasher.write_u64(self.seed);
+        }
+        hasher
+    }
+}
+
+/// Multiply-shift (Fibonacci) hashing for integer keys: one multiply per word.
+/// Only the top bits of the product are well mixed, so `finish` reverses the bits and a
+/// power-of-two table indexing with the low `k` bits sees the top `k` bits of the product.
+#[derive(Debug, Clone)]
+pub struct FibonacciHasher(u64);
+
+impl FibonacciHasher {
+    fn mix(&mut self, word: u64) {
+        self.0 = (self.0 ^ word).wrapping_mul(GOLDEN_GAMMA);
+    }
+}
+
+impl Hasher for FibonacciHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.mix(u64::from_le_bytes(word));
+        }
+        // the last chunk is zero padded, the length tells `b"a"` and `b"a\0"` apart
+        self.mix(bytes.len() as u64);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.mix(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.mix(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.mix(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.mix(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.mix(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.reverse_bits()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FibonacciBuildHasher {
+    seed: u64,
+}
+
+impl FibonacciBuildHasher {
+    #[allow(dead_code)]
+    pub fn with_seed(seed: u64) -> Self {
+        FibonacciBuildHasher { seed }
+    }
+}
+
+impl BuildHasher for FibonacciBuildHasher {
+    type Hasher = FibonacciHasher;
+
+    fn build_hasher(&self) -> FibonacciHasher {
+        FibonacciHasher(self.seed)
+    }
+}
+
+/// MurmurHash3-style 64-bit hasher: every 8-byte word goes through the x64 block mix and
+/// `finish` applies the `fmix64` avalanche, so all output bits depend on all input bits.
+#[derive(Debug, Clone)]
+pub struct Mix64Hasher {
+    state: u64,
+    length: u64,
+}
+
+impl Mix64Hasher {
+    fn mix(&mut self, word: u64) {
+        let k = word
+            .wrapping_mul(MURMUR_C1)
+            .rotate_left(31)
+            .wrapping_mul(MURMUR_C2);
+        self.state = (self.state ^ k)
+            .rotate_left(27)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+    }
+}
+
+impl Hasher for Mix64Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.mix(u64::from_le_bytes(word));
+            // count the real bytes, not the zero padding of the last chunk
+            self.length += chunk.len() as u64;
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.mix(i);
+        self.length += 8;
+    }
+
+    fn finish(&self) -> u64 {
+        fmix64(self.state ^ self.length)
+    }
+}
+
+/// Final avalanche step of MurmurHash3.
+pub fn fmix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+}
+
+/// Builds [`Mix64Hasher`]s from a seed. A random seed keeps adversaries from
+/// precomputing colliding keys.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Mix64BuildHasher {
+    seed: u64,
+}
+
+impl Mix64BuildHasher {
+    pub fn with_seed(seed: u64) -> Self {
+        Mix64BuildHasher { seed }
+    }
+
+    pub fn random() -> Self {
+        Self::with_seed(rand::thread_rng().gen())
+    }
+}
+
+impl BuildHasher for Mix64BuildHasher {
+    type Hasher = Mix64Hasher;
+
+    fn build_hasher(&self) -> Mix64Hasher {
+        Mix64Hasher {
+            state: self.seed,
+            length: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_bytes<B: BuildHasher>(build: &B, bytes: &[u8]) -> u64 {
+        let mut hasher = build.build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn zero_padding_does_not_collide() {
+        let fibonacci = FibonacciBuildHasher::with_seed(7);
+        let mix64 = Mix64BuildHasher::with_seed(7);
+        let inputs: [&[u8]; 5] = [b"", b"a", b"a\0", b"a\0\0\0\0\0\0\0", b"a\0\0\0\0\0\0\0\0"];
+        for (i, x) in inputs.iter().enumerate() {
+            for y in &inputs[i + 1..] {
+                assert_ne!(
+                    hash_bytes(&fibonacci, x),
+                    hash_bytes(&fibonacci, y),
+                    "{:?} {:?}",
+                    x,
+                    y
+                );
+                assert_ne!(
+                    hash_bytes(&mix64, x),
+                    hash_bytes(&mix64, y),
+                    "{:?} {:?}",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+}
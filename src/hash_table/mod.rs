@@ -0,0 +1,84 @@
+// Hash table variants behind a common `Map` trait so they can be compared side by side,
+// and the set / multimap / sharded concurrent / cache types built on the open-addressing table.
+
+// The chained and cuckoo tables are run by hash_bench, the perfect hash map by probabilistic;
+// the hash binary itself only uses them in its tests.
+pub mod cache;
+#[allow(dead_code)]
+pub mod chained;
+#[allow(dead_code)]
+#[path = "../codec.rs"]
+pub mod codec;
+#[allow(dead_code)]
+pub mod cuckoo;
+pub mod hashers;
+pub mod multimap;
+pub mod open_addressing;
+#[allow(dead_code)]
+pub mod perfect;
+pub mod set;
+pub mod sharded;
+
+/// Operations shared by every hash table variant.
+#[allow(dead_code)]
+pub trait Map<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced.
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+    fn get(&self, key: &K) -> Option<&V>;
+    fn remove(&mut self, key: &K) -> Option<V>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Slots (or buckets, for chaining) the entries are spread over.
+    fn slots(&self) -> usize;
+
+    /// Approximate heap memory held by the table, in bytes.
+    fn heap_size(&self) -> usize;
+
+    fn load_factor(&self) -> f64 {
+        self.len() as f64 / self.slots() as f64
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Map;
+    use rand::Rng;
+    use std::collections::HashMap;
+
+    /// Runs random inserts, removes and lookups on tables made by `make`
+    /// and checks every answer against `std::collections::HashMap`.
+    pub fn check_map_against_std<M: Map<u32, u32>>(make: impl Fn() -> M) {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..20 {
+            let mut table = make();
+            let mut expected = HashMap::new();
+            let key_space = rng.gen_range(8..2_000);
+
+            for _ in 0..5_000 {
+                let key = rng.gen_range(0..key_space);
+                match rng.gen_range(0..3) {
+                    0 => {
+                        let value = rng.gen::<u32>();
+                        assert_eq!(table.insert(key, value), expected.insert(key, value));
+                    }
+                    1 => assert_eq!(table.remove(&key), expected.remove(&key)),
+                    _ => assert_eq!(table.get(&key), expected.get(&key)),
+                }
+                assert_eq!(table.len(), expected.len());
+            }
+
+            for key in 0..key_space {
+                assert_eq!(table.get(&key), expected.get(&key));
+            }
+        }
+    }
+}
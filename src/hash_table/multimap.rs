@@ -0,0 +1,254 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+use super::open_addressing::{HashTable, LinearProbing, ProbeStrategy};
+
+/// A map holding any number of values per key, kept in insertion order
+/// in a `HashTable<K, Vec<V>>`. Keys without values are removed.
+pub struct HashMultiMap<K, V, P = LinearProbing, S = RandomState> {
+    table: HashTable<K, Vec<V>, P, S>,
+    // values across all keys
+    len: usize,
+}
+
+impl<K: Hash + Eq, V> HashMultiMap<K, V> {
+    pub fn new() -> Self {
+        HashMultiMap {
+            table: HashTable::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<K, V, P, S> HashMultiMap<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    #[allow(dead_code)]
+    pub fn with_strategy_and_hasher(strategy: P, hasher: S) -> Self {
+        HashMultiMap {
+            table: HashTable::with_strategy_and_hasher(strategy, hasher),
+            len: 0,
+        }
+    }
+
+    /// Number of values across all keys.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of distinct keys.
+    #[allow(dead_code)]
+    pub fn key_count(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Adds `value` after any values already stored under `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.table.entry(key).or_insert_with(Vec::new).push(value);
+        self.len += 1;
+    }
+
+    /// Values stored under `key` in insertion order, empty if there are none.
+    pub fn get_all<Q>(&self, key: &Q) -> &[V]
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of values stored under `key`.
+    #[allow(dead_code)]
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_all(key).len()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.contains_key(key)
+    }
+
+    /// Removes the first occurrence of `value` under `key`, returning whether there was one.
+    #[allow(dead_code)]
+    pub fn remove_one<Q>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let Some(values) = self.table.get_mut(key) else {
+            return false;
+        };
+        let Some(index) = values.iter().position(|v| v == value) else {
+            return false;
+        };
+        values.remove(index);
+        if values.is_empty() {
+            self.table.remove(key);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Removes `key` with all its values.
+    #[allow(dead_code)]
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let values = self.table.remove(key).unwrap_or_default();
+        self.len -= values.len();
+        values
+    }
+}
+
+impl<K, V, P, S> HashMultiMap<K, V, P, S> {
+    #[allow(dead_code)]
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.table.keys()
+    }
+
+    /// Every (key, value) pair, the values of one key together and in insertion order.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.table
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+    }
+
+    /// Every key with all of its values.
+    pub fn iter_all(&self) -> impl Iterator<Item = (&K, &[V])> {
+        self.table
+            .iter()
+            .map(|(key, values)| (key, values.as_slice()))
+    }
+}
+
+impl<K, V, P, S> Default for HashMultiMap<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        HashMultiMap {
+            table: HashTable::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<K, V, P, S> Extend<(K, V)> for HashMultiMap<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, P, S> FromIterator<(K, V)> for HashMultiMap<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, P, S> fmt::Debug for HashMultiMap<K, V, P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter_all()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashMap;
+
+    #[test]
+    fn matches_map_of_vectors() {
+        let mut rng = rand::thread_rng();
+        let mut map = HashMultiMap::new();
+        let mut expected: HashMap<u32, Vec<u32>> = HashMap::new();
+
+        for _ in 0..20_000 {
+            let key = rng.gen_range(0..100);
+            let value = rng.gen_range(0..5);
+            match rng.gen_range(0..10) {
+                0..=5 => {
+                    map.insert(key, value);
+                    expected.entry(key).or_default().push(value);
+                }
+                6..=8 => {
+                    let values = expected.entry(key).or_default();
+                    let removed = match values.iter().position(|&v| v == value) {
+                        Some(index) => {
+                            values.remove(index);
+                            true
+                        }
+                        None => false,
+                    };
+                    assert_eq!(map.remove_one(&key, &value), removed);
+                }
+                _ => assert_eq!(
+                    map.remove_all(&key),
+                    expected.remove(&key).unwrap_or_default()
+                ),
+            }
+            expected.retain(|_, values| !values.is_empty());
+            assert_eq!(
+                map.get_all(&key),
+                expected.get(&key).map_or(&[][..], Vec::as_slice)
+            );
+        }
+
+        assert_eq!(map.key_count(), expected.len());
+        assert_eq!(map.len(), expected.values().map(Vec::len).sum::<usize>());
+        assert_eq!(map.iter().count(), map.len());
+        for (key, values) in &expected {
+            assert_eq!(map.count(key), values.len());
+        }
+    }
+
+    #[test]
+    fn groups_values_by_key() {
+        let map: HashMultiMap<&str, u32> = vec![("a", 1), ("b", 2), ("a", 3)].into_iter().collect();
+        assert_eq!(map.get_all("a"), &[1, 3]);
+        assert_eq!(map.get_all("c"), &[] as &[u32]);
+        assert_eq!(map.count("a"), 2);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.key_count(), 2);
+        assert!(map.contains_key("b"));
+    }
+}
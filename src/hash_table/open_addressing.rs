@@ -0,0 +1,1407 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+use super::Map;
+
+const INITIAL_CAPACITY: usize = 16;
+const LOAD_FACTOR: f64 = 0.75;
+// share of the slots that may hold tombstones before the table is rehashed at the same capacity
+const TOMBSTONE_FACTOR: f64 = 0.25;
+
+/// Order in which the slots of a table are visited for a key.
+/// Capacities are always powers of two, so `mask` is `capacity - 1`.
+pub trait ProbeStrategy {
+    /// Robin Hood keeps every probe sequence ordered by distance from the home slot and deletes
+    /// by shifting the following entries back, so it never leaves tombstones behind.
+    const ROBIN_HOOD: bool = false;
+
+    /// Slot inspected at step `i` of the probe sequence for `hash`, step 0 being the home slot.
+    fn probe(&self, hash: u64, i: usize, mask: usize) -> usize;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LinearProbing;
+
+impl ProbeStrategy for LinearProbing {
+    fn probe(&self, hash: u64, i: usize, mask: usize) -> usize {
+        (hash as usize).wrapping_add(i) & mask
+    }
+}
+
+/// Steps by triangular numbers, which visits every slot of a power-of-two table.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QuadraticProbing;
+
+impl ProbeStrategy for QuadraticProbing {
+    fn probe(&self, hash: u64, i: usize, mask: usize) -> usize {
+        (hash as usize).wrapping_add(i.wrapping_mul(i + 1) / 2) & mask
+    }
+}
+
+/// Steps by a second hash taken from the high bits. The step is odd,
+/// so it is coprime with the power-of-two capacity and visits every slot.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DoubleHashing;
+
+impl ProbeStrategy for DoubleHashing {
+    fn probe(&self, hash: u64, i: usize, mask: usize) -> usize {
+        let step = ((hash >> 32) as usize) | 1;
+        (hash as usize).wrapping_add(i.wrapping_mul(step)) & mask
+    }
+}
+
+/// Linear probing where an insert takes the slot of any entry closer to its home slot.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RobinHood;
+
+impl ProbeStrategy for RobinHood {
+    const ROBIN_HOOD: bool = true;
+
+    fn probe(&self, hash: u64, i: usize, mask: usize) -> usize {
+        (hash as usize).wrapping_add(i) & mask
+    }
+}
+
+/// Occupied entries keep their full hash so rehashing and Robin Hood distances don't rehash keys.
+#[derive(Debug, Clone, PartialEq)]
+enum Slot<K, V> {
+    Occupied(u64, K, V),
+    Deleted,
+    Empty,
+}
+
+/// Number of slots inspected to reach each stored key, 1 meaning the key sits in its home slot.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProbeStats {
+    pub mean: f64,
+    pub max: usize,
+    /// number of keys per probe length
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+impl fmt::Display for ProbeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "mean probe length: {:.3}, max: {}, histogram:",
+            self.mean, self.max
+        )?;
+        for (length, count) in &self.histogram {
+            write!(f, " {}: {}", length, count)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct HashTable<K, V, P = LinearProbing, S = RandomState> {
+    table: Vec<Slot<K, V>>,
+    // entries in both tables
+    size: usize,
+    deleted: usize,
+    // table being drained into `table` by an incremental resize, empty otherwise
+    old: Vec<Slot<K, V>>,
+    old_size: usize,
+    // slots of `old` below this index have been migrated
+    migrated: usize,
+    // slots of `old` migrated per mutating operation, `None` to resize all at once
+    migration_step: Option<usize>,
+    strategy: P,
+    hasher: S,
+    // share of the slots that may be in use before the table grows
+    max_load: f64,
+}
+
+/// Smallest power-of-two capacity that holds `entries` without reaching `max_load`.
+fn capacity_for(entries: usize, max_load: f64) -> usize {
+    let mut capacity = INITIAL_CAPACITY;
+    while entries as f64 >= capacity as f64 * max_load {
+        capacity *= 2;
+    }
+    capacity
+}
+
+fn empty_slots<K, V>(count: usize) -> Vec<Slot<K, V>> {
+    (0..count).map(|_| Slot::Empty).collect()
+}
+
+/// Distance of slot `index` from the home slot of `hash` in a table of `mask + 1` slots.
+fn distance(hash: u64, index: usize, mask: usize) -> usize {
+    index.wrapping_sub(hash as usize) & mask
+}
+
+impl<K, V> HashTable<K, V>
+where
+    K: Hash + Eq,
+{
+    pub fn new() -> Self {
+        Self::with_strategy(LinearProbing)
+    }
+
+    /// Table that holds `capacity` entries before it has to grow.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, P> HashTable<K, V, P>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+{
+    pub fn with_strategy(strategy: P) -> Self {
+        Self::with_strategy_and_hasher(strategy, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashTable<K, V, LinearProbing, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_capacity_and_hasher(0, hasher)
+    }
+
+    /// Table that holds `capacity` entries before it has to grow.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut table = Self::with_strategy_and_hasher(LinearProbing, hasher);
+        table.table = empty_slots(capacity_for(capacity, LOAD_FACTOR));
+        table
+    }
+}
+
+impl<K, V, P, S> HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    pub fn with_strategy_and_hasher(strategy: P, hasher: S) -> Self {
+        HashTable {
+            table: empty_slots(INITIAL_CAPACITY),
+            size: 0,
+            deleted: 0,
+            old: Vec::new(),
+            old_size: 0,
+            migrated: 0,
+            migration_step: None,
+            strategy,
+            hasher,
+            max_load: LOAD_FACTOR,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Number of entries the table holds before it has to grow.
+    pub fn capacity(&self) -> usize {
+        (self.table.len() as f64 * self.max_load).ceil() as usize - 1
+    }
+
+    /// Number of slots, used or not, counting the old table during an incremental resize.
+    pub fn slots(&self) -> usize {
+        self.table.len() + self.old.len()
+    }
+
+    /// Changes the share of slots that may be in use before the table grows, 0.75 by default.
+    pub fn set_max_load_factor(&mut self, max_load: f64) {
+        assert!(
+            max_load > 0.0 && max_load < 1.0,
+            "load factor must be in (0, 1)"
+        );
+        self.max_load = max_load;
+        self.reserve(0);
+    }
+
+    /// Switches to incremental resizing: instead of rehashing every entry at once when the table
+    /// grows, the old table is kept next to the new one and `step` of its slots are moved over
+    /// on every insert, remove, `get_mut` or `entry` call. `None` goes back to resizing all at
+    /// once and finishes any migration in progress.
+    pub fn set_incremental_resize(&mut self, step: Option<usize>) {
+        assert!(step != Some(0), "migration step must be positive");
+        self.migration_step = step;
+        if step.is_none() {
+            self.finish_resize();
+        }
+    }
+
+    /// Whether entries are still waiting in the old table of an incremental resize.
+    pub fn is_resizing(&self) -> bool {
+        !self.old.is_empty()
+    }
+
+    /// Moves every remaining entry out of the old table, completing an incremental resize.
+    pub fn finish_resize(&mut self) {
+        self.migrate(self.old.len());
+    }
+
+    /// Makes room for at least `additional` more entries without growing.
+    /// Finishes an incremental resize first.
+    pub fn reserve(&mut self, additional: usize) {
+        self.finish_resize();
+        let needed = capacity_for(self.size + additional, self.max_load);
+        if needed > self.table.len() {
+            self.rehash(needed);
+        }
+    }
+
+    /// Shrinks the table as far as the load factor allows, dropping all tombstones.
+    pub fn shrink_to_fit(&mut self) {
+        self.finish_resize();
+        self.rehash(capacity_for(self.size, self.max_load));
+    }
+
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.hasher.hash_one(key)
+    }
+
+    fn mask(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    /// Distance of the entry stored at `index` with `hash` from its home slot, Robin Hood only.
+    fn distance(&self, hash: u64, index: usize) -> usize {
+        distance(hash, index, self.mask())
+    }
+
+    /// Slot of `slots` holding `key`. Probing goes past tombstones and only stops at an empty
+    /// slot, or for Robin Hood at the first entry closer to its home slot than `key` would be.
+    fn find_in<Q>(&self, slots: &[Slot<K, V>], hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mask = slots.len().wrapping_sub(1);
+
+        for i in 0..slots.len() {
+            let index = self.strategy.probe(hash, i, mask);
+            match &slots[index] {
+                Slot::Empty => return None,
+                Slot::Occupied(h, k, _) if *h == hash && k.borrow() == key => return Some(index),
+                Slot::Occupied(h, _, _) if P::ROBIN_HOOD && distance(*h, index, mask) < i => {
+                    return None
+                }
+                Slot::Occupied(_, _, _) | Slot::Deleted => {}
+            }
+        }
+        None
+    }
+
+    fn find_hashed<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.find_in(&self.table, hash, key)
+    }
+
+    /// Slot of the old table holding `key` during an incremental resize.
+    /// Migrated slots are left as tombstones, so the probe sequences of the rest stay intact.
+    fn find_old<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.old_size == 0 {
+            return None;
+        }
+        self.find_in(&self.old, hash, key)
+    }
+
+    /// Slot of the current table holding `key`, moving it over from the old table first
+    /// if it hasn't been migrated yet.
+    fn find_current<Q>(&mut self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if let Some(index) = self.find_hashed(hash, key) {
+            return Some(index);
+        }
+        let old_index = self.find_old(hash, key)?;
+        let (hash, key, value) = self.take_old(old_index)?;
+        let index = self.place(hash, key, value);
+        self.release_old_if_drained();
+        Some(index)
+    }
+
+    /// First tombstone or empty slot on the probe path of `hash`. Not used by Robin Hood.
+    fn find_free_slot(&self, hash: u64) -> usize {
+        let mask = self.mask();
+        (0..self.table.len())
+            .map(|i| self.strategy.probe(hash, i, mask))
+            .find(|&index| !matches!(self.table[index], Slot::Occupied(_, _, _)))
+            .expect("hash table has no free slot")
+    }
+
+    /// Robin Hood insertion of a key known to be absent: whenever the carried entry is further
+    /// from home than the resident one, they swap and the resident moves on.
+    /// Returns the slot the new entry ended up in.
+    fn robin_hood_insert(&mut self, hash: u64, key: K, value: V) -> usize {
+        let mask = self.mask();
+        let mut carried = Slot::Occupied(hash, key, value);
+        let mut index = hash as usize & mask;
+        let mut distance = 0;
+        let mut placed_at = None;
+
+        loop {
+            match &self.table[index] {
+                Slot::Empty => {
+                    self.table[index] = carried;
+                    return placed_at.unwrap_or(index);
+                }
+                Slot::Occupied(h, _, _) => {
+                    let resident_distance = self.distance(*h, index);
+                    if resident_distance < distance {
+                        carried = std::mem::replace(&mut self.table[index], carried);
+                        distance = resident_distance;
+                        placed_at.get_or_insert(index);
+                    }
+                }
+                Slot::Deleted => unreachable!("Robin Hood tables hold no tombstones"),
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Writes an entry whose key is absent into the current table without growing it.
+    /// Returns the slot it ended up in.
+    fn place(&mut self, hash: u64, key: K, value: V) -> usize {
+        if P::ROBIN_HOOD {
+            return self.robin_hood_insert(hash, key, value);
+        }
+        let index = self.find_free_slot(hash);
+        if let Slot::Deleted = self.table[index] {
+            self.deleted -= 1;
+        }
+        self.table[index] = Slot::Occupied(hash, key, value);
+        index
+    }
+
+    /// Moves the entries of the current table into a fresh table of `new_capacity` slots,
+    /// dropping all tombstones.
+    fn rehash(&mut self, new_capacity: usize) {
+        let old_table = std::mem::replace(&mut self.table, empty_slots(new_capacity));
+        self.deleted = 0;
+
+        for slot in old_table {
+            if let Slot::Occupied(hash, key, value) = slot {
+                self.place(hash, key, value);
+            }
+        }
+    }
+
+    /// Doubles the table. In incremental mode the current table becomes the old table and is
+    /// migrated by later operations; a resize still in progress is finished first.
+    fn resize(&mut self) {
+        if self.migration_step.is_none() {
+            self.rehash(self.table.len() * 2);
+            return;
+        }
+        self.finish_resize();
+        let capacity = self.table.len() * 2;
+        self.old = std::mem::replace(&mut self.table, empty_slots(capacity));
+        self.old_size = self.size;
+        self.migrated = 0;
+        self.deleted = 0;
+    }
+
+    /// Takes the entry out of slot `index` of the old table, leaving a tombstone.
+    fn take_old(&mut self, index: usize) -> Option<(u64, K, V)> {
+        match std::mem::replace(&mut self.old[index], Slot::Deleted) {
+            Slot::Occupied(hash, key, value) => {
+                self.old_size -= 1;
+                Some((hash, key, value))
+            }
+            slot => {
+                self.old[index] = slot;
+                None
+            }
+        }
+    }
+
+    fn release_old_if_drained(&mut self) {
+        if self.old_size == 0 && !self.old.is_empty() {
+            self.old = Vec::new();
+            self.migrated = 0;
+        }
+    }
+
+    /// Migrates up to `slots` slots of the old table into the current one.
+    fn migrate(&mut self, slots: usize) {
+        let end = (self.migrated + slots).min(self.old.len());
+        while self.migrated < end && self.old_size > 0 {
+            if let Some((hash, key, value)) = self.take_old(self.migrated) {
+                self.place(hash, key, value);
+            }
+            self.migrated += 1;
+        }
+        self.release_old_if_drained();
+    }
+
+    /// The share of an incremental resize done by every mutating operation.
+    fn migrate_step(&mut self) {
+        if let Some(step) = self.migration_step {
+            self.migrate(step);
+        }
+    }
+
+    /// Inserts a key known to be absent, growing or compacting first if needed.
+    /// Returns the slot it was written to.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        if (self.size + self.deleted + 1) as f64 / self.table.len() as f64 >= self.max_load {
+            if (self.size + 1) as f64 / self.table.len() as f64 >= self.max_load {
+                self.resize();
+            } else {
+                self.rehash(self.table.len());
+            }
+        }
+        self.size += 1;
+        self.place(hash, key, value)
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.migrate_step();
+        let hash = self.hash(&key);
+        match self.find_current(hash, &key) {
+            Some(index) => Some(std::mem::replace(self.value_mut(index), value)),
+            None => {
+                self.insert_new(hash, key, value);
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.migrate_step();
+        let hash = self.hash(key);
+        if let Some(index) = self.find_hashed(hash, key) {
+            return Some(self.remove_at(index).1);
+        }
+
+        let (_, _, value) = self.take_old(self.find_old(hash, key)?)?;
+        self.size -= 1;
+        self.release_old_if_drained();
+        Some(value)
+    }
+
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let replacement = if P::ROBIN_HOOD {
+            Slot::Empty
+        } else {
+            Slot::Deleted
+        };
+        let entry = match std::mem::replace(&mut self.table[index], replacement) {
+            Slot::Occupied(_, key, value) => (key, value),
+            Slot::Deleted | Slot::Empty => unreachable!(),
+        };
+        self.size -= 1;
+
+        if P::ROBIN_HOOD {
+            self.backward_shift(index);
+        } else {
+            self.deleted += 1;
+            if self.deleted as f64 > self.table.len() as f64 * TOMBSTONE_FACTOR {
+                self.rehash(self.table.len());
+            }
+        }
+        entry
+    }
+
+    /// Closes the hole left at `index` by moving the following entries one slot back,
+    /// up to the first empty slot or entry already in its home slot.
+    fn backward_shift(&mut self, mut index: usize) {
+        let mask = self.mask();
+        loop {
+            let next = (index + 1) & mask;
+            match &self.table[next] {
+                Slot::Occupied(h, _, _) if self.distance(*h, next) > 0 => {
+                    self.table.swap(index, next);
+                    index = next;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Looks in both tables during an incremental resize, but migrates nothing.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash(key);
+        let slot = match self.find_hashed(hash, key) {
+            Some(index) => &self.table[index],
+            None => &self.old[self.find_old(hash, key)?],
+        };
+        match slot {
+            Slot::Occupied(_, _, v) => Some(v),
+            Slot::Deleted | Slot::Empty => None,
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.migrate_step();
+        let hash = self.hash(key);
+        let index = self.find_current(hash, key)?;
+        Some(self.value_mut(index))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    fn value_mut(&mut self, index: usize) -> &mut V {
+        match self.table[index] {
+            Slot::Occupied(_, _, ref mut v) => v,
+            Slot::Deleted | Slot::Empty => unreachable!(),
+        }
+    }
+
+    /// In-place access to the entry for `key`, inserting only if it is vacant.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, P, S> {
+        self.migrate_step();
+        let hash = self.hash(&key);
+        match self.find_current(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { table: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                table: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    /// Keeps only the entries for which `keep` returns true.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.finish_resize();
+        for slot in &mut self.table {
+            if let Slot::Occupied(_, key, value) = slot {
+                if !keep(key, value) {
+                    *slot = Slot::Deleted;
+                    self.size -= 1;
+                    self.deleted += 1;
+                }
+            }
+        }
+        // Robin Hood can't have tombstones, and backward shifting mid-scan would revisit entries
+        if (P::ROBIN_HOOD && self.deleted > 0)
+            || self.deleted as f64 > self.table.len() as f64 * TOMBSTONE_FACTOR
+        {
+            self.rehash(self.table.len());
+        }
+    }
+
+    /// Probe lengths of all stored keys, replayed from each key's hash.
+    /// Entries still in the old table are measured there.
+    pub fn probe_stats(&self) -> ProbeStats {
+        let mut stats = ProbeStats::default();
+        let mut total = 0;
+
+        for slots in [&self.table, &self.old] {
+            let mask = slots.len().wrapping_sub(1);
+            for (index, slot) in slots.iter().enumerate() {
+                if let Slot::Occupied(hash, _, _) = slot {
+                    let length = (0..slots.len())
+                        .position(|i| self.strategy.probe(*hash, i, mask) == index)
+                        .expect("entry is off its probe sequence")
+                        + 1;
+                    *stats.histogram.entry(length).or_insert(0) += 1;
+                    stats.max = stats.max.max(length);
+                    total += length;
+                }
+            }
+        }
+        if self.size > 0 {
+            stats.mean = total as f64 / self.size as f64;
+        }
+        stats
+    }
+}
+
+impl<K, V, P, S> HashTable<K, V, P, S> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            slots: self.table.iter().chain(self.old.iter()),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            slots: self.table.iter_mut().chain(self.old.iter_mut()),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Removes every entry, handing them out by value. The table keeps its capacity.
+    pub fn drain(&mut self) -> IntoIter<K, V> {
+        let capacity = self.table.len();
+        let slots = std::mem::replace(&mut self.table, empty_slots(capacity));
+        let old = std::mem::take(&mut self.old);
+        self.size = 0;
+        self.deleted = 0;
+        self.old_size = 0;
+        self.migrated = 0;
+        IntoIter {
+            slots: slots.into_iter().chain(old),
+        }
+    }
+}
+
+pub enum Entry<'a, K, V, P, S> {
+    Occupied(OccupiedEntry<'a, K, V, P, S>),
+    Vacant(VacantEntry<'a, K, V, P, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V, P, S> {
+    table: &'a mut HashTable<K, V, P, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, P, S> {
+    table: &'a mut HashTable<K, V, P, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V, P, S> Entry<'a, K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, modify: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            modify(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => &entry.key,
+        }
+    }
+}
+
+impl<'a, K, V, P, S> OccupiedEntry<'a, K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        match self.table.table[self.index] {
+            Slot::Occupied(_, ref key, _) => key,
+            Slot::Deleted | Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn get(&self) -> &V {
+        match self.table.table[self.index] {
+            Slot::Occupied(_, _, ref value) => value,
+            Slot::Deleted | Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.table.value_mut(self.index)
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.table.value_mut(self.index)
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.table.remove_at(self.index).1
+    }
+}
+
+impl<'a, K, V, P, S> VacantEntry<'a, K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.table.insert_new(self.hash, self.key, value);
+        self.table.value_mut(index)
+    }
+}
+
+type Slots<I> = std::iter::Chain<I, I>;
+
+/// Iterators run over the current table, then the old one during an incremental resize.
+pub struct Iter<'a, K, V> {
+    slots: Slots<std::slice::Iter<'a, Slot<K, V>>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| match slot {
+            Slot::Occupied(_, key, value) => Some((key, value)),
+            Slot::Deleted | Slot::Empty => None,
+        })
+    }
+}
+
+pub struct IterMut<'a, K, V> {
+    slots: Slots<std::slice::IterMut<'a, Slot<K, V>>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| match slot {
+            Slot::Occupied(_, key, value) => Some((&*key, value)),
+            Slot::Deleted | Slot::Empty => None,
+        })
+    }
+}
+
+pub struct IntoIter<K, V> {
+    slots: Slots<std::vec::IntoIter<Slot<K, V>>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| match slot {
+            Slot::Occupied(_, key, value) => Some((key, value)),
+            Slot::Deleted | Slot::Empty => None,
+        })
+    }
+}
+
+impl<'a, K, V, P, S> IntoIterator for &'a HashTable<K, V, P, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, P, S> IntoIterator for &'a mut HashTable<K, V, P, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, P, S> IntoIterator for HashTable<K, V, P, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            slots: self.table.into_iter().chain(self.old),
+        }
+    }
+}
+
+impl<K, V, P, S> Map<K, V> for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashTable::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        HashTable::get(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        HashTable::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+
+    fn slots(&self) -> usize {
+        HashTable::slots(self)
+    }
+
+    fn heap_size(&self) -> usize {
+        (self.table.capacity() + self.old.capacity()) * std::mem::size_of::<Slot<K, V>>()
+    }
+}
+
+impl<K, V, P, S> Default for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_strategy_and_hasher(P::default(), S::default())
+    }
+}
+
+impl<K, Q, V, P, S> Index<&Q> for HashTable<K, V, P, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in HashTable")
+    }
+}
+
+impl<K, V, P, S> Extend<(K, V)> for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, P, S> FromIterator<(K, V)> for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut table = Self::default();
+        table.extend(iter);
+        table
+    }
+}
+
+/// Equal when both hold the same keys with equal values, regardless of slot layout.
+impl<K, V, P, S> PartialEq for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, P, S> Eq for HashTable<K, V, P, S>
+where
+    K: Hash + Eq,
+    V: Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, P, S> fmt::Debug for HashTable<K, V, P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::hashers::{FibonacciBuildHasher, FnvBuildHasher, Mix64BuildHasher};
+    use super::super::tests::check_map_against_std;
+    use super::Entry as HashTableEntry;
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashMap;
+    use std::hash::Hasher;
+
+    fn occupied<K, V, P, S>(table: &HashTable<K, V, P, S>) -> usize {
+        table
+            .table
+            .iter()
+            .filter(|entry| matches!(entry, Slot::Occupied(_, _, _)))
+            .count()
+    }
+
+    fn check_against_std<P, S>(strategy: P, hasher: S)
+    where
+        P: ProbeStrategy + Copy,
+        S: BuildHasher + Clone,
+    {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..20 {
+            let mut table = HashTable::with_strategy_and_hasher(strategy, hasher.clone());
+            let mut expected = HashMap::new();
+            // a small key space keeps clusters dense and fills them with tombstones
+            let key_space = rng.gen_range(8..200);
+
+            for _ in 0..5_000 {
+                let key = rng.gen_range(0..key_space);
+                match rng.gen_range(0..5) {
+                    0 => {
+                        let value = rng.gen::<u32>();
+                        assert_eq!(table.insert(key, value), expected.insert(key, value));
+                    }
+                    1 => assert_eq!(table.remove(&key), expected.remove(&key)),
+                    2 => {
+                        let value = rng.gen::<u32>();
+                        let got = *table
+                            .entry(key)
+                            .and_modify(|v| *v = v.wrapping_add(1))
+                            .or_insert(value);
+                        let want = *expected
+                            .entry(key)
+                            .and_modify(|v| *v = v.wrapping_add(1))
+                            .or_insert(value);
+                        assert_eq!(got, want);
+                    }
+                    3 => {
+                        if let Some(value) = table.get_mut(&key) {
+                            *value /= 2;
+                        }
+                        if let Some(value) = expected.get_mut(&key) {
+                            *value /= 2;
+                        }
+                    }
+                    _ => {
+                        assert_eq!(table.get(&key), expected.get(&key));
+                        assert_eq!(table.contains_key(&key), expected.contains_key(&key));
+                    }
+                }
+                assert_eq!(table.len(), expected.len());
+            }
+
+            assert_eq!(occupied(&table), expected.len());
+            for key in 0..key_space {
+                assert_eq!(table.get(&key), expected.get(&key));
+            }
+            let stats = table.probe_stats();
+            assert_eq!(stats.histogram.values().sum::<usize>(), expected.len());
+        }
+    }
+
+    fn check_retain<P: ProbeStrategy + Default>() {
+        let mut table: HashTable<u32, u32, P> = (0..1_000).map(|key| (key, key * 2)).collect();
+        table.retain(|key, value| {
+            *value += 1;
+            key % 3 == 0
+        });
+        assert_eq!(table.len(), 334);
+        assert_eq!(occupied(&table), 334);
+        for key in 0..1_000 {
+            let expected = if key % 3 == 0 {
+                Some(key * 2 + 1)
+            } else {
+                None
+            };
+            assert_eq!(table.get(&key).copied(), expected);
+        }
+        table.insert(1, 1);
+        assert_eq!(table[&1], 1);
+    }
+
+    #[test]
+    fn retain_keeps_matching_entries() {
+        check_retain::<LinearProbing>();
+        check_retain::<QuadraticProbing>();
+        check_retain::<DoubleHashing>();
+        check_retain::<RobinHood>();
+    }
+
+    #[test]
+    fn iteration_visits_every_entry_once() {
+        let mut table: HashTable<String, usize> =
+            (0..500).map(|i| (format!("key{}", i), i)).collect();
+        for i in (0..500).step_by(5) {
+            table.remove(&format!("key{}", i));
+        }
+
+        let mut keys: Vec<usize> = table.keys().map(|key| key[3..].parse().unwrap()).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (0..500).filter(|i| i % 5 != 0).collect::<Vec<_>>());
+        assert_eq!(table.values().sum::<usize>(), keys.iter().sum());
+
+        for (_, value) in table.iter_mut() {
+            *value *= 10;
+        }
+        assert!(table
+            .iter()
+            .all(|(key, &value)| value == key[3..].parse::<usize>().unwrap() * 10));
+
+        let capacity = table.capacity();
+        let mut drained: Vec<(String, usize)> = table.drain().collect();
+        drained.sort_unstable_by_key(|(_, value)| *value);
+        assert_eq!(drained.len(), 400);
+        assert!(table.is_empty());
+        assert_eq!(table.iter().count(), 0);
+        assert_eq!(table.capacity(), capacity);
+    }
+
+    #[test]
+    fn reserve_and_shrink_to_fit() {
+        let mut table = HashTable::new();
+        table.reserve(1_000);
+        let capacity = table.capacity();
+        assert!(capacity >= 1_000);
+        for key in 0..1_000 {
+            table.insert(key, key);
+        }
+        assert_eq!(table.capacity(), capacity);
+
+        table.retain(|&key, _| key < 10);
+        table.shrink_to_fit();
+        assert!(table.capacity() >= 10);
+        assert!(table.capacity() < 20);
+        assert_eq!(table.deleted, 0);
+        assert!((0..10).all(|key| table[&key] == key));
+    }
+
+    #[test]
+    fn entry_counts_words() {
+        let mut counts = HashTable::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(counts["the"], 3);
+        assert_eq!(counts["and"], 2);
+        assert_eq!(counts["cat"], 1);
+
+        match counts.entry("hat") {
+            HashTableEntry::Occupied(entry) => assert_eq!(entry.remove(), 1),
+            HashTableEntry::Vacant(_) => panic!("hat is present"),
+        }
+        assert!(!counts.contains_key("hat"));
+        assert_eq!(*counts.entry("dog").or_insert_with(|| 7), 7);
+    }
+
+    #[test]
+    fn std_traits() {
+        let a: HashTable<u32, &str> = vec![(1, "one"), (2, "two")].into_iter().collect();
+        let mut b = HashTable::new();
+        b.extend(vec![(2, "two"), (1, "one")]);
+        assert_eq!(a, b);
+        b.insert(3, "three");
+        assert_ne!(a, b);
+        assert_eq!(format!("{:?}", HashTable::<u32, u32>::new()), "{}");
+        assert_eq!(
+            format!(
+                "{:?}",
+                a.into_iter().filter(|(k, _)| *k == 1).collect::<Vec<_>>()
+            ),
+            "[(1, \"one\")]"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn index_panics_on_missing_key() {
+        let table: HashTable<u32, u32> = HashTable::new();
+        let _ = table[&1];
+    }
+
+    #[test]
+    fn linear_probing_matches_std_hash_map() {
+        check_against_std(LinearProbing, RandomState::new());
+    }
+
+    #[test]
+    fn quadratic_probing_matches_std_hash_map() {
+        check_against_std(QuadraticProbing, RandomState::new());
+    }
+
+    #[test]
+    fn double_hashing_matches_std_hash_map() {
+        check_against_std(DoubleHashing, RandomState::new());
+    }
+
+    #[test]
+    fn robin_hood_matches_std_hash_map() {
+        check_against_std(RobinHood, RandomState::new());
+    }
+
+    #[test]
+    fn bundled_hashers_match_std_hash_map() {
+        check_against_std(LinearProbing, FnvBuildHasher::with_seed(7));
+        check_against_std(QuadraticProbing, FibonacciBuildHasher::default());
+        check_against_std(DoubleHashing, Mix64BuildHasher::random());
+        check_against_std(RobinHood, FibonacciBuildHasher::with_seed(7));
+    }
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        let fnv = |bytes: &[u8]| {
+            let mut hasher = FnvBuildHasher::default().build_hasher();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(fnv(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn seeds_change_the_hash() {
+        let key = "the same key";
+        assert_eq!(
+            Mix64BuildHasher::with_seed(1).hash_one(key),
+            Mix64BuildHasher::with_seed(1).hash_one(key)
+        );
+        assert_ne!(
+            Mix64BuildHasher::with_seed(1).hash_one(key),
+            Mix64BuildHasher::with_seed(2).hash_one(key)
+        );
+        assert_ne!(
+            FnvBuildHasher::with_seed(1).hash_one(key),
+            FnvBuildHasher::with_seed(2).hash_one(key)
+        );
+    }
+
+    #[test]
+    fn fibonacci_hashing_spreads_keys_differing_in_high_bits() {
+        // identity-like hashing would send all of these to slot 0
+        let mut table = HashTable::with_hasher(FibonacciBuildHasher::default());
+        for i in 0..1_000u64 {
+            table.insert(i << 40, i);
+        }
+        assert!(table.probe_stats().mean < 3.0);
+    }
+
+    #[test]
+    fn with_capacity_does_not_grow() {
+        let mut table = HashTable::with_capacity_and_hasher(1_000, FnvBuildHasher::default());
+        let capacity = table.table.len();
+        for key in 0..1_000 {
+            table.insert(key, ());
+        }
+        assert_eq!(table.table.len(), capacity);
+    }
+
+    #[test]
+    fn high_load_factors_match_std_hash_map() {
+        check_map_against_std(|| {
+            let mut table = HashTable::with_strategy(QuadraticProbing);
+            table.set_max_load_factor(0.95);
+            table
+        });
+        check_map_against_std(|| {
+            let mut table = HashTable::with_strategy(RobinHood);
+            table.set_max_load_factor(0.95);
+            table
+        });
+    }
+
+    fn incremental<P: ProbeStrategy>(strategy: P, step: usize) -> HashTable<u32, u32, P> {
+        let mut table = HashTable::with_strategy(strategy);
+        table.set_incremental_resize(Some(step));
+        table
+    }
+
+    #[test]
+    fn incremental_resizing_matches_std_hash_map() {
+        for step in [1, 3, 16] {
+            check_map_against_std(|| incremental(LinearProbing, step));
+            check_map_against_std(|| incremental(QuadraticProbing, step));
+            check_map_against_std(|| incremental(DoubleHashing, step));
+            check_map_against_std(|| incremental(RobinHood, step));
+        }
+    }
+
+    #[test]
+    fn incremental_resize_migrates_a_bounded_number_of_slots_per_operation() {
+        let mut table = incremental(RobinHood, 8);
+        let mut key = 0;
+        while !table.is_resizing() {
+            table.insert(key, key);
+            key += 1;
+        }
+        let old_slots = table.old.len();
+        assert_eq!(table.table.len(), 2 * old_slots);
+        assert!(table.old_size > 0);
+
+        let mut operations = 0;
+        while table.is_resizing() {
+            let migrated = table.migrated;
+            *table.get_mut(&(operations % key)).unwrap() += 1;
+            assert!(table.migrated <= migrated + 8 || !table.is_resizing());
+            assert_eq!(table.len() as u32, key);
+            operations += 1;
+        }
+        assert!(operations <= old_slots.div_ceil(8) as u32);
+        assert!((0..key).all(|k| table.get(&k) == Some(&(k + u32::from(k < operations)))));
+    }
+
+    #[test]
+    fn lookups_and_iteration_see_both_tables_during_a_resize() {
+        let mut table = incremental(LinearProbing, 1);
+        let mut key = 0;
+        while !table.is_resizing() {
+            table.insert(key, key);
+            key += 1;
+        }
+        assert!(table.old_size > 0 && occupied(&table) > 0);
+        assert!((0..key).all(|k| table.contains_key(&k)));
+
+        let mut keys: Vec<u32> = table.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (0..key).collect::<Vec<_>>());
+        assert_eq!(
+            table.probe_stats().histogram.values().sum::<usize>(),
+            key as usize
+        );
+
+        // removing from the old table and re-inserting must not leave a duplicate behind
+        let stale = (0..key)
+            .find(|k| table.find_old(table.hash(k), k).is_some())
+            .unwrap();
+        assert_eq!(table.remove(&stale), Some(stale));
+        assert_eq!(table.insert(stale, 0), None);
+        assert_eq!(table.iter().filter(|(&k, _)| k == stale).count(), 1);
+
+        table.finish_resize();
+        assert!(!table.is_resizing());
+        assert_eq!(occupied(&table), key as usize);
+        assert_eq!(table.slots(), table.table.len());
+        assert!((0..key).all(|k| table.contains_key(&k)));
+
+        table.set_incremental_resize(None);
+        for k in key..key * 4 {
+            table.insert(k, k);
+            assert!(!table.is_resizing());
+        }
+    }
+
+    #[test]
+    fn robin_hood_leaves_no_tombstones() {
+        let mut table = HashTable::with_strategy(RobinHood);
+        for key in 0..1_000 {
+            table.insert(key, key);
+        }
+        for key in (0..1_000).step_by(2) {
+            assert_eq!(table.remove(&key), Some(key));
+        }
+        assert_eq!(table.deleted, 0);
+        assert!(!table.table.contains(&Slot::Deleted));
+
+        // every probe sequence stays ordered: no entry is further from home than its successor + 1
+        let mask = table.mask();
+        for index in 0..table.table.len() {
+            if let Slot::Occupied(hash, _, _) = table.table[(index + 1) & mask] {
+                let next_distance = table.distance(hash, (index + 1) & mask);
+                let distance = match table.table[index] {
+                    Slot::Occupied(h, _, _) => table.distance(h, index),
+                    _ => 0,
+                };
+                assert!(next_distance <= distance + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn keys_behind_a_tombstone_stay_reachable() {
+        let mut table = HashTable::new();
+        for key in 0..12 {
+            table.insert(key, key);
+        }
+        for key in (0..12).step_by(2) {
+            assert_eq!(table.remove(&key), Some(key));
+        }
+        for key in 0..12 {
+            let expected = if key % 2 == 0 { None } else { Some(&key) };
+            assert_eq!(table.get(&key), expected);
+        }
+
+        // re-inserting a surviving key must update it instead of adding a duplicate
+        for key in (1..12).step_by(2) {
+            table.insert(key, key * 10);
+        }
+        assert_eq!(table.size, 6);
+        assert_eq!(occupied(&table), 6);
+        for key in (1..12).step_by(2) {
+            assert_eq!(table.get(&key), Some(&(key * 10)));
+        }
+    }
+
+    #[test]
+    fn tombstones_are_compacted() {
+        let mut table = HashTable::new();
+        for key in 0..10_000 {
+            table.insert(key, ());
+            assert_eq!(table.remove(&key), Some(()));
+            assert!(table.deleted as f64 <= table.table.len() as f64 * TOMBSTONE_FACTOR);
+        }
+        assert_eq!(table.size, 0);
+        assert_eq!(table.table.len(), INITIAL_CAPACITY);
+    }
+}
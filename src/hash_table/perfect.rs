@@ -0,0 +1,346 @@
+// Minimal perfect hashing for key sets known up front, shared by the hash and probabilistic
+// binaries.
+
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+use rand::Rng;
+
+use super::codec::{take, DecodeError, Encode};
+use super::hashers::{fmix64, Mix64BuildHasher};
+
+/// Average number of keys per bucket. Larger buckets mean fewer displacements to store
+/// but longer searches when building.
+const LAMBDA: usize = 5;
+const MAGIC: &[u8; 4] = b"CHD1";
+
+/// Read-only map over a fixed key set, built with CHD (compress, hash and displace):
+/// keys are hashed into buckets, and every bucket gets a displacement pair that sends its
+/// keys to distinct free slots. There are exactly as many slots as keys, and a lookup
+/// reads one displacement and compares one key, whatever the key set.
+pub struct StaticHashMap<K, V> {
+    seed: u64,
+    displacements: Displacements,
+    entries: Vec<(K, V)>,
+}
+
+/// `(d0, d1)` per bucket; a key with hash parts `f1`, `f2` sits at `f1 + d0 * f2 + d1`.
+type Displacements = Vec<(u32, u32)>;
+
+fn hash_with<Q: Hash + ?Sized>(seed: u64, key: &Q) -> u64 {
+    Mix64BuildHasher::with_seed(seed).hash_one(key)
+}
+
+/// The bucket comes from the high half of the hash, the slot from the low half and a remix.
+fn bucket_of(hash: u64, buckets: usize) -> usize {
+    (hash >> 32) as usize % buckets
+}
+
+fn slot_of(hash: u64, (d0, d1): (u32, u32), slots: usize) -> usize {
+    let f1 = hash & 0xffff_ffff;
+    let f2 = fmix64(hash) >> 32;
+    (f1.wrapping_add((d0 as u64).wrapping_mul(f2))
+        .wrapping_add(d1 as u64)
+        % slots as u64) as usize
+}
+
+/// Displacements placing every key of `hashes` in its own slot, largest buckets first,
+/// and the slot each key got. `None` if some bucket fits nowhere, e.g. on a full-hash collision.
+fn displace(hashes: &[u64], buckets: usize) -> Option<(Displacements, Vec<usize>)> {
+    let slots = hashes.len();
+    let mut members = vec![Vec::new(); buckets];
+    for (key, &hash) in hashes.iter().enumerate() {
+        members[bucket_of(hash, buckets)].push(key);
+    }
+    let mut order: Vec<usize> = (0..buckets).collect();
+    order.sort_unstable_by_key(|&bucket| Reverse(members[bucket].len()));
+
+    let mut displacements = vec![(0, 0); buckets];
+    let mut slot_of_key = vec![0; slots];
+    let mut taken = vec![false; slots];
+    // slots claimed by the current attempt are stamped with its number,
+    // so nothing has to be cleared between attempts
+    let mut stamps = vec![0u64; slots];
+    let mut attempt = 0;
+    let mut placed = Vec::new();
+
+    'buckets: for bucket in order {
+        if members[bucket].is_empty() {
+            break;
+        }
+        for d0 in 0..slots as u32 {
+            for d1 in 0..slots as u32 {
+                attempt += 1;
+                placed.clear();
+                let fits = members[bucket].iter().all(|&key| {
+                    let slot = slot_of(hashes[key], (d0, d1), slots);
+                    if taken[slot] || stamps[slot] == attempt {
+                        return false;
+                    }
+                    stamps[slot] = attempt;
+                    placed.push((key, slot));
+                    true
+                });
+                if fits {
+                    for &(key, slot) in &placed {
+                        taken[slot] = true;
+                        slot_of_key[key] = slot;
+                    }
+                    displacements[bucket] = (d0, d1);
+                    continue 'buckets;
+                }
+            }
+        }
+        return None;
+    }
+    Some((displacements, slot_of_key))
+}
+
+impl<K: Hash + Eq, V> StaticHashMap<K, V> {
+    /// Builds the map from `entries`; of several entries with the same key the last one wins.
+    pub fn new<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let entries: Vec<(K, V)> = entries
+            .into_iter()
+            .collect::<HashMap<K, V>>()
+            .into_iter()
+            .collect();
+        let buckets = entries.len().div_ceil(LAMBDA).max(1);
+        let mut rng = rand::thread_rng();
+
+        loop {
+            let seed = rng.gen();
+            let hashes: Vec<u64> = entries
+                .iter()
+                .map(|(key, _)| hash_with(seed, key))
+                .collect();
+            let Some((displacements, slots)) = displace(&hashes, buckets) else {
+                continue;
+            };
+
+            let mut ordered: Vec<Option<(K, V)>> = entries.iter().map(|_| None).collect();
+            for (entry, slot) in entries.into_iter().zip(slots) {
+                ordered[slot] = Some(entry);
+            }
+            return StaticHashMap {
+                seed,
+                displacements,
+                entries: ordered.into_iter().map(Option::unwrap).collect(),
+            };
+        }
+    }
+
+    /// The only slot `key` can be in.
+    fn slot<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let hash = hash_with(self.seed, key);
+        let displacement = self.displacements[bucket_of(hash, self.displacements.len())];
+        Some(slot_of(hash, displacement, self.entries.len()))
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (k, v) = &self.entries[self.slot(key)?];
+        (k.borrow() == key).then_some(v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K, V> StaticHashMap<K, V> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Encode, V: Encode> StaticHashMap<K, V> {
+    /// The built table: seed, displacements and entries in slot order. Keys are hashed with
+    /// native byte order, so the bytes load on machines of the same endianness.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        self.seed.encode(&mut bytes);
+        (self.displacements.len() as u64).encode(&mut bytes);
+        for &(d0, d1) in &self.displacements {
+            d0.encode(&mut bytes);
+            d1.encode(&mut bytes);
+        }
+        (self.entries.len() as u64).encode(&mut bytes);
+        for (key, value) in &self.entries {
+            key.encode(&mut bytes);
+            value.encode(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Loads a table written by `to_bytes` as is, without rehashing any key.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        if take(&mut bytes, MAGIC.len())? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let seed = u64::decode(&mut bytes)?;
+        let buckets = u64::decode(&mut bytes)? as usize;
+        let displacements = (0..buckets)
+            .map(|_| Ok((u32::decode(&mut bytes)?, u32::decode(&mut bytes)?)))
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+        let len = u64::decode(&mut bytes)? as usize;
+        let entries = (0..len)
+            .map(|_| Ok((K::decode(&mut bytes)?, V::decode(&mut bytes)?)))
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        if len > 0 && buckets == 0 {
+            return Err(DecodeError::Invalid("no buckets"));
+        }
+        Ok(StaticHashMap {
+            seed,
+            displacements,
+            entries,
+        })
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for StaticHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl<K, Q, V> Index<&Q> for StaticHashMap<K, V>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in StaticHashMap")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for StaticHashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(count: usize) -> Vec<String> {
+        let mut rng = rand::thread_rng();
+        (0..count)
+            .map(|_| {
+                let length = rng.gen_range(3..10);
+                (0..length)
+                    .map(|_| rng.gen_range(b'a'..=b'z') as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_key_has_its_own_slot() {
+        for count in [0, 1, 2, 7, 100, 20_000] {
+            let map: StaticHashMap<u64, u64> = (0..count).map(|key| (key * 3, key)).collect();
+            assert_eq!(map.len(), count as usize);
+            assert_eq!(
+                map.displacements.len(),
+                (count as usize).div_ceil(LAMBDA).max(1)
+            );
+            for key in 0..count {
+                assert_eq!(
+                    map.slot(&(key * 3)).map(|slot| map.entries[slot].0),
+                    Some(key * 3)
+                );
+                assert_eq!(map[&(key * 3)], key);
+                assert_eq!(map.get(&(key * 3 + 1)), None);
+            }
+        }
+    }
+
+    #[test]
+    fn later_duplicates_win() {
+        let map = StaticHashMap::new(vec![("a", 1), ("b", 2), ("a", 3)]);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], 3);
+        assert_eq!(map["b"], 2);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let words = words(5_000);
+        let map: StaticHashMap<String, Vec<u32>> = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| (word.clone(), vec![index as u32; word.len()]))
+            .collect();
+        let bytes = map.to_bytes();
+        let loaded = StaticHashMap::<String, Vec<u32>>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(loaded.seed, map.seed);
+        assert_eq!(loaded.len(), map.len());
+        for (key, value) in map.iter() {
+            assert_eq!(loaded.get(key.as_str()), Some(value));
+        }
+        assert!(!loaded.contains_key("not a generated word"));
+        assert_eq!(loaded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn rejects_malformed_bytes() {
+        let map: StaticHashMap<u32, bool> = (0..100).map(|key| (key, key % 2 == 0)).collect();
+        let bytes = map.to_bytes();
+        let load = StaticHashMap::<u32, bool>::from_bytes;
+
+        assert_eq!(
+            load(&bytes[..bytes.len() - 1]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(load(b"CHD0").unwrap_err(), DecodeError::BadMagic);
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(load(&longer).unwrap_err(), DecodeError::TrailingBytes);
+        let mut bad_bool = bytes.clone();
+        *bad_bool.last_mut().unwrap() = 2;
+        assert!(matches!(load(&bad_bool), Err(DecodeError::Invalid(_))));
+        assert!(load(&bytes).is_ok());
+    }
+}
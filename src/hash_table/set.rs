@@ -0,0 +1,269 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+use super::open_addressing::{HashTable, LinearProbing, ProbeStrategy};
+
+/// A set of values, stored as the keys of a `HashTable<T, ()>`.
+pub struct HashSet<T, P = LinearProbing, S = RandomState> {
+    table: HashTable<T, (), P, S>,
+}
+
+impl<T: Hash + Eq> HashSet<T> {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        HashSet {
+            table: HashTable::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_capacity(capacity: usize) -> Self {
+        HashSet {
+            table: HashTable::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, P, S> HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    #[allow(dead_code)]
+    pub fn with_strategy_and_hasher(strategy: P, hasher: S) -> Self {
+        HashSet {
+            table: HashTable::with_strategy_and_hasher(strategy, hasher),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Adds `value`, returning false if it was already present.
+    #[allow(dead_code)]
+    pub fn insert(&mut self, value: T) -> bool {
+        self.table.insert(value, ()).is_none()
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.contains_key(value)
+    }
+
+    /// Removes `value`, returning whether it was present.
+    #[allow(dead_code)]
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.remove(value).is_some()
+    }
+
+    #[allow(dead_code)]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        self.table.retain(|value, _| keep(value));
+    }
+
+    /// Values in `self` or `other`, each once.
+    #[allow(dead_code)]
+    pub fn union<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a T> {
+        self.iter()
+            .chain(other.iter().filter(move |value| !self.contains(*value)))
+    }
+
+    /// Values in both sets. Walks the smaller set and probes the larger one.
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a T> {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small.iter().filter(move |value| large.contains(*value))
+    }
+
+    /// Values in `self` but not in `other`.
+    #[allow(dead_code)]
+    pub fn difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a T> {
+        self.iter().filter(move |value| !other.contains(*value))
+    }
+
+    /// Values in exactly one of the two sets.
+    #[allow(dead_code)]
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a T> {
+        self.difference(other).chain(other.difference(self))
+    }
+
+    #[allow(dead_code)]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|value| other.contains(value))
+    }
+
+    #[allow(dead_code)]
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<T, P, S> HashSet<T, P, S> {
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.table.keys()
+    }
+
+    #[allow(dead_code)]
+    pub fn drain(&mut self) -> impl Iterator<Item = T> {
+        self.table.drain().map(|(value, _)| value)
+    }
+}
+
+impl<T, P, S> Default for HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        HashSet {
+            table: HashTable::default(),
+        }
+    }
+}
+
+impl<T, P, S> IntoIterator for HashSet<T, P, S> {
+    type Item = T;
+    type IntoIter = std::iter::Map<super::open_addressing::IntoIter<T, ()>, fn((T, ())) -> T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.table.into_iter().map(|(value, _)| value)
+    }
+}
+
+impl<T, P, S> Extend<T> for HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.table.extend(iter.into_iter().map(|value| (value, ())));
+    }
+}
+
+impl<T, P, S> FromIterator<T> for HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy + Default,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T, P, S> PartialEq for HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.table == other.table
+    }
+}
+
+impl<T, P, S> Eq for HashSet<T, P, S>
+where
+    T: Hash + Eq,
+    P: ProbeStrategy,
+    S: BuildHasher,
+{
+}
+
+impl<T: fmt::Debug, P, S> fmt::Debug for HashSet<T, P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::open_addressing::RobinHood;
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashSet as StdHashSet;
+
+    fn sorted<'a>(values: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+        let mut values: Vec<u32> = values.copied().collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn set_operations_match_std_hash_set() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let left: Vec<u32> = (0..rng.gen_range(0..300))
+                .map(|_| rng.gen_range(0..400))
+                .collect();
+            let right: Vec<u32> = (0..rng.gen_range(0..300))
+                .map(|_| rng.gen_range(0..400))
+                .collect();
+            let a: HashSet<u32> = left.iter().copied().collect();
+            let b: HashSet<u32> = right.iter().copied().collect();
+            let std_a: StdHashSet<u32> = left.into_iter().collect();
+            let std_b: StdHashSet<u32> = right.into_iter().collect();
+
+            assert_eq!(a.len(), std_a.len());
+            assert_eq!(sorted(a.union(&b)), sorted(std_a.union(&std_b)));
+            assert_eq!(
+                sorted(a.intersection(&b)),
+                sorted(std_a.intersection(&std_b))
+            );
+            assert_eq!(sorted(a.difference(&b)), sorted(std_a.difference(&std_b)));
+            assert_eq!(
+                sorted(a.symmetric_difference(&b)),
+                sorted(std_a.symmetric_difference(&std_b))
+            );
+            assert_eq!(a.is_subset(&b), std_a.is_subset(&std_b));
+            assert_eq!(a.is_disjoint(&b), std_a.is_disjoint(&std_b));
+        }
+    }
+
+    #[test]
+    fn insert_remove_and_subsets() {
+        let mut set = HashSet::with_strategy_and_hasher(RobinHood, RandomState::new());
+        assert!(set.insert("apple"));
+        assert!(set.insert("pear"));
+        assert!(!set.insert("apple"));
+        assert!(set.contains("pear"));
+        assert!(set.remove("pear"));
+        assert!(!set.remove("pear"));
+        assert_eq!(set.len(), 1);
+
+        let mut superset = HashSet::with_strategy_and_hasher(RobinHood, RandomState::new());
+        superset.extend(["apple", "plum"]);
+        assert!(set.is_subset(&superset));
+        assert!(superset.is_superset(&set));
+        assert!(!superset.is_subset(&set));
+        assert_eq!(format!("{:?}", set), "{\"apple\"}");
+    }
+}
@@ -0,0 +1,341 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Deref;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::open_addressing::{HashTable, LinearProbing};
+
+type Shard<K, V, S> = HashTable<K, V, LinearProbing, S>;
+
+/// Hash table that can be shared between threads. Keys are spread over a power-of-two number
+/// of shards by the top bits of their hash, and every shard sits behind its own `RwLock`,
+/// so threads working on different shards don't wait for each other.
+pub struct ShardedHashTable<K, V, S = RandomState> {
+    shards: Vec<RwLock<Shard<K, V, S>>>,
+    hasher: S,
+    // log2 of the shard count
+    shard_bits: u32,
+}
+
+/// Read access to a value, holding its shard's read lock until dropped.
+#[allow(dead_code)]
+pub struct Ref<'a, K, V, S> {
+    _guard: RwLockReadGuard<'a, Shard<K, V, S>>,
+    value: *const V,
+}
+
+impl<K, V, S> Deref for Ref<'_, K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        // SAFETY: `value` points into the shard `_guard` keeps read-locked,
+        // so the table can't be changed or moved while the guard lives.
+        unsafe { &*self.value }
+    }
+}
+
+/// A few shards per core keeps the chance of two threads hitting the same shard low.
+fn default_shard_count() -> usize {
+    let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+    (cores * 4).next_power_of_two()
+}
+
+impl<K: Hash + Eq, V> ShardedHashTable<K, V> {
+    pub fn new() -> Self {
+        Self::with_shards(default_shard_count())
+    }
+
+    /// Table with `shards` shards, rounded up to a power of two.
+    pub fn with_shards(shards: usize) -> Self {
+        Self::with_shards_and_hasher(shards, RandomState::new())
+    }
+}
+
+impl<K, V, S> ShardedHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    /// Table with `shards` shards, rounded up to a power of two, all hashing with `hasher`.
+    pub fn with_shards_and_hasher(shards: usize, hasher: S) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        ShardedHashTable {
+            shards: (0..shards)
+                .map(|_| RwLock::new(HashTable::with_hasher(hasher.clone())))
+                .collect(),
+            hasher,
+            shard_bits: shards.trailing_zeros(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Sum of the shard sizes. Shards are counted one after another,
+    /// so with concurrent writers this is only a snapshot.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|index| self.read_shard(index).len())
+            .sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Shard of `key`, picked by the top bits of its hash. The tables inside the shards
+    /// index slots by the low bits, so the two choices stay independent.
+    fn shard_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key);
+        hash.rotate_left(self.shard_bits) as usize & (self.shards.len() - 1)
+    }
+
+    fn read_shard(&self, index: usize) -> RwLockReadGuard<'_, Shard<K, V, S>> {
+        self.shards[index].read().expect("shard lock poisoned")
+    }
+
+    fn write_shard(&self, index: usize) -> RwLockWriteGuard<'_, Shard<K, V, S>> {
+        self.shards[index].write().expect("shard lock poisoned")
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced.
+    #[allow(dead_code)]
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write_shard(self.shard_index(&key)).insert(key, value)
+    }
+
+    #[allow(dead_code)]
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.write_shard(self.shard_index(key)).remove(key)
+    }
+
+    /// Read access to the value under `key`. Writers to the same shard
+    /// block until the returned guard is dropped.
+    #[allow(dead_code)]
+    pub fn read<Q>(&self, key: &Q) -> Option<Ref<'_, K, V, S>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let guard = self.read_shard(self.shard_index(key));
+        let value: *const V = guard.get(key)?;
+        Some(Ref {
+            _guard: guard,
+            value,
+        })
+    }
+
+    /// Copy of the value under `key`, without keeping the shard locked.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.read_shard(self.shard_index(key)).get(key).cloned()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read_shard(self.shard_index(key)).contains_key(key)
+    }
+
+    /// Runs `f` on the value under `key` while holding the shard's write lock, so the
+    /// read-modify-write is atomic. Returns what `f` returned, or `None` if `key` is absent.
+    #[allow(dead_code)]
+    pub fn update<Q, R, F>(&self, key: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&mut V) -> R,
+    {
+        self.write_shard(self.shard_index(key)).get_mut(key).map(f)
+    }
+
+    /// Like `update`, but inserts `default` first if `key` is absent.
+    pub fn upsert<R, F>(&self, key: K, default: V, f: F) -> R
+    where
+        F: FnOnce(&mut V) -> R,
+    {
+        let mut shard = self.write_shard(self.shard_index(&key));
+        f(shard.entry(key).or_insert(default))
+    }
+
+    /// Copies of all entries. Each shard is copied in one go under its read lock, so the
+    /// entries of a shard are a consistent snapshot, but different shards may be taken
+    /// before and after a concurrent write.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> Iter<'_, K, V, S>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        Iter {
+            table: self,
+            next_shard: 0,
+            entries: Vec::new().into_iter(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub struct Iter<'a, K, V, S> {
+    table: &'a ShardedHashTable<K, V, S>,
+    next_shard: usize,
+    entries: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V, S> Iterator for Iter<'_, K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(entry);
+            }
+            if self.next_shard == self.table.shards.len() {
+                return None;
+            }
+            let shard = self.table.read_shard(self.next_shard);
+            self.entries = shard
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Vec<_>>()
+                .into_iter();
+            self.next_shard += 1;
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ShardedHashTable<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    #[test]
+    fn single_threaded_operations() {
+        let table = ShardedHashTable::with_shards(5);
+        assert_eq!(table.shard_count(), 8);
+        for key in 0..1_000 {
+            assert_eq!(table.insert(key, key), None);
+        }
+        assert_eq!(table.len(), 1_000);
+        assert_eq!(table.insert(7, 70), Some(7));
+        assert_eq!(*table.read(&7).unwrap(), 70);
+        assert_eq!(table.get(&8), Some(8));
+        assert_eq!(
+            table.update(&8, |value| std::mem::replace(value, 0)),
+            Some(8)
+        );
+        assert_eq!(table.update(&1_000, |value| *value), None);
+        assert_eq!(table.upsert(1_000, 5, |value| *value), 5);
+        assert_eq!(table.remove(&1_000), Some(5));
+        assert!(!table.contains_key(&1_000));
+
+        // the keys are spread over every shard
+        for index in 0..table.shard_count() {
+            assert!(table.read_shard(index).len() > 50);
+        }
+        let mut entries: Vec<_> = table.iter().collect();
+        entries.sort_unstable();
+        assert_eq!(entries.len(), 1_000);
+        assert_eq!(entries[7], (7, 70));
+        assert_eq!(entries[8], (8, 0));
+    }
+
+    #[test]
+    fn concurrent_stress() {
+        const THREADS: u32 = 8;
+        const KEYS_PER_THREAD: u32 = 2_000;
+        let table = ShardedHashTable::with_shards(4);
+        let done = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            // readers check that snapshots never show a half-written entry
+            let reader = scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    for (key, value) in table.iter() {
+                        assert!(key == u32::MAX || value % 1_000_000 == key);
+                    }
+                }
+            });
+
+            let writers: Vec<_> = (0..THREADS)
+                .map(|thread| {
+                    let table = &table;
+                    scope.spawn(move || {
+                        let mut rng = rand::thread_rng();
+                        let mut expected = HashMap::new();
+                        let keys = thread * KEYS_PER_THREAD..(thread + 1) * KEYS_PER_THREAD;
+                        for _ in 0..20_000 {
+                            let key = rng.gen_range(keys.clone());
+                            match rng.gen_range(0..4) {
+                                0 => assert_eq!(table.insert(key, key), expected.insert(key, key)),
+                                1 => assert_eq!(table.remove(&key), expected.remove(&key)),
+                                2 => {
+                                    let updated = table.update(&key, |value| {
+                                        *value += 1_000_000;
+                                        *value
+                                    });
+                                    let want = expected.get_mut(&key).map(|value| {
+                                        *value += 1_000_000;
+                                        *value
+                                    });
+                                    assert_eq!(updated, want);
+                                }
+                                _ => assert_eq!(table.get(&key), expected.get(&key).copied()),
+                            }
+                            // a counter every thread bumps, shared across shards' locks
+                            table.upsert(u32::MAX, 0, |count| *count += 1);
+                        }
+                        expected
+                    })
+                })
+                .collect();
+
+            let mut expected: HashMap<u32, u32> = HashMap::new();
+            for writer in writers {
+                expected.extend(writer.join().unwrap());
+            }
+            done.store(true, Ordering::Relaxed);
+            reader.join().unwrap();
+
+            assert_eq!(table.get(&u32::MAX), Some(THREADS * 20_000));
+            assert_eq!(table.len(), expected.len() + 1);
+            assert!(expected
+                .iter()
+                .all(|(key, value)| table.get(key) == Some(*value)));
+        });
+    }
+}
@@ -1,92 +1,142 @@
-fn build_kmp_automaton(pattern: &str) -> Vec<Vec<usize>> {
+mod matcher;
+use matcher::{prefix_function, Matcher, Symbol, SymbolTable};
+mod substring_index;
+use substring_index::SubstringIndex;
+mod suffix_array;
+use suffix_array::{longest_common_substring, SuffixArray};
+mod suffix_automaton;
+use suffix_automaton::SuffixAutomaton;
+mod suffix_tree;
+use suffix_tree::SuffixTree;
+
+fn build_kmp_automaton<T: Symbol>(pattern: &[T]) -> Vec<T::Table> {
     let m = pattern.len();
-    let mut automaton = vec![vec![0; 256]; m + 1];
-    let mut lps = vec![0; m + 1];
-    
-    // Making the LPS table (Longest Proper Prefix which is also Suffix)
-    let mut len = 0;
-    let mut i = 1;
-    while i < m {
-        if pattern.as_bytes()[i] == pattern.as_bytes()[len] {
-            len += 1;
-            lps[i] = len;
-            i += 1;
-        } else if len != 0 {
-            len = lps[len - 1];
-        } else {
-            lps[i] = 0;
-            i += 1;
+    let mut automaton: Vec<T::Table> = Vec::with_capacity(m + 1);
+    let lps = prefix_function(pattern);
+
+    // Symbols of the pattern, each once; any other symbol leads back to state 0
+    let mut symbols: Vec<&T> = Vec::new();
+    for symbol in pattern {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
         }
     }
-    
+
     // Making the automaton
-    for j in 0..256 {
-        automaton[0][j] = 0;
-    }
-    automaton[0][pattern.as_bytes()[0] as usize] = 1;
-    
-    for i in 1..=m {
-        for j in 0..256 {
-            if i < m && j == pattern.as_bytes()[i] as usize {
-                automaton[i][j] = i + 1;
-            } else {
-                automaton[i][j] = automaton[lps[i - 1]][j];
+    for i in 0..=m {
+        let mut row = T::Table::new(0);
+        for &symbol in &symbols {
+            if i < m && *symbol == pattern[i] {
+                row.set(symbol, i + 1);
+            } else if i > 0 {
+                row.set(symbol, automaton[lps[i - 1]].get(symbol));
             }
         }
+        automaton.push(row);
     }
-    
+
     automaton
 }
-fn kmp_search_automaton(text: &str, pattern: &str) -> Option<usize> {
-    let automaton = build_kmp_automaton(pattern);
-    let mut state = 0;
 
-    for (i, &c) in text.as_bytes().iter().enumerate() {
-        state = automaton[state][c as usize];
-        if state == pattern.len() {
-            return Some(i - pattern.len() + 1);
+/// Knuth-Morris-Pratt: scans the text once, and on a mismatch falls back to the longest
+/// border of the part of the pattern matched so far instead of moving back in the text.
+pub struct Kmp<T> {
+    pattern: Vec<T>,
+    prefix: Vec<usize>,
+}
+
+impl<T: Eq + Clone> Kmp<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        Kmp {
+            pattern: pattern.to_vec(),
+            prefix: prefix_function(pattern),
         }
     }
+}
 
-    None
+impl<T: Eq> Kmp<T> {
+    /// Scans `text` from `start` with the first `matched` symbols of the pattern already
+    /// matched.
+    fn scan(&self, text: &[T], start: usize, mut matched: usize) -> Option<usize> {
+        for (i, symbol) in text.iter().enumerate().skip(start) {
+            while matched > 0 && self.pattern[matched] != *symbol {
+                matched = self.prefix[matched - 1];
+            }
+            if self.pattern[matched] == *symbol {
+                matched += 1;
+            }
+            if matched == self.pattern.len() {
+                return Some(i + 1 - matched);
+            }
+        }
+        None
+    }
 }
 
+impl<T: Eq> Matcher<T> for Kmp<T> {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
 
-fn kmp_prefix_function(pattern: &str) -> Vec<usize> {
-    let m = pattern.len();
-    let mut pi = vec![0; m];
-    let mut k = 0;
-    
-    for i in 1..m {
-        while k > 0 && pattern.chars().nth(k) != pattern.chars().nth(i) {
-            k = pi[k - 1];
-        }
-        if pattern.chars().nth(k) == pattern.chars().nth(i) {
-            k += 1;
-        }
-        pi[i] = k;
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        self.scan(text, start, 0)
+    }
+
+    /// Carries on after the match with its longest border matched, so overlapping matches are
+    /// found in one pass too.
+    fn search_after(&self, text: &[T], previous: usize) -> Option<usize> {
+        let len = self.pattern.len();
+        self.scan(text, previous + len, self.prefix[len - 1])
     }
-    
-    pi
 }
 
-fn kmp_search(text: &str, pattern: &str) -> Option<usize> {
-    let pi = kmp_prefix_function(pattern);
-    let mut j = 0;
-    
-    for (i, c) in text.char_indices() {
-        while j > 0 && pattern.chars().nth(j) != Some(c) {
-            j = pi[j - 1];
-        }
-        if pattern.chars().nth(j) == Some(c) {
-            j += 1;
+/// KMP with the fallbacks folded into a transition table, one row per number of matched
+/// symbols, so each text symbol costs one lookup.
+pub struct KmpAutomaton<T: Symbol> {
+    automaton: Vec<T::Table>,
+    len: usize,
+}
+
+impl<T: Symbol> KmpAutomaton<T> {
+    pub fn new(pattern: &[T]) -> Self {
+        KmpAutomaton {
+            automaton: build_kmp_automaton(pattern),
+            len: pattern.len(),
         }
-        if j == pattern.len() {
-            return Some(i - pattern.len() + 1);
+    }
+
+    fn scan(&self, text: &[T], start: usize, mut state: usize) -> Option<usize> {
+        for (i, symbol) in text.iter().enumerate().skip(start) {
+            state = self.automaton[state].get(symbol);
+            if state == self.len {
+                return Some(i + 1 - self.len);
+            }
         }
+        None
     }
-    
-    None
+}
+
+impl<T: Symbol> Matcher<T> for KmpAutomaton<T> {
+    fn pattern_len(&self) -> usize {
+        self.len
+    }
+
+    fn search(&self, text: &[T], start: usize) -> Option<usize> {
+        self.scan(text, start, 0)
+    }
+
+    /// The row for a full match leads on to the overlapping ones.
+    fn search_after(&self, text: &[T], previous: usize) -> Option<usize> {
+        self.scan(text, previous + self.len, self.len)
+    }
+}
+
+fn kmp_search_automaton(text: &str, pattern: &str) -> Option<usize> {
+    KmpAutomaton::new(pattern.as_bytes()).find(text.as_bytes())
+}
+
+fn kmp_search(text: &str, pattern: &str) -> Option<usize> {
+    Kmp::new(pattern.as_bytes()).find(text.as_bytes())
 }
 
 use std::time::Instant;
@@ -137,4 +187,121 @@ fn main() {
             println!("| Case {:<4} | {} | {} |", i + 1, automaton_result, kmp_result);
         }
     }
-}
\ No newline at end of file
+
+    // Precompiled matchers: the prefix function and the automaton are built once per pattern
+    let texts: Vec<&[u8]> = test_cases.iter().map(|(text, _)| text.as_bytes()).collect();
+    println!("\n| {:^7} | {:^7} | {:^11} | {:^26} |", "Pattern", "Matches", "Overlapping", "First matches in case 2");
+    println!("|{:-^9}|{:-^9}|{:-^13}|{:-^28}|", "", "", "", "");
+    for pattern in ["aaa", "aab", "or", "ut", "dolor"] {
+        let kmp = Kmp::new(pattern.as_bytes());
+        let automaton = KmpAutomaton::new(pattern.as_bytes());
+        let matches: usize = texts.iter().map(|text| kmp.count(text)).sum();
+        let overlapping: usize = texts.iter().map(|text| automaton.count_overlapping(text)).sum();
+        let first: Vec<usize> = automaton.find_overlapping_iter(texts[1]).take(5).collect();
+        println!("| {:<7} | {:>7} | {:>11} | {:<26} |", pattern, matches, overlapping, format!("{:?}", first));
+    }
+
+    // Suffix array: index the text once, then answer any number of pattern queries
+    let (text, _) = test_cases[4];
+    let start = Instant::now();
+    let index = SuffixArray::new(text.as_bytes());
+    println!("\nSuffix array of case 5 ({} bytes) built in {:?}", text.len(), start.elapsed());
+    for pattern in ["ut", "dolor", "ex ea", "xyz"] {
+        println!("{:>7}: {} occurrence(s) at {:?}", format!("{:?}", pattern), index.count(pattern.as_bytes()), index.locate(pattern.as_bytes()));
+    }
+    println!("Longest repeated substring: {:?}", String::from_utf8_lossy(index.longest_repeated_substring()));
+    println!("Distinct substrings: {}", index.distinct_substrings());
+    println!("Longest common substring with case 4: {:?}", String::from_utf8_lossy(longest_common_substring(text.as_bytes(), test_cases[3].0.as_bytes())));
+
+    let mut rng_state = 0x2545_f491_u32;
+    let large: Vec<u8> = (0..1_000_000).map(|_| { rng_state ^= rng_state << 13; rng_state ^= rng_state >> 17; rng_state ^= rng_state << 5; b'a' + (rng_state % 4) as u8 }).collect();
+    let start = Instant::now();
+    let index = SuffixArray::new(&large);
+    println!("Suffix array of 1,000,000 random bytes over 4 symbols built in {:?}, longest repeat {} bytes", start.elapsed(), index.longest_repeated_substring().len());
+
+    // Online indexes: a log stream is appended to chunk by chunk and queried in between
+    let chunks = [
+        "GET /index.html 200\n",
+        "GET /about.html 200\n",
+        "POST /login 401\n",
+        "POST /login 200\n",
+        "GET /index.html 304\n",
+    ];
+    let mut automaton = SuffixAutomaton::new();
+    let mut tree = SuffixTree::new();
+    println!("\n| {:^6} | {:^10} | {:^18} | {:^18} | {:^10} |", "Bytes", "Distinct", "\"POST /login\"", "\"index.html 3\"", "\"200\\n\"");
+    println!("|{:-^8}|{:-^12}|{:-^20}|{:-^20}|{:-^12}|", "", "", "", "", "");
+    for chunk in chunks {
+        automaton.extend(chunk);
+        tree.extend(chunk);
+        let describe = |pattern: &str| match automaton.first_occurrence(pattern.as_bytes()) {
+            Some(first) => format!("{}, first at {}", tree.count(pattern.as_bytes()), first),
+            None => "0".to_string(),
+        };
+        println!("| {:>6} | {:>10} | {:>18} | {:>18} | {:>10} |", automaton.text_len(), tree.distinct_substrings(), describe("POST /login"), describe("index.html 3"), automaton.count(b"200\n"));
+    }
+
+    let start = Instant::now();
+    let automaton: SuffixAutomaton = { let mut automaton = SuffixAutomaton::new(); automaton.extend(&large); automaton };
+    let automaton_time = start.elapsed();
+    let start = Instant::now();
+    let tree: SuffixTree = { let mut tree = SuffixTree::new(); tree.extend(&large); tree };
+    let tree_time = start.elapsed();
+    println!("Indexing 1,000,000 random bytes: suffix automaton {:?} ({} states), suffix tree {:?} ({} nodes)", automaton_time, automaton.state_count(), tree_time, tree.node_count());
+    let pattern = &large[500_000..500_008];
+    let indexes: [(&str, &dyn SubstringIndex); 3] = [("Suffix array", &index), ("Suffix automaton", &automaton), ("Suffix tree", &tree)];
+    // the automaton's first count after appending also adds up the occurrences of every state
+    for (name, index) in indexes {
+        let start = Instant::now();
+        let count = index.count(pattern);
+        println!("{:>16}: {} occurrence(s) of an 8-byte pattern in {:?}", name, count, start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::check_matcher;
+
+    #[test]
+    fn matchers_find_every_match() {
+        check_matcher(Kmp::new);
+        check_matcher(KmpAutomaton::new);
+    }
+
+    #[test]
+    fn overlapping_matches_in_one_pass() {
+        let kmp = Kmp::new(b"aa");
+        assert_eq!(kmp.count(b"aaaaa"), 2);
+        assert_eq!(kmp.count_overlapping(b"aaaaa"), 4);
+        let automaton = KmpAutomaton::new(b"aba");
+        assert_eq!(
+            automaton
+                .find_overlapping_iter(b"abababa")
+                .collect::<Vec<_>>(),
+            vec![0, 2, 4]
+        );
+        assert_eq!(kmp_search("Hello, World!", "World"), Some(7));
+        assert_eq!(kmp_search_automaton("Hello, World!", "xyz"), None);
+    }
+
+    #[test]
+    fn searches_tokens_and_chars() {
+        let tokens: Vec<&str> = "to be or not to be or to be".split(' ').collect();
+        let automaton = KmpAutomaton::new(&["to", "be", "or"]);
+        assert_eq!(
+            automaton.find_overlapping_iter(&tokens).collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+        assert_eq!(Kmp::new(&["be", "or"]).count(&tokens), 2);
+
+        // positions count chars, not bytes
+        let text: Vec<char> = "naïve naïveté".chars().collect();
+        let pattern: Vec<char> = "ïve".chars().collect();
+        assert_eq!(
+            Kmp::new(&pattern).find_iter(&text).collect::<Vec<_>>(),
+            vec![2, 8]
+        );
+        assert_eq!(KmpAutomaton::new(&pattern).count(&text), 2);
+    }
+}
@@ -0,0 +1,236 @@
+// Interface shared by the single-pattern matchers of the kmp and boyer_moore binaries: a
+// matcher preprocesses its pattern once, in its constructor, and then searches any number of
+// texts for it. Patterns and texts are slices of any symbol type, bytes or tokens alike.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Longest proper border, i.e. prefix that is also a suffix, of each prefix of `pattern`:
+/// `prefix[i]` is for `pattern[..=i]`.
+pub fn prefix_function<T: Eq>(pattern: &[T]) -> Vec<usize> {
+    let mut prefix = vec![0; pattern.len()];
+    let mut border = 0;
+    for i in 1..pattern.len() {
+        while border > 0 && pattern[border] != pattern[i] {
+            border = prefix[border - 1];
+        }
+        if pattern[border] == pattern[i] {
+            border += 1;
+        }
+        prefix[i] = border;
+    }
+    prefix
+}
+
+/// Shifts or states indexed by symbol, as precomputed by the matchers, with one value for
+/// every symbol that wasn't set.
+pub trait SymbolTable<T> {
+    fn new(default: usize) -> Self;
+    fn get(&self, symbol: &T) -> usize;
+    fn set(&mut self, symbol: &T, value: usize);
+}
+
+/// Table with a slot for each of the 256 bytes.
+pub struct DenseTable([usize; 256]);
+
+impl SymbolTable<u8> for DenseTable {
+    fn new(default: usize) -> Self {
+        DenseTable([default; 256])
+    }
+
+    fn get(&self, symbol: &u8) -> usize {
+        self.0[*symbol as usize]
+    }
+
+    fn set(&mut self, symbol: &u8, value: usize) {
+        self.0[*symbol as usize] = value;
+    }
+}
+
+/// Table holding only the symbols that were set, for alphabets too large to list.
+pub struct HashedTable<T> {
+    values: HashMap<T, usize>,
+    default: usize,
+}
+
+impl<T: Hash + Eq + Clone> SymbolTable<T> for HashedTable<T> {
+    fn new(default: usize) -> Self {
+        HashedTable {
+            values: HashMap::new(),
+            default,
+        }
+    }
+
+    fn get(&self, symbol: &T) -> usize {
+        self.values.get(symbol).copied().unwrap_or(self.default)
+    }
+
+    fn set(&mut self, symbol: &T, value: usize) {
+        self.values.insert(symbol.clone(), value);
+    }
+}
+
+/// Symbol type for the matchers that index tables by symbol: bytes get dense tables, and
+/// everything else hashed ones.
+pub trait Symbol: Eq + Clone {
+    type Table: SymbolTable<Self>;
+}
+
+impl Symbol for u8 {
+    type Table = DenseTable;
+}
+
+macro_rules! hashed_symbols {
+    ($($symbol:ty),*) => {
+        $(impl Symbol for $symbol {
+            type Table = HashedTable<$symbol>;
+        })*
+    };
+}
+
+hashed_symbols!(char, u16, u32, u64, i32, i64, String);
+
+impl<'a> Symbol for &'a str {
+    type Table = HashedTable<&'a str>;
+}
+
+/// Precompiled single-pattern search. An empty pattern matches at every position of the
+/// text, its end included.
+pub trait Matcher<T> {
+    fn pattern_len(&self) -> usize;
+
+    /// Start of the first match at or after `start`. Only called with a non-empty pattern
+    /// and `start <= text.len()`.
+    fn search(&self, text: &[T], start: usize) -> Option<usize>;
+
+    /// Start of the first match after the one at `previous`, which it may overlap. Matchers
+    /// that know how far the pattern must move to match itself again skip ahead.
+    fn search_after(&self, text: &[T], previous: usize) -> Option<usize> {
+        self.search(text, previous + 1)
+    }
+
+    /// Start of the first match at or after `start`.
+    fn find_at(&self, text: &[T], start: usize) -> Option<usize> {
+        if start > text.len() {
+            None
+        } else if self.pattern_len() == 0 {
+            Some(start)
+        } else {
+            self.search(text, start)
+        }
+    }
+
+    fn find(&self, text: &[T]) -> Option<usize> {
+        self.find_at(text, 0)
+    }
+
+    /// Starts of the non-overlapping matches, from left to right.
+    fn find_iter<'m, 't>(&'m self, text: &'t [T]) -> Matches<'m, 't, Self, T> {
+        Matches::new(self, text, false)
+    }
+
+    /// Starts of all matches, overlapping ones included.
+    fn find_overlapping_iter<'m, 't>(&'m self, text: &'t [T]) -> Matches<'m, 't, Self, T> {
+        Matches::new(self, text, true)
+    }
+
+    /// Number of non-overlapping matches.
+    fn count(&self, text: &[T]) -> usize {
+        self.find_iter(text).count()
+    }
+
+    /// Number of matches, overlapping ones included.
+    fn count_overlapping(&self, text: &[T]) -> usize {
+        self.find_overlapping_iter(text).count()
+    }
+}
+
+/// Iterator over the starts of the matches of a pattern in a text.
+pub struct Matches<'m, 't, M: ?Sized, T> {
+    matcher: &'m M,
+    text: &'t [T],
+    overlapping: bool,
+    // start of the last match reported; none before the first
+    previous: Option<usize>,
+    done: bool,
+}
+
+impl<'m, 't, M: Matcher<T> + ?Sized, T> Matches<'m, 't, M, T> {
+    fn new(matcher: &'m M, text: &'t [T], overlapping: bool) -> Self {
+        Matches {
+            matcher,
+            text,
+            overlapping,
+            previous: None,
+            done: false,
+        }
+    }
+}
+
+impl<M: Matcher<T> + ?Sized, T> Iterator for Matches<'_, '_, M, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.done {
+            return None;
+        }
+        let len = self.matcher.pattern_len();
+        let found = match self.previous {
+            None => self.matcher.find_at(self.text, 0),
+            Some(previous) if len == 0 => self.matcher.find_at(self.text, previous + 1),
+            Some(previous) if self.overlapping => self.matcher.search_after(self.text, previous),
+            Some(previous) => self.matcher.find_at(self.text, previous + len),
+        };
+        self.previous = found;
+        self.done = found.is_none();
+        found
+    }
+}
+
+/// Checks `find`, the match iterators and the counts of matchers made by `build` against a
+/// naive scan, on random short patterns and texts over small alphabets.
+#[cfg(test)]
+pub fn check_matcher<M: Matcher<u8>>(build: impl Fn(&[u8]) -> M) {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        let alphabet = rng.gen_range(1..4);
+        let text: Vec<u8> = (0..rng.gen_range(0..60))
+            .map(|_| b'a' + rng.gen_range(0..alphabet))
+            .collect();
+        let pattern: Vec<u8> = (0..rng.gen_range(0..5))
+            .map(|_| b'a' + rng.gen_range(0..alphabet))
+            .collect();
+        let matcher = build(&pattern);
+        let overlapping: Vec<usize> = (0..=text.len())
+            .filter(|&i| text[i..].starts_with(&pattern))
+            .collect();
+        let mut non_overlapping = Vec::new();
+        for &i in &overlapping {
+            if non_overlapping
+                .last()
+                .is_none_or(|&last| i >= last + pattern.len().max(1))
+            {
+                non_overlapping.push(i);
+            }
+        }
+        assert_eq!(matcher.find(&text), overlapping.first().copied());
+        assert_eq!(
+            matcher.find_overlapping_iter(&text).collect::<Vec<_>>(),
+            overlapping,
+            "{:?} in {:?}",
+            pattern,
+            text
+        );
+        assert_eq!(
+            matcher.find_iter(&text).collect::<Vec<_>>(),
+            non_overlapping,
+            "{:?} in {:?}",
+            pattern,
+            text
+        );
+        assert_eq!(matcher.count(&text), non_overlapping.len());
+        assert_eq!(matcher.count_overlapping(&text), overlapping.len());
+    }
+}
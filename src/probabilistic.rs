@@ -2,6 +2,19 @@ use rand::Rng;
 use std::collections::{HashSet};
 use std::hash::{Hash, Hasher};
 use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasher;
+
+// the hash binary owns these modules; this one only needs a seeded hasher and StaticHashMap
+#[allow(dead_code)]
+mod codec;
+#[allow(dead_code)]
+#[path = "hash_table/hashers.rs"]
+mod hashers;
+use hashers::Mix64BuildHasher;
+#[allow(dead_code)]
+#[path = "hash_table/perfect.rs"]
+mod perfect;
+use perfect::StaticHashMap;
 
 fn generate_word(rng: &mut impl Rng) -> String {
     let length = rng.gen_range(3..10);
@@ -31,22 +44,15 @@ fn generate_dataset(article_count: usize) -> String {
 
 struct BloomFilter {
     bit_array: Vec<bool>,
-    hash_functions: Vec<Box<dyn Fn(&str) -> usize>>,
+    // independently seeded hashers, one per hash function
+    hash_functions: Vec<Mix64BuildHasher>,
 }
 
 impl BloomFilter {
     fn new(size: usize, num_hash_functions: usize) -> Self {
         let bit_array = vec![false; size];
         let hash_functions = (0..num_hash_functions)
-            .map(|i| {
-                Box::new(move |s: &str| {
-                    let mut hash = 5381u64;
-                    for b in s.bytes() {
-                        hash = ((hash << 5).wrapping_add(hash)).wrapping_add(b as u64);
-                    }
-                    (hash.wrapping_add(i as u64) % size as u64) as usize
-                }) as Box<dyn Fn(&str) -> usize>
-            })
+            .map(|i| Mix64BuildHasher::with_seed(i as u64))
             .collect();
 
         BloomFilter {
@@ -55,9 +61,13 @@ impl BloomFilter {
         }
     }
 
+    fn index(&self, hash_fn: &Mix64BuildHasher, item: &str) -> usize {
+        (hash_fn.hash_one(item) % self.bit_array.len() as u64) as usize
+    }
+
     fn insert(&mut self, item: &str) {
         for hash_fn in &self.hash_functions {
-            let index = hash_fn(item);
+            let index = self.index(hash_fn, item);
             self.bit_array[index] = true;
         }
     }
@@ -65,7 +75,7 @@ impl BloomFilter {
     fn might_contain(&self, item: &str) -> bool {
         self.hash_functions
             .iter()
-            .all(|hash_fn| self.bit_array[hash_fn(item)])
+            .all(|hash_fn| self.bit_array[self.index(hash_fn, item)])
     }
 }
 
@@ -191,20 +201,15 @@ impl HyperLogLog {
 
 struct CountMinSketch {
     counters: Vec<Vec<u32>>,
-    hash_fns: Vec<Box<dyn Fn(&str) -> usize>>,
+    // one seeded hasher per row
+    hash_fns: Vec<Mix64BuildHasher>,
 }
 
 impl CountMinSketch {
     fn new(d: usize, w: usize) -> Self {
         let counters = vec![vec![0; w]; d];
         let hash_fns = (0..d)
-            .map(|i| {
-                Box::new(move |s: &str| {
-                    let mut hasher = DefaultHasher::new();
-                    s.hash(&mut hasher);
-                    (hasher.finish().wrapping_add(i as u64) % w as u64) as usize
-                }) as Box<dyn Fn(&str) -> usize>
-            })
+            .map(|i| Mix64BuildHasher::with_seed(i as u64))
             .collect();
 
         CountMinSketch {
@@ -213,9 +218,14 @@ impl CountMinSketch {
         }
     }
 
+    fn index(&self, hash_fn: &Mix64BuildHasher, item: &str) -> usize {
+        let width = self.counters[0].len() as u64;
+        (hash_fn.hash_one(item) % width) as usize
+    }
+
     fn add(&mut self, item: &str, count: u32) {
         for (i, hash_fn) in self.hash_fns.iter().enumerate() {
-            let index = hash_fn(item);
+            let index = self.index(hash_fn, item);
             self.counters[i][index] += count;
         }
     }
@@ -224,7 +234,7 @@ impl CountMinSketch {
         self.hash_fns
             .iter()
             .enumerate()
-            .map(|(i, hash_fn)| self.counters[i][hash_fn(item)])
+            .map(|(i, hash_fn)| self.counters[i][self.index(hash_fn, item)])
             .min()
             .unwrap_or(0)
     }
@@ -348,4 +358,19 @@ fn main() {
     for word in &sample_words {
         println!("Estimated count of '{}': {}", word, count_min_sketch.estimate(word));
     }
+
+    let mut word_counts = std::collections::HashMap::new();
+    for line in train_data.lines() {
+        for word in line.split('\t').nth(1).unwrap_or("").split_whitespace() {
+            *word_counts.entry(word.to_string()).or_insert(0u32) += 1;
+        }
+    }
+    let exact: StaticHashMap<String, u32> = word_counts.into_iter().collect();
+    let bytes = exact.to_bytes();
+    let exact = StaticHashMap::<String, u32>::from_bytes(&bytes).unwrap();
+
+    println!("\nPerfect hash table of all {} distinct words ({} bytes serialised):", exact.len(), bytes.len());
+    for word in train_data.lines().next().unwrap().split_whitespace().skip(1).take(5) {
+        println!("Count of '{}': exact {}, Count-Min estimate {}", word, exact[word], count_min_sketch.estimate(word));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,389 @@
+// Compressed radix tree (Patricia trie) keyed on bytes, the compact counterpart of the
+// HashMap-per-character trie in trie.rs.
+
+use std::mem;
+
+/// Map from byte strings to values. Chains of single-child nodes are collapsed into one edge
+/// labelled with all their bytes, so every inner node either holds a value or branches.
+pub struct RadixTree<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    // sorted by first byte, which is unique among siblings
+    edges: Vec<Edge<V>>,
+    value: Option<V>,
+}
+
+struct Edge<V> {
+    label: Box<[u8]>,
+    node: Node<V>,
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+impl<V> Node<V> {
+    fn new(value: Option<V>) -> Self {
+        Node {
+            edges: Vec::new(),
+            value,
+        }
+    }
+
+    fn edge_index(&self, first: u8) -> Result<usize, usize> {
+        self.edges
+            .binary_search_by_key(&first, |edge| edge.label[0])
+    }
+
+    /// Edge whose label starts with the first byte of `key`, if `key` isn't empty.
+    fn edge_for(&self, key: &[u8]) -> Option<&Edge<V>> {
+        let index = self.edge_index(*key.first()?).ok()?;
+        Some(&self.edges[index])
+    }
+
+    /// Removes the value under `key`. On the way back up, an edge to a node left with neither
+    /// a value nor children is dropped, and one to a node left with a single child is merged
+    /// with that child's edge, so the tree stays compressed.
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let Some(&first) = key.first() else {
+            return self.value.take();
+        };
+        let index = self.edge_index(first).ok()?;
+        let edge = &mut self.edges[index];
+        let value = edge.node.remove(key.strip_prefix(&*edge.label)?)?;
+        match (edge.node.value.is_some(), edge.node.edges.len()) {
+            (false, 0) => {
+                self.edges.remove(index);
+            }
+            (false, 1) => edge.merge_child(),
+            _ => {}
+        }
+        Some(value)
+    }
+}
+
+impl<V> Edge<V> {
+    /// Cuts the label after `at` bytes, putting a new empty node at the cut.
+    fn split(&mut self, at: usize) {
+        let tail = Edge {
+            label: self.label[at..].into(),
+            node: mem::replace(&mut self.node, Node::new(None)),
+        };
+        self.label = self.label[..at].into();
+        self.node.edges.push(tail);
+    }
+
+    /// Folds the only edge below into this one.
+    fn merge_child(&mut self) {
+        let child = self.node.edges.pop().expect("node has one child");
+        self.label = [&*self.label, &*child.label].concat().into();
+        self.node = child.node;
+    }
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> Self {
+        RadixTree {
+            root: Node::new(None),
+            len: 0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced. A key ending inside
+    /// an edge splits it, a key leaving the tree mid-edge splits it and adds one new edge.
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        let mut rest = key.as_ref();
+        let mut node = &mut self.root;
+        while let Some(&first) = rest.first() {
+            let index = match node.edge_index(first) {
+                Ok(index) => index,
+                Err(index) => {
+                    let edge = Edge {
+                        label: rest.into(),
+                        node: Node::new(Some(value)),
+                    };
+                    node.edges.insert(index, edge);
+                    self.len += 1;
+                    return None;
+                }
+            };
+            let edge = &mut node.edges[index];
+            let common = common_prefix_len(&edge.label, rest);
+            if common < edge.label.len() {
+                edge.split(common);
+            }
+            rest = &rest[common..];
+            node = &mut edge.node;
+        }
+        let old = node.value.replace(value);
+        self.len += old.is_none() as usize;
+        old
+    }
+
+    fn find(&self, key: &[u8]) -> Option<&Node<V>> {
+        let mut rest = key;
+        let mut node = &self.root;
+        while !rest.is_empty() {
+            let edge = node.edge_for(rest)?;
+            rest = rest.strip_prefix(&*edge.label)?;
+            node = &edge.node;
+        }
+        Some(node)
+    }
+
+    /// Node of the shortest stored path that `prefix` is a prefix of, and that path.
+    /// `prefix` may end inside an edge, in which case the path is longer than it.
+    fn find_prefix(&self, prefix: &[u8]) -> Option<(Vec<u8>, &Node<V>)> {
+        let mut path = Vec::with_capacity(prefix.len());
+        let mut node = &self.root;
+        while path.len() < prefix.len() {
+            let rest = &prefix[path.len()..];
+            let edge = node.edge_for(rest)?;
+            if !edge
+                .label
+                .starts_with(&rest[..rest.len().min(edge.label.len())])
+            {
+                return None;
+            }
+            path.extend_from_slice(&edge.label);
+            node = &edge.node;
+        }
+        Some((path, node))
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.find(key.as_ref())?.value.as_ref()
+    }
+
+    #[allow(dead_code)]
+    pub fn get_mut(&mut self, key: impl AsRef<[u8]>) -> Option<&mut V> {
+        let mut rest = key.as_ref();
+        let mut node = &mut self.root;
+        while let Some(&first) = rest.first() {
+            let index = node.edge_index(first).ok()?;
+            let edge = &mut node.edges[index];
+            rest = rest.strip_prefix(&*edge.label)?;
+            node = &mut edge.node;
+        }
+        node.value.as_mut()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn starts_with(&self, prefix: impl AsRef<[u8]>) -> bool {
+        self.find_prefix(prefix.as_ref()).is_some()
+    }
+
+    /// Number of stored keys starting with `prefix`. Nodes don't keep subtree sizes,
+    /// so this walks every entry under the prefix.
+    #[allow(dead_code)]
+    pub fn count_with_prefix(&self, prefix: impl AsRef<[u8]>) -> usize {
+        self.iter_prefix(prefix).count()
+    }
+
+    /// Entries whose key starts with `prefix`, in lexicographic key order.
+    #[allow(dead_code)]
+    pub fn iter_prefix(&self, prefix: impl AsRef<[u8]>) -> Iter<'_, V> {
+        Iter {
+            stack: self.find_prefix(prefix.as_ref()).into_iter().collect(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.iter_prefix([])
+    }
+
+    #[allow(dead_code)]
+    pub fn keys_with_prefix(&self, prefix: impl AsRef<[u8]>) -> Vec<Vec<u8>> {
+        self.iter_prefix(prefix).map(|(key, _)| key).collect()
+    }
+
+    /// Entry with the longest key that `text` starts with.
+    #[allow(dead_code)]
+    pub fn longest_prefix_of(&self, text: impl AsRef<[u8]>) -> Option<(Vec<u8>, &V)> {
+        let text = text.as_ref();
+        let mut depth = 0;
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        while let Some(edge) = node.edge_for(&text[depth..]) {
+            if !text[depth..].starts_with(&edge.label) {
+                break;
+            }
+            depth += edge.label.len();
+            node = &edge.node;
+            if let Some(value) = &node.value {
+                longest = Some((depth, value));
+            }
+        }
+        longest.map(|(len, value)| (text[..len].to_vec(), value))
+    }
+
+    /// Removes `key`, returning its value. Edges left redundant are dropped or merged.
+    pub fn delete(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let value = self.root.remove(key.as_ref())?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Number of nodes below the root, one per edge. A plain trie has one per key byte.
+    pub fn edge_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            count += node.edges.len();
+            stack.extend(node.edges.iter().map(|edge| &edge.node));
+        }
+        count
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Depth-first walk in edge order. Siblings are sorted by first byte, so keys come out sorted.
+#[allow(dead_code)]
+pub struct Iter<'a, V> {
+    stack: Vec<(Vec<u8>, &'a Node<V>)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for edge in node.edges.iter().rev() {
+                self.stack
+                    .push(([&key[..], &edge.label].concat(), &edge.node));
+            }
+            if let Some(value) = &node.value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<K: AsRef<[u8]>, V> FromIterator<(K, V)> for RadixTree<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = RadixTree::new();
+        for (key, value) in iter {
+            tree.insert(key, value);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    fn labels<V>(node: &Node<V>) -> Vec<&[u8]> {
+        node.edges.iter().map(|edge| &*edge.label).collect()
+    }
+
+    #[test]
+    fn splits_and_merges_edges() {
+        let mut tree = RadixTree::new();
+        tree.insert("romane", 1);
+        assert_eq!(labels(&tree.root), vec![b"romane"]);
+        // diverging inside an edge splits it into a shared part and two branches
+        tree.insert("romanus", 2);
+        assert_eq!(labels(&tree.root), vec![b"roman"]);
+        assert_eq!(labels(&tree.root.edges[0].node), vec![&b"e"[..], b"us"]);
+        // a key ending inside an edge splits it too
+        tree.insert("rom", 3);
+        assert_eq!(labels(&tree.root), vec![b"rom"]);
+        assert_eq!(tree.edge_count(), 4);
+        assert_eq!(tree.insert("rom", 4), Some(3));
+        assert_eq!(tree.len(), 3);
+
+        // "rom" keeps its node as long as it holds a value; without it, the edge merges back
+        assert_eq!(tree.delete("romanus"), Some(2));
+        assert_eq!(labels(&tree.root.edges[0].node), vec![b"ane"]);
+        assert_eq!(tree.delete("rom"), Some(4));
+        assert_eq!(labels(&tree.root), vec![b"romane"]);
+        assert_eq!(tree.delete("rom"), None);
+        assert_eq!(tree.delete("romane"), Some(1));
+        assert!(tree.is_empty());
+        assert_eq!(tree.edge_count(), 0);
+    }
+
+    #[test]
+    fn prefix_queries() {
+        let tree: RadixTree<usize> = ["tea", "ten", "to", "inn", "in", "tenant", ""]
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| (word, i))
+            .collect();
+        assert_eq!(tree.get(""), Some(&6));
+        assert_eq!(
+            tree.keys_with_prefix("te"),
+            vec![b"tea".to_vec(), b"ten".to_vec(), b"tenant".to_vec()]
+        );
+        // a prefix ending inside an edge still finds the keys below it
+        assert_eq!(tree.keys_with_prefix("tena"), vec![b"tenant".to_vec()]);
+        assert!(tree.starts_with("tena"));
+        assert!(!tree.starts_with("tenx"));
+        assert_eq!(tree.count_with_prefix("t"), 4);
+        assert_eq!(tree.count_with_prefix(""), 7);
+        assert_eq!(
+            tree.longest_prefix_of("tenants"),
+            Some((b"tenant".to_vec(), &5))
+        );
+        assert_eq!(tree.longest_prefix_of("tenor"), Some((b"ten".to_vec(), &1)));
+        assert_eq!(tree.longest_prefix_of("x"), Some((Vec::new(), &6)));
+        assert_eq!(tree.get("te"), None);
+        assert_eq!(tree.get("tenants"), None);
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        let mut rng = rand::thread_rng();
+        let mut tree = RadixTree::new();
+        let mut expected = BTreeMap::new();
+        for i in 0..20_000 {
+            // short keys over a small alphabet share lots of prefixes
+            let len = rng.gen_range(0..6);
+            let key: Vec<u8> = (0..len).map(|_| rng.gen_range(b'a'..b'd')).collect();
+            if rng.gen_bool(0.6) {
+                assert_eq!(tree.insert(&key, i), expected.insert(key, i));
+            } else {
+                assert_eq!(tree.delete(&key), expected.remove(&key));
+            }
+            assert_eq!(tree.len(), expected.len());
+        }
+        let entries: Vec<_> = tree.iter().map(|(key, &value)| (key, value)).collect();
+        assert_eq!(entries, expected.into_iter().collect::<Vec<_>>());
+
+        // compressed: every node below the root either holds a value or branches
+        let mut stack = vec![&tree.root];
+        while let Some(node) = stack.pop() {
+            for edge in &node.edges {
+                assert!(edge.node.value.is_some() || edge.node.edges.len() > 1);
+                stack.push(&edge.node);
+            }
+        }
+    }
+}
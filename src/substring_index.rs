@@ -0,0 +1,23 @@
+// Queries shared by the full-text indexes of the kmp binary: unlike the one-shot matchers,
+// they index the text once and then answer any number of patterns.
+
+/// Index over every substring of a byte text. An empty pattern occurs at each of the text's
+/// positions, so its count is the text's length.
+pub trait SubstringIndex {
+    /// Length of the indexed text.
+    fn text_len(&self) -> usize;
+
+    /// Number of occurrences of `pattern`, overlapping ones included.
+    fn count(&self, pattern: &[u8]) -> usize;
+
+    /// Start of the leftmost occurrence of `pattern`.
+    fn first_occurrence(&self, pattern: &[u8]) -> Option<usize>;
+
+    /// Number of distinct non-empty substrings of the text.
+    fn distinct_substrings(&self) -> usize;
+
+    #[allow(dead_code)]
+    fn contains(&self, pattern: &[u8]) -> bool {
+        self.first_occurrence(pattern).is_some()
+    }
+}
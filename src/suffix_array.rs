@@ -0,0 +1,321 @@
+// Suffix array with its LCP array, for substring queries over a fixed text.
+
+use std::cmp::Ordering;
+
+use crate::substring_index::SubstringIndex;
+
+/// Start positions of the suffixes of `text` in sorted order, by prefix doubling: after the
+/// round for `k`, suffixes are sorted by their first `2k` symbols. Each round sorts pairs of
+/// ranks with two counting sorts, so building takes O(n log n). Symbols must be below
+/// `alphabet`.
+fn sort_suffixes(text: &[u32], alphabet: usize) -> Vec<usize> {
+    let n = text.len();
+    // rank 0 stands for "past the end", which sorts before every symbol
+    let mut rank: Vec<usize> = text.iter().map(|&symbol| symbol as usize + 1).collect();
+    let mut counts = vec![0; alphabet.max(n) + 1];
+    let mut suffixes = Vec::with_capacity(n);
+    counting_sort(0..n, &rank, &mut counts, &mut suffixes);
+
+    let mut by_second = Vec::with_capacity(n);
+    let mut next_rank = vec![0; n];
+    let mut k = 1;
+    while k < n {
+        // by rank of the second half: suffixes too short to have one first
+        by_second.clear();
+        by_second.extend(n - k..n);
+        by_second.extend(suffixes.iter().filter(|&&i| i >= k).map(|&i| i - k));
+        // then stably by rank of the first half
+        counting_sort(by_second.iter().copied(), &rank, &mut counts, &mut suffixes);
+
+        let key = |i: usize| (rank[i], rank.get(i + k).copied().unwrap_or(0));
+        next_rank[suffixes[0]] = 1;
+        for pair in suffixes.windows(2) {
+            next_rank[pair[1]] = next_rank[pair[0]] + (key(pair[0]) != key(pair[1])) as usize;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+        if rank[suffixes[n - 1]] == n {
+            break;
+        }
+        k *= 2;
+    }
+    suffixes
+}
+
+/// Writes `items` to `out` stably sorted by `rank`, using `counts` as scratch space.
+fn counting_sort(
+    items: impl Iterator<Item = usize> + Clone,
+    rank: &[usize],
+    counts: &mut [usize],
+    out: &mut Vec<usize>,
+) {
+    counts.fill(0);
+    for i in items.clone() {
+        counts[rank[i]] += 1;
+    }
+    let mut start = 0;
+    for count in counts.iter_mut() {
+        start += *count;
+        *count = start - *count;
+    }
+    out.clear();
+    out.resize(rank.len(), 0);
+    for i in items {
+        out[counts[rank[i]]] = i;
+        counts[rank[i]] += 1;
+    }
+}
+
+/// Kasai's algorithm: `lcp[i]` is the length of the longest common prefix of the suffixes at
+/// `suffixes[i - 1]` and `suffixes[i]`, 0 for `i == 0`. Going through the suffixes in text
+/// order, the common prefix shrinks by at most one per step, so this is O(n).
+fn lcp_array<T: Eq>(text: &[T], suffixes: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut rank = vec![0; n];
+    for (index, &suffix) in suffixes.iter().enumerate() {
+        rank[suffix] = index;
+    }
+    let mut lcp = vec![0; n];
+    let mut common = 0;
+    for suffix in 0..n {
+        if rank[suffix] == 0 {
+            common = 0;
+            continue;
+        }
+        let previous = suffixes[rank[suffix] - 1];
+        while suffix + common < n
+            && previous + common < n
+            && text[suffix + common] == text[previous + common]
+        {
+            common += 1;
+        }
+        lcp[rank[suffix]] = common;
+        common = common.saturating_sub(1);
+    }
+    lcp
+}
+
+/// Sorted suffixes of a byte string and the common prefix lengths of neighbouring ones.
+pub struct SuffixArray<'t> {
+    text: &'t [u8],
+    suffixes: Vec<usize>,
+    lcp: Vec<usize>,
+}
+
+impl<'t> SuffixArray<'t> {
+    pub fn new(text: &'t [u8]) -> Self {
+        let symbols: Vec<u32> = text.iter().map(|&byte| byte as u32).collect();
+        let suffixes = sort_suffixes(&symbols, 256);
+        let lcp = lcp_array(text, &suffixes);
+        SuffixArray {
+            text,
+            suffixes,
+            lcp,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn text(&self) -> &'t [u8] {
+        self.text
+    }
+
+    /// Start positions of the suffixes in sorted order.
+    #[allow(dead_code)]
+    pub fn suffixes(&self) -> &[usize] {
+        &self.suffixes
+    }
+
+    /// Common prefix length of each suffix with the one sorted before it.
+    #[allow(dead_code)]
+    pub fn lcp(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    /// Compares the suffix at `start`, cut to the pattern's length, with `pattern`.
+    fn compare(&self, start: usize, pattern: &[u8]) -> Ordering {
+        let end = (start + pattern.len()).min(self.text.len());
+        self.text[start..end].cmp(pattern)
+    }
+
+    /// Range of `suffixes` starting with `pattern`, found by two binary searches.
+    fn range(&self, pattern: &[u8]) -> std::ops::Range<usize> {
+        let start = self
+            .suffixes
+            .partition_point(|&suffix| self.compare(suffix, pattern) == Ordering::Less);
+        let end = start
+            + self.suffixes[start..]
+                .partition_point(|&suffix| self.compare(suffix, pattern) == Ordering::Equal);
+        start..end
+    }
+
+    /// Number of occurrences of `pattern`, in O(m log n).
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        self.range(pattern).len()
+    }
+
+    /// Start positions of every occurrence of `pattern`, in increasing order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut positions = self.suffixes[self.range(pattern)].to_vec();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Longest substring occurring at least twice, occurrences possibly overlapping; the
+    /// earliest in sorted order if several are as long. Empty if no symbol repeats.
+    pub fn longest_repeated_substring(&self) -> &'t [u8] {
+        let best =
+            (1..self.lcp.len()).max_by_key(|&index| (self.lcp[index], std::cmp::Reverse(index)));
+        match best {
+            Some(index) => {
+                let start = self.suffixes[index];
+                &self.text[start..start + self.lcp[index]]
+            }
+            None => &[],
+        }
+    }
+
+    /// Number of distinct non-empty substrings: each suffix adds its prefixes, except those
+    /// it shares with the suffix sorted before it.
+    pub fn distinct_substrings(&self) -> usize {
+        let n = self.text.len();
+        n * (n + 1) / 2 - self.lcp.iter().sum::<usize>()
+    }
+}
+
+impl SubstringIndex for SuffixArray<'_> {
+    fn text_len(&self) -> usize {
+        self.text.len()
+    }
+
+    fn count(&self, pattern: &[u8]) -> usize {
+        SuffixArray::count(self, pattern)
+    }
+
+    fn first_occurrence(&self, pattern: &[u8]) -> Option<usize> {
+        self.suffixes[self.range(pattern)].iter().copied().min()
+    }
+
+    fn distinct_substrings(&self) -> usize {
+        SuffixArray::distinct_substrings(self)
+    }
+}
+
+/// Longest substring of both `a` and `b`, the earliest in sorted order if several are as long.
+/// Built on the suffix array of `a`, a separator and `b`: the separator is a symbol outside
+/// the byte range, so no common prefix runs across it, and the answer is the longest common
+/// prefix of two neighbouring suffixes that start in different texts.
+pub fn longest_common_substring<'a>(a: &'a [u8], b: &[u8]) -> &'a [u8] {
+    const SEPARATOR: u32 = 256;
+    let symbols: Vec<u32> = a
+        .iter()
+        .map(|&byte| byte as u32)
+        .chain([SEPARATOR])
+        .chain(b.iter().map(|&byte| byte as u32))
+        .collect();
+    let suffixes = sort_suffixes(&symbols, 257);
+    let lcp = lcp_array(&symbols, &suffixes);
+
+    let in_a = |suffix: usize| suffix < a.len();
+    let mut best: &[u8] = &[];
+    for index in 1..suffixes.len() {
+        let (previous, current) = (suffixes[index - 1], suffixes[index]);
+        if in_a(previous) != in_a(current) && lcp[index] > best.len() {
+            let start = previous.min(current);
+            best = &a[start..start + lcp[index]];
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashSet;
+
+    fn random_text(len: usize, alphabet: u8) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        (0..len)
+            .map(|_| b'a' + rng.gen_range(0..alphabet))
+            .collect()
+    }
+
+    #[test]
+    fn matches_naive_construction() {
+        for len in [0, 1, 2, 3, 10, 100, 1_000] {
+            for alphabet in [1, 2, 4, 26] {
+                let text = random_text(len, alphabet);
+                let array = SuffixArray::new(&text);
+                let mut naive: Vec<usize> = (0..text.len()).collect();
+                naive.sort_by_key(|&i| &text[i..]);
+                assert_eq!(array.suffixes(), naive);
+
+                let naive_lcp: Vec<usize> = (0..naive.len())
+                    .map(|i| match i {
+                        0 => 0,
+                        _ => text[naive[i - 1]..]
+                            .iter()
+                            .zip(&text[naive[i]..])
+                            .take_while(|(a, b)| a == b)
+                            .count(),
+                    })
+                    .collect();
+                assert_eq!(array.lcp(), naive_lcp);
+            }
+        }
+    }
+
+    #[test]
+    fn substring_queries() {
+        let text = b"mississippi";
+        let array = SuffixArray::new(text);
+        assert_eq!(array.count(b"ssi"), 2);
+        assert_eq!(array.locate(b"ssi"), vec![2, 5]);
+        assert_eq!(array.locate(b"i"), vec![1, 4, 7, 10]);
+        assert_eq!(array.count(b"sip"), 1);
+        assert_eq!(array.count(b"spa"), 0);
+        assert_eq!(array.count(b"mississippis"), 0);
+        assert_eq!(array.count(b""), text.len());
+        assert_eq!(array.longest_repeated_substring(), b"issi");
+        assert_eq!(
+            longest_common_substring(b"mississippi", b"misty mountains"),
+            b"mis"
+        );
+        assert_eq!(longest_common_substring(b"abc", b"xyz"), b"");
+
+        for alphabet in [2, 3] {
+            let text = random_text(300, alphabet);
+            let array = SuffixArray::new(&text);
+            let mut distinct = HashSet::new();
+            for start in 0..text.len() {
+                for end in start + 1..=text.len() {
+                    distinct.insert(&text[start..end]);
+                }
+            }
+            assert_eq!(array.distinct_substrings(), distinct.len());
+
+            let pattern = &text[100..104];
+            let naive: Vec<usize> = (0..=text.len() - pattern.len())
+                .filter(|&i| text[i..].starts_with(pattern))
+                .collect();
+            assert_eq!(array.locate(pattern), naive);
+
+            let repeated = array.longest_repeated_substring();
+            let occurrences = (0..=text.len() - repeated.len())
+                .filter(|&i| text[i..].starts_with(repeated))
+                .count();
+            assert!(occurrences >= 2);
+            // nothing longer repeats
+            let longer: HashSet<&[u8]> = text.windows(repeated.len() + 1).collect();
+            assert_eq!(longer.len(), text.len() - repeated.len());
+
+            let other = random_text(200, alphabet);
+            let common = longest_common_substring(&text, &other);
+            assert!(other.windows(common.len()).any(|w| w == common));
+            assert!(text.windows(common.len()).any(|w| w == common));
+            let from_text: HashSet<&[u8]> = text.windows(common.len() + 1).collect();
+            assert!(other
+                .windows(common.len() + 1)
+                .all(|w| !from_text.contains(w)));
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Suffix automaton: the smallest automaton accepting the suffixes of a text, extended one
+// byte at a time, so a growing text can be queried between appends.
+
+use std::cell::OnceCell;
+
+use crate::substring_index::SubstringIndex;
+
+const ROOT: u32 = 0;
+
+/// Set of substrings that end at the same positions of the text: the suffixes of the longest
+/// one down to, but not including, the longest one of the state's suffix link.
+struct State {
+    // transitions, sorted by byte
+    next: Vec<(u8, u32)>,
+    // state of the longest suffix ending at more positions; none for the root
+    link: Option<u32>,
+    // length of the longest substring of the state
+    len: usize,
+    // end of the first occurrence of the state's substrings
+    first_end: usize,
+    // whether the state was split off another one rather than created for a new byte
+    is_clone: bool,
+}
+
+impl State {
+    fn child(&self, byte: u8) -> Option<u32> {
+        let index = self.next.binary_search_by_key(&byte, |&(b, _)| b).ok()?;
+        Some(self.next[index].1)
+    }
+
+    fn set_child(&mut self, byte: u8, state: u32) {
+        match self.next.binary_search_by_key(&byte, |&(b, _)| b) {
+            Ok(index) => self.next[index].1 = state,
+            Err(index) => self.next.insert(index, (byte, state)),
+        }
+    }
+}
+
+/// Index of a byte text that can be appended to. The automaton has at most `2n` states and
+/// `3n` transitions, and appending a byte takes amortised constant time per distinct byte.
+pub struct SuffixAutomaton {
+    states: Vec<State>,
+    // state of the whole text
+    last: u32,
+    len: usize,
+    distinct: usize,
+    // occurrence count of each state, computed on the first count query after an append
+    occurrences: OnceCell<Vec<usize>>,
+}
+
+impl SuffixAutomaton {
+    pub fn new() -> Self {
+        SuffixAutomaton {
+            states: vec![State {
+                next: Vec::new(),
+                link: None,
+                len: 0,
+                first_end: 0,
+                is_clone: false,
+            }],
+            last: ROOT,
+            len: 0,
+            distinct: 0,
+            occurrences: OnceCell::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Appends `byte` to the text.
+    pub fn push(&mut self, byte: u8) {
+        let current = self.states.len() as u32;
+        self.states.push(State {
+            next: Vec::new(),
+            link: None,
+            len: self.len + 1,
+            first_end: self.len,
+            is_clone: false,
+        });
+        self.len += 1;
+        self.occurrences = OnceCell::new();
+
+        // every suffix of the old text without a transition on `byte` now gets one
+        let mut state = Some(self.last);
+        while let Some(s) = state {
+            if self.states[s as usize].child(byte).is_some() {
+                break;
+            }
+            self.states[s as usize].set_child(byte, current);
+            state = self.states[s as usize].link;
+        }
+        self.last = current;
+
+        let link = match state {
+            None => ROOT,
+            Some(s) => {
+                let target = self.states[s as usize].child(byte).unwrap();
+                if self.states[target as usize].len == self.states[s as usize].len + 1 {
+                    target
+                } else {
+                    // `target` also holds longer strings that don't end at the new position:
+                    // split the shorter ones off into a clone
+                    let clone = self.states.len() as u32;
+                    let target_state = &self.states[target as usize];
+                    self.states.push(State {
+                        next: target_state.next.clone(),
+                        link: target_state.link,
+                        len: self.states[s as usize].len + 1,
+                        first_end: target_state.first_end,
+                        is_clone: true,
+                    });
+                    self.states[target as usize].link = Some(clone);
+                    let mut state = Some(s);
+                    while let Some(s) = state {
+                        if self.states[s as usize].child(byte) != Some(target) {
+                            break;
+                        }
+                        self.states[s as usize].set_child(byte, clone);
+                        state = self.states[s as usize].link;
+                    }
+                    clone
+                }
+            }
+        };
+        self.states[current as usize].link = Some(link);
+        // the new substrings are the suffixes of the text longer than the link's strings
+        self.distinct += self.len - self.states[link as usize].len;
+    }
+
+    /// Appends every byte of `bytes` to the text.
+    pub fn extend(&mut self, bytes: impl AsRef<[u8]>) {
+        for &byte in bytes.as_ref() {
+            self.push(byte);
+        }
+    }
+
+    /// State reached by reading `pattern` from the root, if it is a substring.
+    fn find(&self, pattern: &[u8]) -> Option<u32> {
+        pattern
+            .iter()
+            .try_fold(ROOT, |state, &byte| self.states[state as usize].child(byte))
+    }
+
+    /// Number of end positions of each state: one for each state created for a byte, added
+    /// up along the suffix links from the longest states down.
+    fn occurrences(&self) -> &[usize] {
+        self.occurrences.get_or_init(|| {
+            let mut counts: Vec<usize> = self
+                .states
+                .iter()
+                .map(|state| (!state.is_clone) as usize)
+                .collect();
+            counts[ROOT as usize] = 0;
+            let mut by_len: Vec<u32> = (0..self.states.len() as u32).collect();
+            by_len
+                .sort_unstable_by_key(|&state| std::cmp::Reverse(self.states[state as usize].len));
+            for state in by_len {
+                if let Some(link) = self.states[state as usize].link {
+                    counts[link as usize] += counts[state as usize];
+                }
+            }
+            counts
+        })
+    }
+}
+
+impl SubstringIndex for SuffixAutomaton {
+    fn text_len(&self) -> usize {
+        self.len
+    }
+
+    /// O(m), plus O(n log n) for the first count after an append.
+    fn count(&self, pattern: &[u8]) -> usize {
+        match self.find(pattern) {
+            Some(state) => self.occurrences()[state as usize],
+            None => 0,
+        }
+    }
+
+    fn first_occurrence(&self, pattern: &[u8]) -> Option<usize> {
+        if pattern.is_empty() {
+            return (self.len > 0).then_some(0);
+        }
+        let state = self.find(pattern)?;
+        Some(self.states[state as usize].first_end + 1 - pattern.len())
+    }
+
+    /// Kept up to date by `push`, so O(1).
+    fn distinct_substrings(&self) -> usize {
+        self.distinct
+    }
+}
+
+impl Default for SuffixAutomaton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn answers_like_naive_search_while_growing() {
+        let mut rng = rand::thread_rng();
+        for alphabet in [1, 2, 3, 26] {
+            let full: Vec<u8> = (0..200)
+                .map(|_| b'a' + rng.gen_range(0..alphabet))
+                .collect();
+            let mut automaton = SuffixAutomaton::new();
+            for end in 0..=full.len() {
+                let text = &full[..end];
+                assert_eq!(automaton.len(), text.len());
+                assert!(automaton.state_count() <= (2 * text.len()).max(1));
+                if end % 20 == 0 {
+                    let distinct: HashSet<&[u8]> = (0..text.len())
+                        .flat_map(|start| {
+                            (start + 1..=text.len()).map(move |end| &text[start..end])
+                        })
+                        .collect();
+                    assert_eq!(automaton.distinct_substrings(), distinct.len());
+                    for _ in 0..50 {
+                        let len = rng.gen_range(0..5);
+                        let pattern: Vec<u8> = (0..len)
+                            .map(|_| b'a' + rng.gen_range(0..alphabet))
+                            .collect();
+                        let starts: Vec<usize> = (0..text.len())
+                            .filter(|&i| text[i..].starts_with(&pattern))
+                            .collect();
+                        assert_eq!(automaton.count(&pattern), starts.len(), "{:?}", pattern);
+                        assert_eq!(
+                            automaton.first_occurrence(&pattern),
+                            starts.first().copied()
+                        );
+                        assert_eq!(automaton.contains(&pattern), !starts.is_empty());
+                    }
+                }
+                if let Some(&byte) = full.get(end) {
+                    automaton.push(byte);
+                }
+            }
+        }
+    }
+}
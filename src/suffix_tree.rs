@@ -0,0 +1,296 @@
+// Suffix tree built online with Ukkonen's algorithm: a compressed trie of the suffixes of a
+// text, extended one byte at a time like the suffix automaton.
+
+use crate::substring_index::SubstringIndex;
+
+const ROOT: u32 = 0;
+/// Edge end of the leaves, which always run to the end of the text.
+const OPEN: usize = usize::MAX;
+
+struct Node {
+    // the edge into the node is `text[start..end]`
+    start: usize,
+    end: usize,
+    // children, sorted by the first byte of their edge
+    children: Vec<(u8, u32)>,
+    // node of the string without its first byte, for internal nodes
+    link: u32,
+    // start of the suffix a leaf stands for
+    suffix: usize,
+}
+
+impl Node {
+    fn child(&self, byte: u8) -> Option<u32> {
+        let index = self
+            .children
+            .binary_search_by_key(&byte, |&(b, _)| b)
+            .ok()?;
+        Some(self.children[index].1)
+    }
+
+    fn set_child(&mut self, byte: u8, node: u32) {
+        match self.children.binary_search_by_key(&byte, |&(b, _)| b) {
+            Ok(index) => self.children[index].1 = node,
+            Err(index) => self.children.insert(index, (byte, node)),
+        }
+    }
+}
+
+/// Suffix tree of a byte text that can be appended to, without a terminator: suffixes that
+/// are also found earlier in the text stay implicit, inside an edge, until a later byte tells
+/// them apart. Appending takes amortised constant time.
+pub struct SuffixTree {
+    text: Vec<u8>,
+    nodes: Vec<Node>,
+    // the active point: where the longest implicit suffix ends, as a node, the text index of
+    // the first byte of the edge taken from it and how far along that edge
+    active_node: u32,
+    active_edge: usize,
+    active_len: usize,
+    // number of suffixes without a leaf yet
+    remainder: usize,
+}
+
+impl SuffixTree {
+    pub fn new() -> Self {
+        SuffixTree {
+            text: Vec::new(),
+            nodes: vec![Node {
+                start: 0,
+                end: 0,
+                children: Vec::new(),
+                link: ROOT,
+                suffix: 0,
+            }],
+            active_node: ROOT,
+            active_edge: 0,
+            active_len: 0,
+            remainder: 0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn new_node(&mut self, start: usize, end: usize, suffix: usize) -> u32 {
+        self.nodes.push(Node {
+            start,
+            end,
+            children: Vec::new(),
+            link: ROOT,
+            suffix,
+        });
+        (self.nodes.len() - 1) as u32
+    }
+
+    fn edge_len(&self, node: u32) -> usize {
+        let node = &self.nodes[node as usize];
+        node.end.min(self.text.len()) - node.start
+    }
+
+    /// Appends `byte` to the text, giving a leaf to every pending suffix that can't be
+    /// extended by it, from the longest, until one can.
+    pub fn push(&mut self, byte: u8) {
+        self.text.push(byte);
+        let position = self.text.len() - 1;
+        self.remainder += 1;
+        // internal node created in this step that still needs its suffix link
+        let mut unlinked: Option<u32> = None;
+        while self.remainder > 0 {
+            if self.active_len == 0 {
+                self.active_edge = position;
+            }
+            let edge_byte = self.text[self.active_edge];
+            let suffix = position + 1 - self.remainder;
+            match self.nodes[self.active_node as usize].child(edge_byte) {
+                None => {
+                    let leaf = self.new_node(position, OPEN, suffix);
+                    self.nodes[self.active_node as usize].set_child(edge_byte, leaf);
+                    if let Some(node) = unlinked.take() {
+                        self.nodes[node as usize].link = self.active_node;
+                    }
+                }
+                Some(next) => {
+                    let len = self.edge_len(next);
+                    if self.active_len >= len {
+                        // the active point lies past this edge: walk down
+                        self.active_node = next;
+                        self.active_edge += len;
+                        self.active_len -= len;
+                        continue;
+                    }
+                    let split_at = self.nodes[next as usize].start + self.active_len;
+                    if self.text[split_at] == byte {
+                        // already in the tree, and so are all the shorter pending suffixes
+                        if let Some(node) = unlinked.take() {
+                            self.nodes[node as usize].link = self.active_node;
+                        }
+                        self.active_len += 1;
+                        break;
+                    }
+                    let start = self.nodes[next as usize].start;
+                    let split = self.new_node(start, split_at, 0);
+                    self.nodes[self.active_node as usize].set_child(edge_byte, split);
+                    let leaf = self.new_node(position, OPEN, suffix);
+                    self.nodes[split as usize].set_child(byte, leaf);
+                    self.nodes[next as usize].start = split_at;
+                    self.nodes[split as usize].set_child(self.text[split_at], next);
+                    if let Some(node) = unlinked.replace(split) {
+                        self.nodes[node as usize].link = split;
+                    }
+                }
+            }
+            self.remainder -= 1;
+            if self.active_node == ROOT && self.active_len > 0 {
+                self.active_len -= 1;
+                self.active_edge = position + 1 - self.remainder;
+            } else if self.active_node != ROOT {
+                self.active_node = self.nodes[self.active_node as usize].link;
+            }
+        }
+    }
+
+    /// Appends every byte of `bytes` to the text.
+    pub fn extend(&mut self, bytes: impl AsRef<[u8]>) {
+        for &byte in bytes.as_ref() {
+            self.push(byte);
+        }
+    }
+
+    /// Node at or below the point where `pattern` ends, if it is a substring.
+    fn find(&self, pattern: &[u8]) -> Option<u32> {
+        let mut node = ROOT;
+        let mut matched = 0;
+        while matched < pattern.len() {
+            node = self.nodes[node as usize].child(pattern[matched])?;
+            let start = self.nodes[node as usize].start;
+            let len = self.edge_len(node).min(pattern.len() - matched);
+            if self.text[start..start + len] != pattern[matched..matched + len] {
+                return None;
+            }
+            matched += len;
+        }
+        Some(node)
+    }
+
+    /// Starts of the suffixes with a leaf below `node`.
+    fn leaf_suffixes(&self, node: u32) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = vec![node];
+        std::iter::from_fn(move || loop {
+            let node = &self.nodes[stack.pop()? as usize];
+            if node.end == OPEN {
+                return Some(node.suffix);
+            }
+            stack.extend(node.children.iter().map(|&(_, child)| child));
+        })
+    }
+
+    /// Starts of the suffixes still implicit that begin with `pattern`. They are the shortest
+    /// `remainder` suffixes, so they start after every suffix with a leaf.
+    fn pending_matches<'a>(&'a self, pattern: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        (self.text.len() - self.remainder..self.text.len())
+            .filter(move |&start| self.text[start..].starts_with(pattern))
+    }
+}
+
+impl SubstringIndex for SuffixTree {
+    fn text_len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// O(m) to find the pattern, then one step per node below it and a comparison for each
+    /// implicit suffix, of which there are few unless the text ends in a long repeat.
+    fn count(&self, pattern: &[u8]) -> usize {
+        match self.find(pattern) {
+            Some(node) => self.leaf_suffixes(node).count() + self.pending_matches(pattern).count(),
+            None => 0,
+        }
+    }
+
+    fn first_occurrence(&self, pattern: &[u8]) -> Option<usize> {
+        let node = self.find(pattern)?;
+        self.leaf_suffixes(node)
+            .min()
+            .or_else(|| self.pending_matches(pattern).next())
+    }
+
+    /// Every substring ends at a distinct point of the tree, so this is the total length of
+    /// the edges, in O(n).
+    fn distinct_substrings(&self) -> usize {
+        (1..self.nodes.len() as u32)
+            .map(|node| self.edge_len(node))
+            .sum()
+    }
+}
+
+impl Default for SuffixTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suffix_automaton::SuffixAutomaton;
+    use rand::Rng;
+
+    #[test]
+    fn agrees_with_suffix_automaton_while_growing() {
+        let mut rng = rand::thread_rng();
+        for alphabet in [1, 2, 3, 26] {
+            let mut tree = SuffixTree::new();
+            let mut automaton = SuffixAutomaton::new();
+            for _ in 0..300 {
+                let byte = b'a' + rng.gen_range(0..alphabet);
+                tree.push(byte);
+                automaton.push(byte);
+                assert_eq!(tree.distinct_substrings(), automaton.distinct_substrings());
+                for _ in 0..10 {
+                    let len = rng.gen_range(0..6);
+                    let pattern: Vec<u8> = (0..len)
+                        .map(|_| b'a' + rng.gen_range(0..alphabet))
+                        .collect();
+                    assert_eq!(tree.count(&pattern), automaton.count(&pattern));
+                    assert_eq!(
+                        tree.first_occurrence(&pattern),
+                        automaton.first_occurrence(&pattern)
+                    );
+                }
+            }
+            assert!(tree.node_count() <= 2 * tree.len());
+        }
+    }
+
+    #[test]
+    fn every_suffix_gets_a_leaf_once_it_is_unique() {
+        let mut tree = SuffixTree::new();
+        tree.extend("abcabx");
+        // "abcabx" has no repeated suffix, so the tree is explicit
+        assert_eq!(tree.remainder, 0);
+        let mut leaves: Vec<usize> = tree.leaf_suffixes(ROOT).collect();
+        leaves.sort_unstable();
+        assert_eq!(leaves, (0..6).collect::<Vec<_>>());
+        assert_eq!(tree.count(b"ab"), 2);
+        assert_eq!(tree.first_occurrence(b"bx"), Some(4));
+
+        tree.extend("ab");
+        // "ab" and "b" are pending
+        assert_eq!(tree.remainder, 2);
+        assert_eq!(tree.count(b"ab"), 3);
+        assert_eq!(tree.count(b"b"), 3);
+        assert_eq!(tree.first_occurrence(b"bc"), Some(1));
+        assert!(!tree.contains(b"ba"));
+    }
+}
@@ -0,0 +1,464 @@
+// Ternary search tree: a byte trie whose nodes are binary search trees over the next byte,
+// so a node costs three links instead of a child table. Used by the trie binary.
+
+use std::cmp::Ordering;
+
+use crate::codec::{take, DecodeError, Encode};
+
+const NIL: u32 = u32::MAX;
+const MAGIC: &[u8; 4] = b"TST1";
+
+struct Node<V> {
+    byte: u8,
+    // subtrees of keys with a smaller / the same / a larger byte at this position
+    lo: u32,
+    eq: u32,
+    hi: u32,
+    value: Option<V>,
+}
+
+/// Map from non-empty byte strings to values, with nodes kept in one vector.
+pub struct TernarySearchTree<V> {
+    nodes: Vec<Node<V>>,
+    root: u32,
+    len: usize,
+}
+
+impl<V> TernarySearchTree<V> {
+    pub fn new() -> Self {
+        TernarySearchTree {
+            nodes: Vec::new(),
+            root: NIL,
+            len: 0,
+        }
+    }
+
+    /// Tree of `entries`, inserted median first and then each half the same way, so for
+    /// sorted entries every byte-level search tree comes out balanced.
+    pub fn from_sorted<K: AsRef<[u8]>>(entries: Vec<(K, V)>) -> Self {
+        let mut tree = TernarySearchTree::new();
+        let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+        let mut ranges = vec![(0, entries.len())];
+        while let Some((start, end)) = ranges.pop() {
+            if start == end {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            let (key, value) = entries[middle].take().expect("each entry is inserted once");
+            tree.insert(key, value);
+            ranges.push((middle + 1, end));
+            ranges.push((start, middle));
+        }
+        tree
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[allow(dead_code)]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn new_node(&mut self, byte: u8) -> u32 {
+        self.nodes.push(Node {
+            byte,
+            lo: NIL,
+            eq: NIL,
+            hi: NIL,
+            value: None,
+        });
+        (self.nodes.len() - 1) as u32
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced. Panics if `key` is empty.
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        let key = key.as_ref();
+        assert!(!key.is_empty(), "empty key");
+        if self.root == NIL {
+            self.root = self.new_node(key[0]);
+        }
+        let mut index = self.root;
+        let mut depth = 0;
+        loop {
+            let byte = key[depth];
+            let node = &self.nodes[index as usize];
+            let (next, is_eq) = match byte.cmp(&node.byte) {
+                Ordering::Less => (node.lo, false),
+                Ordering::Greater => (node.hi, false),
+                Ordering::Equal if depth + 1 == key.len() => break,
+                Ordering::Equal => (node.eq, true),
+            };
+            let child_byte = if is_eq { key[depth + 1] } else { byte };
+            let next = if next == NIL {
+                let child = self.new_node(child_byte);
+                let node = &mut self.nodes[index as usize];
+                match (is_eq, byte < node.byte) {
+                    (true, _) => node.eq = child,
+                    (false, true) => node.lo = child,
+                    (false, false) => node.hi = child,
+                }
+                child
+            } else {
+                next
+            };
+            depth += is_eq as usize;
+            index = next;
+        }
+        let old = self.nodes[index as usize].value.replace(value);
+        self.len += old.is_none() as usize;
+        old
+    }
+
+    /// Node where `key` ends, if its path exists.
+    fn find(&self, key: &[u8]) -> Option<u32> {
+        let mut index = self.root;
+        let mut depth = 0;
+        while index != NIL && depth < key.len() {
+            let node = &self.nodes[index as usize];
+            if key[depth] < node.byte {
+                index = node.lo;
+            } else if key[depth] > node.byte {
+                index = node.hi;
+            } else if depth + 1 == key.len() {
+                return Some(index);
+            } else {
+                index = node.eq;
+                depth += 1;
+            }
+        }
+        None
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        let index = self.find(key.as_ref())?;
+        self.nodes[index as usize].value.as_ref()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Entries whose key starts with `prefix`, in key order.
+    #[allow(dead_code)]
+    pub fn prefix_search(&self, prefix: impl AsRef<[u8]>) -> Vec<(Vec<u8>, &V)> {
+        let prefix = prefix.as_ref();
+        let mut found = Vec::new();
+        let start = if prefix.is_empty() {
+            self.root
+        } else {
+            let Some(index) = self.find(prefix) else {
+                return found;
+            };
+            if let Some(value) = &self.nodes[index as usize].value {
+                found.push((prefix.to_vec(), value));
+            }
+            self.nodes[index as usize].eq
+        };
+        self.collect_subtree(start, prefix.to_vec(), &mut found);
+        found
+    }
+
+    /// In-order walk of the subtree at `index`, `path` spelling the bytes above it.
+    #[allow(dead_code)]
+    fn collect_subtree<'a>(
+        &'a self,
+        index: u32,
+        mut path: Vec<u8>,
+        found: &mut Vec<(Vec<u8>, &'a V)>,
+    ) {
+        // (node, bytes of `path` above it, lo subtree done); a node is entered once to queue
+        // its lo subtree, and again to emit its key and queue its eq subtree
+        let mut stack = vec![(index, path.len(), false)];
+        while let Some((index, depth, visited)) = stack.pop() {
+            if index == NIL {
+                continue;
+            }
+            let node = &self.nodes[index as usize];
+            if visited {
+                path.truncate(depth);
+                path.push(node.byte);
+                if let Some(value) = &node.value {
+                    found.push((path.clone(), value));
+                }
+                stack.push((node.eq, depth + 1, false));
+            } else {
+                stack.push((node.hi, depth, false));
+                stack.push((index, depth, true));
+                stack.push((node.lo, depth, false));
+            }
+        }
+    }
+
+    /// Entries whose key is a prefix of `text`, shortest first, with the key's length.
+    #[allow(dead_code)]
+    pub fn common_prefix_search(&self, text: impl AsRef<[u8]>) -> Vec<(usize, &V)> {
+        let text = text.as_ref();
+        let mut found = Vec::new();
+        let mut index = self.root;
+        let mut depth = 0;
+        while index != NIL && depth < text.len() {
+            let node = &self.nodes[index as usize];
+            if text[depth] < node.byte {
+                index = node.lo;
+            } else if text[depth] > node.byte {
+                index = node.hi;
+            } else {
+                depth += 1;
+                if let Some(value) = &node.value {
+                    found.push((depth, value));
+                }
+                index = node.eq;
+            }
+        }
+        found
+    }
+}
+
+impl<V: Encode> TernarySearchTree<V> {
+    /// The node vector as is, so loading doesn't rebuild anything.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        self.len.encode(&mut bytes);
+        self.root.encode(&mut bytes);
+        self.nodes.len().encode(&mut bytes);
+        for node in &self.nodes {
+            node.byte.encode(&mut bytes);
+            node.lo.encode(&mut bytes);
+            node.eq.encode(&mut bytes);
+            node.hi.encode(&mut bytes);
+            node.value.is_some().encode(&mut bytes);
+            if let Some(value) = &node.value {
+                value.encode(&mut bytes);
+            }
+        }
+        bytes
+    }
+
+    #[allow(dead_code)]
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        if take(&mut bytes, MAGIC.len())? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let len = usize::decode(&mut bytes)?;
+        let root = u32::decode(&mut bytes)?;
+        let count = usize::decode(&mut bytes)?;
+        let mut nodes = Vec::new();
+        for _ in 0..count {
+            nodes.push(Node {
+                byte: u8::decode(&mut bytes)?,
+                lo: u32::decode(&mut bytes)?,
+                eq: u32::decode(&mut bytes)?,
+                hi: u32::decode(&mut bytes)?,
+                value: match bool::decode(&mut bytes)? {
+                    true => Some(V::decode(&mut bytes)?),
+                    false => None,
+                },
+            });
+        }
+
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        let in_range = |link: u32| link == NIL || (link as usize) < nodes.len();
+        let links_in_range = nodes
+            .iter()
+            .all(|node| in_range(node.lo) && in_range(node.eq) && in_range(node.hi));
+        if !in_range(root) || !links_in_range {
+            return Err(DecodeError::Invalid("link out of range"));
+        }
+        // every node hangs off the root exactly once, so a lookup never loops or aliases
+        let mut seen = vec![false; nodes.len()];
+        let mut stack = vec![root];
+        while let Some(index) = stack.pop() {
+            if index == NIL {
+                continue;
+            }
+            if std::mem::replace(&mut seen[index as usize], true) {
+                return Err(DecodeError::Invalid("node reached twice"));
+            }
+            let node = &nodes[index as usize];
+            stack.extend([node.lo, node.eq, node.hi]);
+        }
+        if seen.contains(&false) {
+            return Err(DecodeError::Invalid("unreachable node"));
+        }
+        if nodes.iter().filter(|node| node.value.is_some()).count() != len {
+            return Err(DecodeError::Invalid("entry count"));
+        }
+        Ok(TernarySearchTree { nodes, root, len })
+    }
+}
+
+impl<V> Default for TernarySearchTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    fn height<V>(tree: &TernarySearchTree<V>, index: u32) -> usize {
+        if index == NIL {
+            return 0;
+        }
+        let node = &tree.nodes[index as usize];
+        1 + height(tree, node.lo).max(height(tree, node.hi))
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        let mut rng = rand::thread_rng();
+        let mut tree = TernarySearchTree::new();
+        let mut expected = BTreeMap::new();
+        for i in 0..5_000 {
+            let len = rng.gen_range(1..6);
+            let key: Vec<u8> = (0..len).map(|_| rng.gen_range(b'a'..b'e')).collect();
+            assert_eq!(tree.insert(&key, i), expected.insert(key, i));
+        }
+        assert_eq!(tree.len(), expected.len());
+        let all: Vec<_> = tree
+            .prefix_search([])
+            .into_iter()
+            .map(|(key, &value)| (key, value))
+            .collect();
+        assert_eq!(all, expected.clone().into_iter().collect::<Vec<_>>());
+        for prefix in [&b"a"[..], b"bc", b"dddd", b"e"] {
+            let under: Vec<_> = tree
+                .prefix_search(prefix)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect();
+            let want: Vec<_> = expected
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect();
+            assert_eq!(under, want);
+        }
+        let text = b"abcdabcd";
+        let prefixes: Vec<_> = tree
+            .common_prefix_search(text)
+            .into_iter()
+            .map(|(len, &value)| (len, value))
+            .collect();
+        let want: Vec<_> = (1..=text.len())
+            .filter_map(|len| Some((len, *expected.get(&text[..len])?)))
+            .collect();
+        assert_eq!(prefixes, want);
+    }
+
+    #[test]
+    fn median_insertion_balances_sorted_input() {
+        let words: Vec<(String, usize)> = (0..26 * 26)
+            .map(|i| {
+                let word = format!(
+                    "{}{}",
+                    (b'a' + (i / 26) as u8) as char,
+                    (b'a' + (i % 26) as u8) as char
+                );
+                (word, i)
+            })
+            .collect();
+        let mut in_order = TernarySearchTree::new();
+        for (word, i) in &words {
+            in_order.insert(word, *i);
+        }
+        let balanced = TernarySearchTree::from_sorted(words);
+        // inserting sorted keys one by one degenerates the first-byte tree into a list
+        assert_eq!(height(&in_order, in_order.root), 26);
+        assert_eq!(height(&balanced, balanced.root), 5);
+        assert_eq!(balanced.get("qz"), Some(&(16 * 26 + 25)));
+        assert_eq!(balanced.len(), 26 * 26);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let tree = TernarySearchTree::from_sorted(vec![
+            ("car", "Auto".to_string()),
+            ("cart", "Karren".to_string()),
+            ("cat", "Katze".to_string()),
+        ]);
+        let bytes = tree.to_bytes();
+        let loaded = TernarySearchTree::<String>::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.get("cart").map(String::as_str), Some("Karren"));
+        assert_eq!(loaded.common_prefix_search("carts").len(), 2);
+
+        assert_eq!(
+            TernarySearchTree::<String>::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            TernarySearchTree::<String>::from_bytes(b"CHD1").err(),
+            Some(DecodeError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn long_keys_and_chains_do_not_recurse() {
+        let mut tree = TernarySearchTree::new();
+        let long = vec![b'a'; 200_000];
+        tree.insert(&long, 0);
+        // ascending single-byte keys chain through the hi links
+        for byte in b'b'..=u8::MAX {
+            tree.insert([byte], byte as u32);
+        }
+        let loaded = TernarySearchTree::<u32>::from_bytes(&tree.to_bytes()).unwrap();
+        let found = loaded.prefix_search(b"a");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, long);
+        let all = loaded.prefix_search([]);
+        assert_eq!(all.len(), 1 + (u8::MAX - b'a') as usize);
+        assert!(all.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn rejects_cycles_and_shared_nodes() {
+        let node = |lo, eq, hi, value| Node {
+            byte: b'a',
+            lo,
+            eq,
+            hi,
+            value,
+        };
+        // the eq link of the second node leads back to the root
+        let cyclic = TernarySearchTree {
+            nodes: vec![node(NIL, 1, NIL, None), node(NIL, 0, NIL, Some(1u32))],
+            root: 0,
+            len: 1,
+        };
+        // both the lo and the hi link of the root lead to the same node
+        let shared = TernarySearchTree {
+            nodes: vec![node(1, NIL, 1, Some(1u32)), node(NIL, NIL, NIL, Some(2))],
+            root: 0,
+            len: 2,
+        };
+        let orphan = TernarySearchTree {
+            nodes: vec![
+                node(NIL, NIL, NIL, Some(1u32)),
+                node(NIL, NIL, NIL, Some(2)),
+            ],
+            root: 0,
+            len: 2,
+        };
+        for tree in [cyclic, shared, orphan] {
+            assert!(matches!(
+                TernarySearchTree::<u32>::from_bytes(&tree.to_bytes()),
+                Err(DecodeError::Invalid(_))
+            ));
+        }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod map;
 pub mod random;
 pub mod treap;
\ No newline at end of file
@@ -0,0 +1,765 @@
+use std::cmp::Ordering;
+use std::collections::Bound;
+use std::mem::replace;
+use std::ops::RangeBounds;
+
+extern crate rand;
+use rand::Rng;
+
+/// Handle to a node stored in the arena. A handle stays valid until its node is removed and is
+/// never resolved again afterwards, even once the slot has been reused for another node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+struct Node<K, V> {
+    key: K,
+    val: V,
+    priority: u64,
+    size: usize,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+struct Slot<K, V> {
+    generation: u32,
+    node: Option<Node<K, V>>,
+}
+
+/// Arena of treap nodes shared by any number of treap instances. Nodes of every instance live
+/// in one `Vec` and refer to each other by index; removed slots are kept on a free-list and
+/// handed out again by later inserts.
+pub struct Treap<K: Ord, V> {
+    slots: Vec<Slot<K, V>>,
+    freelist: Vec<usize>,
+    instances: Vec<Option<usize>>,
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            freelist: Vec::new(),
+            instances: Vec::new(),
+        }
+    }
+
+    pub fn new_instance(&mut self) -> usize {
+        self.instances.push(None);
+        self.instances.len() - 1
+    }
+
+    ///helper function
+    fn new_slot(&mut self, key: K, val: V) -> usize {
+        let node = Node {
+            key,
+            val,
+            priority: Self::gen_priority_random(),
+            size: 1,
+            parent: None,
+            left: None,
+            right: None,
+        };
+        match self.freelist.pop() {
+            Some(idx) => {
+                self.slots[idx].node = Some(node);
+                idx
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    ///helper function
+    fn free_slot(&mut self, idx: usize) -> Node<K, V> {
+        let slot = &mut self.slots[idx];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.freelist.push(idx);
+        slot.node.take().expect("slot already free")
+    }
+
+    ///helper function
+    fn gen_priority_random() -> u64 {
+        rand::thread_rng().gen()
+    }
+
+    fn node(&self, idx: usize) -> &Node<K, V> {
+        self.slots[idx].node.as_ref().expect("slot is free")
+    }
+
+    fn node_mut(&mut self, idx: usize) -> &mut Node<K, V> {
+        self.slots[idx].node.as_mut().expect("slot is free")
+    }
+
+    fn id(&self, idx: usize) -> NodeId {
+        NodeId {
+            index: idx,
+            generation: self.slots[idx].generation,
+        }
+    }
+
+    fn resolve(&self, id: NodeId) -> Option<usize> {
+        match self.slots.get(id.index) {
+            Some(slot) if slot.generation == id.generation && slot.node.is_some() => Some(id.index),
+            _ => None,
+        }
+    }
+
+    fn size(&self, n: Option<usize>) -> usize {
+        n.map(|idx| self.node(idx).size).unwrap_or(0)
+    }
+
+    fn update(&mut self, n: usize) {
+        let size = 1 + self.size(self.node(n).left) + self.size(self.node(n).right);
+        self.node_mut(n).size = size;
+    }
+
+    fn set_left(&mut self, parent: usize, child: Option<usize>) {
+        self.node_mut(parent).left = child;
+        if let Some(x) = child {
+            self.node_mut(x).parent = Some(parent);
+        }
+    }
+
+    fn set_right(&mut self, parent: usize, child: Option<usize>) {
+        self.node_mut(parent).right = child;
+        if let Some(x) = child {
+            self.node_mut(x).parent = Some(parent);
+        }
+    }
+
+    /// splits a subtree into keys before `key` and the rest; `to_left` sends an equal key left
+    fn split_at(
+        &mut self,
+        t: Option<usize>,
+        key: &K,
+        to_left: bool,
+    ) -> (Option<usize>, Option<usize>) {
+        let n = match t {
+            Some(n) => n,
+            None => return (None, None),
+        };
+        let ordering = self.node(n).key.cmp(key);
+        self.node_mut(n).parent = None;
+        if ordering == Ordering::Less || to_left && ordering == Ordering::Equal {
+            let (l, r) = self.split_at(self.node(n).right, key, to_left);
+            self.set_right(n, l);
+            self.update(n);
+            (Some(n), r)
+        } else {
+            let (l, r) = self.split_at(self.node(n).left, key, to_left);
+            self.set_left(n, r);
+            self.update(n);
+            (l, Some(n))
+        }
+    }
+
+    /// merges two subtrees, every key of `a` must be less than every key of `b`
+    fn merge_at(&mut self, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        let (a, b) = match (a, b) {
+            (None, b) => return b.map(|b| self.detach(b)),
+            (a, None) => return a.map(|a| self.detach(a)),
+            (Some(a), Some(b)) => (a, b),
+        };
+        if self.node(a).priority > self.node(b).priority {
+            let r = self.merge_at(self.node(a).right, Some(b));
+            self.set_right(a, r);
+            self.update(a);
+            Some(self.detach(a))
+        } else {
+            let l = self.merge_at(Some(a), self.node(b).left);
+            self.set_left(b, l);
+            self.update(b);
+            Some(self.detach(b))
+        }
+    }
+
+    fn detach(&mut self, n: usize) -> usize {
+        self.node_mut(n).parent = None;
+        n
+    }
+
+    fn find_index(&self, instance: usize, k: &K) -> Option<usize> {
+        let mut cur = self.instances[instance];
+        while let Some(n) = cur {
+            let node = self.node(n);
+            cur = match k.cmp(&node.key) {
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+                Ordering::Equal => return Some(n),
+            };
+        }
+        None
+    }
+
+    pub fn key(&self, id: NodeId) -> Option<&K> {
+        self.resolve(id).map(|idx| &self.node(idx).key)
+    }
+
+    pub fn val(&self, id: NodeId) -> Option<&V> {
+        self.resolve(id).map(|idx| &self.node(idx).val)
+    }
+
+    pub fn val_mut(&mut self, id: NodeId) -> Option<&mut V> {
+        self.resolve(id).map(move |idx| &mut self.node_mut(idx).val)
+    }
+
+    pub fn len(&self, instance: usize) -> usize {
+        self.size(self.instances[instance])
+    }
+
+    pub fn is_empty(&self, instance: usize) -> bool {
+        self.instances[instance].is_none()
+    }
+
+    /// number of slots allocated by the arena, free ones included
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn find(&self, instance: usize, k: &K) -> Option<NodeId> {
+        self.find_index(instance, k).map(|idx| self.id(idx))
+    }
+
+    pub fn get(&self, instance: usize, k: &K) -> Option<&V> {
+        self.find_index(instance, k).map(|idx| &self.node(idx).val)
+    }
+
+    pub fn get_mut(&mut self, instance: usize, k: &K) -> Option<&mut V> {
+        self.find_index(instance, k)
+            .map(move |idx| &mut self.node_mut(idx).val)
+    }
+
+    ///returns the handle of the item and the previous value if an item with the same key existed
+    pub fn insert(&mut self, instance: usize, k: K, val: V) -> (NodeId, Option<V>) {
+        if let Some(idx) = self.find_index(instance, &k) {
+            let old = replace(&mut self.node_mut(idx).val, val);
+            return (self.id(idx), Some(old));
+        }
+        let (l, r) = self.split_at(self.instances[instance], &k, false);
+        let idx = self.new_slot(k, val);
+        let l = self.merge_at(l, Some(idx));
+        self.instances[instance] = self.merge_at(l, r);
+        (self.id(idx), None)
+    }
+
+    pub fn remove(&mut self, instance: usize, k: &K) -> Option<V> {
+        let idx = self.find_index(instance, k)?;
+        Some(self.remove_index(instance, idx).1)
+    }
+
+    ///returns None if the handle is stale or the node belongs to another instance
+    pub fn remove_node(&mut self, instance: usize, id: NodeId) -> Option<(K, V)> {
+        let idx = self.resolve(id)?;
+        if self.instances[instance] != Some(self.root_of(idx)) {
+            return None;
+        }
+        Some(self.remove_index(instance, idx))
+    }
+
+    fn root_of(&self, mut idx: usize) -> usize {
+        while let Some(p) = self.node(idx).parent {
+            idx = p;
+        }
+        idx
+    }
+
+    /// `idx` must be a node of `instance`
+    fn remove_index(&mut self, instance: usize, idx: usize) -> (K, V) {
+        let (parent, left, right) = {
+            let node = self.node(idx);
+            (node.parent, node.left, node.right)
+        };
+        let merged = self.merge_at(left, right);
+        match parent {
+            None => self.instances[instance] = merged,
+            Some(p) => {
+                if self.node(p).left == Some(idx) {
+                    self.set_left(p, merged);
+                } else {
+                    self.set_right(p, merged);
+                }
+                let mut cur = Some(p);
+                while let Some(x) = cur {
+                    self.update(x);
+                    cur = self.node(x).parent;
+                }
+            }
+        }
+        let node = self.free_slot(idx);
+        (node.key, node.val)
+    }
+
+    pub fn successor(&self, id: NodeId) -> Option<NodeId> {
+        let idx = self.resolve(id)?;
+        if let Some(mut cur) = self.node(idx).right {
+            while let Some(x) = self.node(cur).left {
+                cur = x;
+            }
+            return Some(self.id(cur));
+        }
+        let mut cur = idx;
+        while let Some(p) = self.node(cur).parent {
+            if self.node(p).left == Some(cur) {
+                return Some(self.id(p));
+            }
+            cur = p;
+        }
+        None
+    }
+
+    pub fn predecessor(&self, id: NodeId) -> Option<NodeId> {
+        let idx = self.resolve(id)?;
+        if let Some(mut cur) = self.node(idx).left {
+            while let Some(x) = self.node(cur).right {
+                cur = x;
+            }
+            return Some(self.id(cur));
+        }
+        let mut cur = idx;
+        while let Some(p) = self.node(cur).parent {
+            if self.node(p).right == Some(cur) {
+                return Some(self.id(p));
+            }
+            cur = p;
+        }
+        None
+    }
+
+    /// number of keys strictly less than `k`
+    pub fn rank(&self, instance: usize, k: &K) -> usize {
+        let mut rank = 0;
+        let mut cur = self.instances[instance];
+        while let Some(n) = cur {
+            let node = self.node(n);
+            if node.key < *k {
+                rank += self.size(node.left) + 1;
+                cur = node.right;
+            } else {
+                cur = node.left;
+            }
+        }
+        rank
+    }
+
+    /// handle of the item with the given 0-based rank
+    pub fn select(&self, instance: usize, mut rank: usize) -> Option<NodeId> {
+        let mut cur = self.instances[instance];
+        while let Some(n) = cur {
+            let node = self.node(n);
+            let left_size = self.size(node.left);
+            match rank.cmp(&left_size) {
+                Ordering::Less => cur = node.left,
+                Ordering::Equal => return Some(self.id(n)),
+                Ordering::Greater => {
+                    rank -= left_size + 1;
+                    cur = node.right;
+                }
+            }
+        }
+        None
+    }
+
+    fn first_from(&self, instance: usize, start: Bound<&K>) -> Option<usize> {
+        let mut best = None;
+        let mut cur = self.instances[instance];
+        while let Some(n) = cur {
+            let node = self.node(n);
+            let inside = match start {
+                Bound::Included(k) => node.key >= *k,
+                Bound::Excluded(k) => node.key > *k,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                best = Some(n);
+                cur = node.left;
+            } else {
+                cur = node.right;
+            }
+        }
+        best
+    }
+
+    pub fn iter(&self, instance: usize) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.query_range(instance, ..).into_iter().map(move |id| {
+            let node = self.node(id.index);
+            (&node.key, &node.val)
+        })
+    }
+
+    /// get handles of items with key in range, in key order
+    pub fn query_range(&self, instance: usize, range: impl RangeBounds<K>) -> Vec<NodeId> {
+        let mut ret = vec![];
+        let mut cur = self
+            .first_from(instance, range.start_bound())
+            .map(|idx| self.id(idx));
+        while let Some(id) = cur {
+            if !range.contains(&self.node(id.index).key) {
+                break;
+            }
+            ret.push(id);
+            cur = self.successor(id);
+        }
+        ret
+    }
+
+    /// removes items with key in range and returns them in key order
+    pub fn remove_key_range(&mut self, instance: usize, range: impl RangeBounds<K>) -> Vec<(K, V)> {
+        let root = self.instances[instance];
+        let (left, rest) = match range.start_bound() {
+            Bound::Included(k) => self.split_at(root, k, false),
+            Bound::Excluded(k) => self.split_at(root, k, true),
+            Bound::Unbounded => (None, root),
+        };
+        let (middle, right) = match range.end_bound() {
+            Bound::Included(k) => self.split_at(rest, k, true),
+            Bound::Excluded(k) => self.split_at(rest, k, false),
+            Bound::Unbounded => (rest, None),
+        };
+        self.instances[instance] = self.merge_at(left, right);
+
+        let mut ret = Vec::with_capacity(self.size(middle));
+        self.drain_subtree(middle, &mut ret);
+        ret
+    }
+
+    fn drain_subtree(&mut self, t: Option<usize>, out: &mut Vec<(K, V)>) {
+        if let Some(n) = t {
+            let (left, right) = (self.node(n).left, self.node(n).right);
+            self.drain_subtree(left, out);
+            let node = self.free_slot(n);
+            out.push((node.key, node.val));
+            self.drain_subtree(right, out);
+        }
+    }
+
+    /// split given treap instance in two: [ x | x.key < k ] stays in `instance`,
+    /// [ x | x.key >= k ] moves to `dest`, which must be empty (e.g. left so by `merge`)
+    pub fn split(&mut self, instance: usize, k: &K, dest: usize) {
+        assert!(self.instances[dest].is_none(), "split into a non-empty instance");
+        let (a, b) = self.split_at(self.instances[instance], k, false);
+        self.instances[instance] = a;
+        self.instances[dest] = b;
+    }
+
+    /// merges 2 trees and returns handle to a combined tree, leaving `inst_b` empty;
+    /// every key of `inst_a` must be less than every key of `inst_b`
+    pub fn merge(&mut self, inst_a: usize, inst_b: usize) -> usize {
+        let (a, b) = (self.instances[inst_a], self.instances[inst_b]);
+        if let (Some(a), Some(b)) = (a, b) {
+            let mut max_a = a;
+            while let Some(x) = self.node(max_a).right {
+                max_a = x;
+            }
+            let mut min_b = b;
+            while let Some(x) = self.node(min_b).left {
+                min_b = x;
+            }
+            assert!(self.node(max_a).key < self.node(min_b).key);
+        }
+        self.instances[inst_a] = self.merge_at(a, b);
+        self.instances[inst_b] = None;
+        inst_a
+    }
+
+    /// average depth of the leaves of given treap instance
+    pub fn dbg_depth(&self, instance: usize) -> f32 {
+        let mut leaf_depths = vec![];
+        let mut q: Vec<(usize, usize)> = self.instances[instance]
+            .map(|r| (r, 1))
+            .into_iter()
+            .collect();
+
+        while let Some((cur, depth)) = q.pop() {
+            let node = self.node(cur);
+            if node.left.is_none() && node.right.is_none() {
+                leaf_depths.push(depth);
+            }
+            q.extend(node.left.map(|x| (x, depth + 1)));
+            q.extend(node.right.map(|x| (x, depth + 1)));
+        }
+
+        if leaf_depths.is_empty() {
+            return 0.;
+        }
+        let total: usize = leaf_depths.iter().sum();
+        total as f32 / leaf_depths.len() as f32
+    }
+}
+
+impl<K: Ord, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+use std::time::Instant;
+
+fn main() {
+    let n = 10_000; // Adjust N based on performance requirements
+    let mut rng = rand::thread_rng();
+
+    let mut treap = Treap::new();
+
+    // Random Order Tree
+    let random_tree = treap.new_instance();
+    let start_random_insert = Instant::now();
+    for _ in 0..n {
+        let value = rng.gen_range(0..n);
+        treap.insert(random_tree, value, ());
+    }
+    let random_insert_duration = start_random_insert.elapsed();
+
+    // Ascending Order Tree
+    let ascending_tree = treap.new_instance();
+    let start_ascending_insert = Instant::now();
+    for value in 0..n {
+        treap.insert(ascending_tree, value, ());
+    }
+    let ascending_insert_duration = start_ascending_insert.elapsed();
+
+    // Search Operation
+    let start_random_search = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        treap.get(random_tree, &value);
+    }
+    let random_search_duration = start_random_search.elapsed();
+
+    let start_ascending_search = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        treap.get(ascending_tree, &value);
+    }
+    let ascending_search_duration = start_ascending_search.elapsed();
+
+    // Delete Operation
+    let start_random_delete = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        treap.remove(random_tree, &value);
+    }
+    let random_delete_duration = start_random_delete.elapsed();
+
+    let start_ascending_delete = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        treap.remove(ascending_tree, &value);
+    }
+    let ascending_delete_duration = start_ascending_delete.elapsed();
+
+    // Results
+    println!("Insertion (Random Order): {:?}", random_insert_duration);
+    println!(
+        "Insertion (Ascending Order): {:?}",
+        ascending_insert_duration
+    );
+    println!("Search (Random Order): {:?}", random_search_duration);
+    println!("Search (Ascending Order): {:?}", ascending_search_duration);
+    println!("Delete (Random Order): {:?}", random_delete_duration);
+    println!("Delete (Ascending Order): {:?}", ascending_delete_duration);
+    println!(
+        "Average leaf depth (Random / Ascending): {:.2} / {:.2}",
+        treap.dbg_depth(random_tree),
+        treap.dbg_depth(ascending_tree)
+    );
+    println!("Arena slots: {}", treap.capacity());
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "treap/map.rs"]
+mod treap;
+
+#[cfg(test)]
+mod tests {
+    use super::treap::TreapMap;
+    use super::*;
+
+    #[test]
+    fn agrees_with_treap_map_on_random_workload() {
+        let mut rng = rand::thread_rng();
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        let mut expected = TreapMap::new();
+
+        for _ in 0..20_000 {
+            let key = rng.gen_range(0..500u32);
+            match rng.gen_range(0..3) {
+                0 => {
+                    let val = rng.gen::<u64>();
+                    assert_eq!(treap.insert(inst, key, val).1, expected.insert(key, val));
+                }
+                1 => assert_eq!(treap.remove(inst, &key), expected.remove(&key)),
+                _ => assert_eq!(treap.get(inst, &key), expected.search(&key)),
+            }
+            assert_eq!(treap.len(inst), expected.len());
+        }
+    }
+
+    #[test]
+    fn rank_and_select_follow_key_order() {
+        let mut rng = rand::thread_rng();
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        let mut keys = vec![];
+        for _ in 0..1_000 {
+            let key = rng.gen_range(0..10_000u32);
+            if treap.insert(inst, key, ()).1.is_none() {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+
+        for (rank, key) in keys.iter().enumerate() {
+            assert_eq!(treap.rank(inst, key), rank);
+            assert_eq!(treap.key(treap.select(inst, rank).unwrap()), Some(key));
+        }
+        assert_eq!(treap.select(inst, keys.len()), None);
+        assert_eq!(treap.rank(inst, &10_000), keys.len());
+    }
+
+    #[test]
+    fn successor_and_predecessor_walk_in_order() {
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        for key in [5, 1, 9, 3, 7] {
+            treap.insert(inst, key, ());
+        }
+
+        let mut forward = vec![];
+        let mut cur = treap.select(inst, 0);
+        while let Some(id) = cur {
+            forward.push(*treap.key(id).unwrap());
+            cur = treap.successor(id);
+        }
+        assert_eq!(forward, vec![1, 3, 5, 7, 9]);
+
+        let mut backward = vec![];
+        let mut cur = treap.select(inst, 4);
+        while let Some(id) = cur {
+            backward.push(*treap.key(id).unwrap());
+            cur = treap.predecessor(id);
+        }
+        assert_eq!(backward, vec![9, 7, 5, 3, 1]);
+    }
+
+    #[test]
+    fn split_and_merge_round_trip() {
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        for key in 0..100 {
+            treap.insert(inst, key, key * 2);
+        }
+
+        let (a, b) = (inst, treap.new_instance());
+        treap.split(a, &40, b);
+        assert_eq!(treap.len(a), 40);
+        assert_eq!(treap.len(b), 60);
+        assert!(treap.iter(a).all(|(k, _)| *k < 40));
+        assert!(treap.iter(b).all(|(k, _)| *k >= 40));
+
+        let merged = treap.merge(a, b);
+        assert!(treap.is_empty(b));
+        let entries: Vec<_> = treap.iter(merged).map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, (0..100).map(|k| (k, k * 2)).collect::<Vec<_>>());
+
+        // splitting again into the emptied instance allocates no new instances
+        for k in 0..100 {
+            treap.split(a, &k, b);
+            assert_eq!(treap.len(b), 100 - k);
+            treap.merge(a, b);
+        }
+        assert_eq!(treap.instances.len(), 2);
+        assert_eq!(treap.len(a), 100);
+    }
+
+    #[test]
+    fn key_ranges_match_treap_map() {
+        let mut rng = rand::thread_rng();
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        let mut expected = TreapMap::new();
+        for _ in 0..500 {
+            let key = rng.gen_range(0..1_000u32);
+            treap.insert(inst, key, key);
+            expected.insert(key, key);
+        }
+
+        let found: Vec<_> = treap
+            .query_range(inst, 200..300)
+            .into_iter()
+            .map(|id| *treap.key(id).unwrap())
+            .collect();
+        let expected_found: Vec<_> = (200..300)
+            .filter(|k| expected.search(k).is_some())
+            .collect();
+        assert_eq!(found, expected_found);
+
+        let removed: Vec<_> = treap
+            .remove_key_range(inst, 200..=300)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        let expected_removed: Vec<_> = (200..=300)
+            .filter(|k| expected.remove(k).is_some())
+            .collect();
+        assert_eq!(removed, expected_removed);
+        assert_eq!(treap.len(inst), expected.len());
+        for key in 0..1_000 {
+            assert_eq!(treap.get(inst, &key), expected.search(&key));
+        }
+    }
+
+    #[test]
+    fn freed_slots_are_reused_and_stale_handles_rejected() {
+        let mut treap = Treap::new();
+        let inst = treap.new_instance();
+        let (id, _) = treap.insert(inst, 1, "one");
+        treap.insert(inst, 2, "two");
+
+        assert_eq!(treap.remove_node(inst, id), Some((1, "one")));
+        assert_eq!(treap.key(id), None);
+        assert_eq!(treap.remove_node(inst, id), None);
+
+        let (reused, _) = treap.insert(inst, 3, "three");
+        assert_eq!(treap.capacity(), 2);
+        assert_ne!(reused, id);
+        assert_eq!(treap.key(id), None);
+        assert_eq!(treap.val(reused), Some(&"three"));
+    }
+
+    #[test]
+    fn remove_node_rejects_nodes_of_other_instances() {
+        let mut treap = Treap::new();
+        let a = treap.new_instance();
+        let b = treap.new_instance();
+        let mut ids = Vec::new();
+        for k in 0..50 {
+            ids.push(treap.insert(a, k, k).0);
+            treap.insert(b, k + 100, k);
+        }
+        for &id in &ids {
+            assert_eq!(treap.remove_node(b, id), None);
+        }
+        assert_eq!(treap.len(a), 50);
+        assert_eq!(treap.len(b), 50);
+        assert!(treap.iter(a).map(|(k, _)| *k).eq(0..50));
+        assert!(treap.iter(b).map(|(k, _)| *k).eq(100..150));
+        for (k, &id) in ids.iter().enumerate() {
+            assert_eq!(treap.key(id), Some(&(k as i32)));
+        }
+
+        assert_eq!(treap.remove_node(a, ids[10]), Some((10, 10)));
+        assert_eq!(treap.len(a), 49);
+        assert_eq!(treap.rank(a, &20), 19);
+    }
+}
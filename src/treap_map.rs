@@ -1,640 +1,33 @@
-use std::cmp::Ordering;
-use std::collections::Bound;
-use std::mem::swap;
-use std::ops::{Deref, DerefMut, RangeBounds};
+use std::fmt::Debug;
 
-#[derive(Copy, Clone)]
-pub enum Direction {
-    Left,
-    Right,
-}
-
-pub struct TreapNode<P> {
-    inner: Option<TreapNodeInner<P>>,
-}
-
-impl<P> Deref for TreapNode<P> {
-    type Target = Option<TreapNodeInner<P>>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}
-
-impl<P> DerefMut for TreapNode<P> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
-    }
-}
-
-pub struct TreapNodeInner<P> {
-    priority: u64,
-    payload: P,
-    left: Box<TreapNode<P>>,
-    right: Box<TreapNode<P>>,
-}
-
-impl<P: Payload> TreapNodeInner<P> {
-    fn new(payload: P) -> Self {
-        Self {
-            priority: rand::thread_rng().gen(),
-            payload,
-            left: Box::new(TreapNode::NONE),
-            right: Box::new(TreapNode::NONE),
-        }
-    }
-
-    fn update(&mut self) {
-        let left_data = self.left.as_ref().as_ref().map(|node| &node.payload);
-        let right_data = self.right.as_ref().as_ref().map(|node| &node.payload);
-        self.payload.update(left_data, right_data);
-    }
-
-    fn push_down(&mut self) {
-        self.left.push(&self.payload, Direction::Left);
-        self.right.push(&self.payload, Direction::Right);
-        self.payload.reset_delta();
-    }
-
-    fn push(&mut self, parent_payload: &P, _: Direction) {
-        self.payload.push(parent_payload);
-    }
-
-    fn push_delta<Delta>(&mut self, delta: Delta)
-    where
-        P: Pushable<Delta>,
-    {
-        self.payload.push(delta);
-    }
-
-    fn key(&self) -> &P::Key {
-        self.payload.key()
-    }
-
-    fn into_payload(self) -> P {
-        self.payload
-    }
-}
-
-impl<P: Payload> TreapNode<P> {
-    pub const NONE: Self = Self { inner: None };
-
-    pub fn new(payload: P) -> Self {
-        Self {
-            inner: Some(TreapNodeInner::new(payload)),
-        }
-    }
-
-    pub fn payload(&self) -> Option<&P> {
-        self.as_ref().map(|node| &node.payload)
-    }
-
-    pub fn into_payload(self) -> Option<P> {
-        self.inner.map(|node| node.into_payload())
-    }
-
-    pub fn replace<Data>(&mut self, data: Data) -> Data
-    where
-        P: Replaceable<Data>,
-    {
-        self.as_mut().unwrap().payload.replace(data)
-    }
-
-    pub fn key(&self) -> Option<&P::Key> {
-        self.as_ref().map(|node| node.key())
-    }
-
-    pub fn push(&mut self, parent_payload: &P, direction: Direction) {
-        if let Some(node) = self.as_mut() {
-            node.push(parent_payload, direction);
-        }
-    }
-
-    pub fn push_delta<Delta>(&mut self, delta: Delta)
-    where
-        P: Pushable<Delta>,
-    {
-        if let Some(node) = self.as_mut() {
-            node.push_delta(delta);
-        }
-    }
-
-    pub fn split(self, split_key: &P::Key, to_left: bool) -> (Self, Self) {
-        match self.inner {
-            Some(mut node) => {
-                node.push_down();
-                let ordering = node.key().cmp(split_key);
-                if ordering == Ordering::Less || to_left && ordering == Ordering::Equal {
-                    let (left, right) = node.right.split(split_key, to_left);
-                    node.right = Box::new(left);
-                    node.update();
-                    (Self { inner: Some(node) }, right)
-                } else {
-                    let (left, right) = node.left.split(split_key, to_left);
-                    node.left = Box::new(right);
-                    node.update();
-                    (left, Self { inner: Some(node) })
-                }
-            }
-            None => (Self::NONE, Self::NONE),
-        }
-    }
-
-    pub fn split_range(self, range: impl RangeBounds<P::Key>) -> (Self, Self, Self) {
-        let (left, right) = match range.start_bound() {
-            Bound::Included(left_bound) => {
-                let (left, right) = self.split(left_bound, false);
-                (left, right)
-            }
-            Bound::Excluded(left_bound) => {
-                let (left, right) = self.split(left_bound, true);
-                (left, right)
-            }
-            Bound::Unbounded => (Self::NONE, self),
-        };
-        let (middle, right) = match range.end_bound() {
-            Bound::Included(right_bound) => {
-                let (middle, right) = right.split(right_bound, true);
-                (middle, right)
-            }
-            Bound::Excluded(right_bound) => {
-                let (middle, right) = right.split(right_bound, false);
-                (middle, right)
-            }
-            Bound::Unbounded => (right, Self::NONE),
-        };
-        (left, middle, right)
-    }
-
-    pub fn range<'a, 's: 'a>(&'s self, r: impl RangeBounds<&'a P::Key>) -> Iter<'s, P> {
-        Iter::new(self, r)
-    }
-
-    pub fn leftmost(&self) -> Option<&Self> {
-        self.as_ref()
-            .map(|node| node.left.leftmost().unwrap_or(self))
-    }
-
-    pub fn rightmost(&self) -> Option<&Self> {
-        self.as_ref()
-            .map(|node| node.right.rightmost().unwrap_or(self))
-    }
-
-    pub fn lower(&self, key: &P::Key) -> Option<&Self> {
-        self.as_ref().and_then(|node| {
-            if node.key() < key {
-                node.right.lower(key).or(Some(self))
-            } else {
-                node.left.lower(key)
-            }
-        })
-    }
+#[path = "treap/map.rs"]
+mod treap;
+use treap::{Payload, TreapMap, TreapNodeInner, TreapSet, TreapValue};
+mod tree_render;
+use tree_render::{TreeShape, TreeView};
 
-    pub fn floor(&self, key: &P::Key) -> Option<&Self> {
-        self.as_ref().and_then(|node| {
-            if node.key() <= key {
-                node.right.floor(key).or(Some(self))
-            } else {
-                node.left.floor(key)
-            }
-        })
-    }
-
-    pub fn higher(&self, key: &P::Key) -> Option<&Self> {
-        self.as_ref().and_then(|node| {
-            if node.key() > key {
-                node.left.higher(key).or(Some(self))
-            } else {
-                node.right.higher(key)
-            }
-        })
-    }
-
-    pub fn ceil(&self, key: &P::Key) -> Option<&Self> {
-        self.as_ref().and_then(|node| {
-            if node.key() >= key {
-                node.left.ceil(key).or(Some(self))
-            } else {
-                node.right.ceil(key)
-            }
-        })
-    }
-
-    pub fn priority(&self) -> u64 {
-        self.as_ref().map(|node| node.priority).unwrap_or(0)
-    }
-
-    pub fn merge(left: Self, right: Self) -> Self {
-        if left.is_some() && right.is_some() {
-            assert!(
-                left.rightmost().unwrap().key().unwrap() < right.leftmost().unwrap().key().unwrap()
-            );
-        }
-        unsafe { Self::merge_unsafe(left, right) }
-    }
-
-    pub unsafe fn merge_unsafe(left: Self, right: Self) -> Self {
-        match left.inner {
-            None => right,
-            Some(mut left) => TreapNode {
-                inner: Some(match right.inner {
-                    None => left,
-                    Some(mut right) => {
-                        if left.priority > right.priority {
-                            left.push_down();
-                            left.right = Box::new(Self::merge_unsafe(
-                                *left.right,
-                                Self { inner: Some(right) },
-                            ));
-                            left.update();
-                            left
-                        } else {
-                            right.push_down();
-                            right.left = Box::new(Self::merge_unsafe(
-                                Self { inner: Some(left) },
-                                *right.left,
-                            ));
-                            right.update();
-                            right
-                        }
-                    }
-                }),
-            },
-        }
-    }
-
-    pub fn binary_search<'s, F>(&'s self, mut f: F)
-    where
-        F: FnMut(&'s P::Key, &'s P, Option<&'s P>, Option<&'s P>) -> Option<Direction>,
-    {
-        if let Some(node) = self.deref() {
-            let direction = f(
-                node.key(),
-                &node.payload,
-                node.left.payload(),
-                node.right.payload(),
-            );
-            if let Some(direction) = direction {
-                match direction {
-                    Direction::Left => {
-                        node.left.binary_search(f);
-                    }
-                    Direction::Right => {
-                        node.right.binary_search(f);
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn find(&self, key: &P::Key) -> &Self {
-        match self.deref() {
-            None => self,
-            Some(node) => {
-                let ordering = node.key().cmp(key);
-                match ordering {
-                    Ordering::Less => node.right.find(key),
-                    Ordering::Equal => self,
-                    Ordering::Greater => node.left.find(key),
-                }
-            }
-        }
-    }
-}
-
-impl<P: Payload> From<TreapNode<P>> for Option<P> {
-    fn from(data: TreapNode<P>) -> Self {
-        data.inner.map(|node| node.payload)
-    }
-}
-
-impl<P: Payload> Default for TreapNode<P> {
-    fn default() -> Self {
-        Self::NONE
-    }
-}
-
-pub trait Payload {
-    type Key: Ord;
-
-    fn key(&self) -> &Self::Key;
-    fn reset_delta(&mut self);
-    fn update(&mut self, left: Option<&Self>, right: Option<&Self>);
-    fn push_delta(&mut self, delta: &Self, direction: Direction);
-}
-
-pub trait Pushable<Delta>: Payload {
-    fn push(&mut self, delta: Delta);
-}
-
-impl<P: Payload> Pushable<&P> for P {
-    fn push(&mut self, delta: &P) {
-        self.push_delta(delta, Direction::Left);
-    }
-}
-
-pub trait Replaceable<Delta>: Payload {
-    fn replace(&mut self, delta: Delta) -> Delta;
-}
-
-pub struct SizePayload<InnerPayload: Payload> {
-    pub inner: InnerPayload,
-    pub size: u32,
-}
-
-impl<Data> From<Data> for SizePayload<PureDataPayload<Data>> {
-    fn from(data: Data) -> Self {
-        Self {
-            inner: data.into(),
-            size: 1,
-        }
-    }
-}
-
-impl<InnerPayload: Payload> Payload for SizePayload<InnerPayload> {
-    type Key = InnerPayload::Key;
-
-    fn key(&self) -> &Self::Key {
-        self.inner.key()
-    }
-
-    fn reset_delta(&mut self) {
-        self.inner.reset_delta();
-    }
-
-    fn update(&mut self, left: Option<&Self>, right: Option<&Self>) {
-        self.inner
-            .update(left.map(|node| &node.inner), right.map(|node| &node.inner));
-        self.size =
-            1 + left.map(|node| node.size).unwrap_or(0) + right.map(|node| node.size).unwrap_or(0);
-    }
-
-    fn push_delta(&mut self, delta: &Self, direction: Direction) {
-        self.inner.push_delta(&delta.inner, direction);
-    }
-}
-
-impl<Data, InnerPayload: Payload + Replaceable<Data>> Replaceable<Data>
-    for SizePayload<InnerPayload>
+impl<P: Payload> TreeView for TreapNodeInner<P>
+where
+    P::Key: Debug,
 {
-    fn replace(&mut self, delta: Data) -> Data {
-        self.inner.replace(delta)
+    fn label(&self) -> String {
+        format!("{:?}", self.key())
     }
-}
 
-pub struct KeyPayload<Key: Ord, InnerPayload: Payload> {
-    pub inner: InnerPayload,
-    pub key: Key,
-}
-
-impl<Key: Ord, InnerPayload: Payload, Data: Into<InnerPayload>> From<(Key, Data)>
-    for KeyPayload<Key, InnerPayload>
-{
-    fn from((key, data): (Key, Data)) -> Self {
-        Self {
-            inner: data.into(),
-            key,
-        }
-    }
-}
-
-impl<Key: Ord, InnerPayload: Payload> Payload for KeyPayload<Key, InnerPayload> {
-    type Key = Key;
-
-    fn key(&self) -> &Self::Key {
-        &self.key
-    }
-
-    fn reset_delta(&mut self) {
-        self.inner.reset_delta();
-    }
-
-    fn update(&mut self, left: Option<&Self>, right: Option<&Self>) {
-        self.inner
-            .update(left.map(|node| &node.inner), right.map(|node| &node.inner));
-    }
-
-    fn push_delta(&mut self, delta: &Self, direction: Direction) {
-        self.inner.push_delta(&delta.inner, direction);
-    }
-}
-
-impl<Data, Key: Ord, InnerPayload: Payload + Replaceable<Data>> Replaceable<Data>
-    for KeyPayload<Key, InnerPayload>
-{
-    fn replace(&mut self, delta: Data) -> Data {
-        self.inner.replace(delta)
-    }
-}
-
-pub struct PureDataPayload<Data> {
-    pub data: Data,
-}
-
-impl<Data> From<Data> for PureDataPayload<Data> {
-    fn from(data: Data) -> Self {
-        Self { data }
-    }
-}
-
-impl<Data> Payload for PureDataPayload<Data> {
-    type Key = usize;
-
-    fn key(&self) -> &Self::Key {
-        unreachable!()
-    }
-
-    fn reset_delta(&mut self) {}
-
-    fn update(&mut self, _: Option<&Self>, _: Option<&Self>) {}
-
-    fn push_delta(&mut self, _: &Self, _: Direction) {}
-}
-
-impl<Data> Replaceable<Data> for PureDataPayload<Data> {
-    fn replace(&mut self, mut data: Data) -> Data {
-        swap(&mut self.data, &mut data);
-        data
-    }
-}
-
-pub struct ImpliedKeyPayload<InnerPayload: Payload> {
-    pub inner: SizePayload<InnerPayload>,
-}
-
-impl<InnerPayload: Payload> From<InnerPayload> for ImpliedKeyPayload<InnerPayload> {
-    fn from(inner: InnerPayload) -> Self {
-        Self {
-            inner: SizePayload { inner, size: 1 },
-        }
-    }
-}
-
-impl<Data, InnerPayload: Payload + Replaceable<Data>> Replaceable<Data>
-    for ImpliedKeyPayload<InnerPayload>
-{
-    fn replace(&mut self, data: Data) -> Data {
-        self.inner.inner.replace(data)
-    }
-}
-
-impl<InnerPayload: Payload> Payload for ImpliedKeyPayload<InnerPayload> {
-    type Key = u32;
-
-    fn key(&self) -> &Self::Key {
-        &self.inner.size
-    }
-
-    fn reset_delta(&mut self) {
-        self.inner.reset_delta();
-    }
-
-    fn update(&mut self, left: Option<&Self>, right: Option<&Self>) {
-        self.inner
-            .update(left.map(|node| &node.inner), right.map(|node| &node.inner));
-    }
-
-    fn push_delta(&mut self, delta: &Self, direction: Direction) {
-        self.inner.push_delta(&delta.inner, direction);
-    }
-}
-
-pub struct Iter<'a, P> {
-    root: &'a TreapNode<P>,
-    from: Option<&'a TreapNode<P>>,
-    to: Option<&'a TreapNode<P>>,
-}
-
-impl<'a, P: Payload> Iter<'a, P> {
-    pub fn new<'b>(root: &'a TreapNode<P>, r: impl RangeBounds<&'b P::Key>) -> Self
-    where
-        'a: 'b,
-    {
-        Self {
-            root,
-            from: match r.start_bound() {
-                Bound::Included(key) => root.ceil(key),
-                Bound::Excluded(key) => root.higher(key),
-                Bound::Unbounded => root.leftmost(),
-            },
-            to: match r.end_bound() {
-                Bound::Included(key) => root.floor(key),
-                Bound::Excluded(key) => root.lower(key),
-                Bound::Unbounded => root.rightmost(),
-            },
-        }
-    }
-}
-
-impl<'a, P: Payload> Iterator for Iter<'a, P> {
-    type Item = &'a TreapNode<P>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.from?.key().unwrap() <= self.to?.key().unwrap() {
-            let res = self.from;
-            self.from = self.root.higher(self.from.unwrap().key().unwrap());
-            res
-        } else {
-            None
-        }
-    }
-}
-
-impl<'a, P: Payload> DoubleEndedIterator for Iter<'a, P> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.from?.key().unwrap() <= self.to?.key().unwrap() {
-            let res = self.to;
-            self.to = self.root.lower(self.to.unwrap().key().unwrap());
-            res
-        } else {
-            None
-        }
-    }
-}
-
-type TreapValue<T, V> = KeyPayload<T, SizePayload<PureDataPayload<V>>>;
-
-pub struct TreapMap<T: Ord, V> {
-    root: TreapNode<TreapValue<T, V>>,
-}
-
-impl<T: Ord, V> TreapMap<T, V> {
-    pub fn new() -> Self {
-        Self {
-            root: TreapNode::NONE,
-        }
-    }
-
-    pub fn insert(&mut self, key: T, value: V) -> Option<V> {
-        let mut root = TreapNode::NONE;
-        swap(&mut self.root, &mut root);
-        let (left, mut node, right) = root.split_range(&key..=&key);
-        let res = if node.is_some() {
-            Some(node.replace(value))
-        } else {
-            node = TreapNode::new((key, value).into());
-            None
-        };
-        unsafe {
-            self.root = TreapNode::merge_unsafe(left, TreapNode::merge_unsafe(node, right));
-        }
-        res
-    }
-
-    pub fn remove(&mut self, key: &T) -> Option<V> {
-        let mut root = TreapNode::NONE;
-        swap(&mut self.root, &mut root);
-        let (left, node, right) = root.split_range(key..=key);
-        unsafe {
-            self.root = TreapNode::merge_unsafe(left, right);
-        }
-        node.into_payload().map(|data| data.inner.inner.data)
-    }
-
-
-    pub fn search(&self, key: &T) -> Option<&V> {
-        self.root
-            .find(key)
-            .payload()
-            .map(|data| &data.inner.inner.data)
-    }
-
-}
-
-impl<T: Ord, V> Default for TreapMap<T, V> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[derive(Default)]
-pub struct TreapSet<T: Ord>(TreapMap<T, ()>);
-
-impl<T: Ord> TreapSet<T> {
-    pub fn new() -> Self {
-        Self(TreapMap::new())
-    }
-
-    pub fn insert(&mut self, key: T) -> bool {
-        self.0.insert(key, ()).is_some()
+    fn left_child(&self) -> Option<&Self> {
+        self.left()
     }
 
-    pub fn remove(&mut self, key: &T) -> bool {
-        self.0.remove(key).is_some()
+    fn right_child(&self) -> Option<&Self> {
+        self.right()
     }
 }
 
-impl<T: Ord> Deref for TreapSet<T> {
-    type Target = TreapMap<T, ()>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
+impl<T: Ord + Debug, V> TreeShape for TreapMap<T, V> {
+    type Node = TreapNodeInner<TreapValue<T, V>>;
 
-impl<T: Ord> DerefMut for TreapSet<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    fn root_node(&self) -> Option<&Self::Node> {
+        self.root()
     }
 }
 
@@ -693,6 +86,26 @@ fn main() {
     }
     let ascending_delete_duration = start_ascending_delete.elapsed();
 
+    let random_shape = random_tree.stats();
+    let ascending_shape = ascending_tree.stats();
+
+    // Bulk Operations
+    let start_bulk_insert = Instant::now();
+    let bulk_tree = TreapSet::from_sorted_iter(0..n);
+    let bulk_insert_duration = start_bulk_insert.elapsed();
+
+    let start_union = Instant::now();
+    let union_tree = bulk_tree.union(random_tree);
+    let union_duration = start_union.elapsed();
+
+    let start_intersection = Instant::now();
+    let intersection_tree = union_tree.intersection(ascending_tree);
+    let intersection_duration = start_intersection.elapsed();
+
+    let start_difference = Instant::now();
+    let difference_tree = intersection_tree.difference(TreapSet::from_sorted_iter(0..n / 2));
+    let difference_duration = start_difference.elapsed();
+
     // Results
     println!("Insertion (Random Order): {:?}", random_insert_duration);
     println!(
@@ -703,4 +116,81 @@ fn main() {
     println!("Search (Ascending Order): {:?}", ascending_search_duration);
     println!("Delete (Random Order): {:?}", random_delete_duration);
     println!("Delete (Ascending Order): {:?}", ascending_delete_duration);
-}
\ No newline at end of file
+    println!("Shape (Random Order):\n{}", random_shape);
+    println!("Shape (Ascending Order):\n{}", ascending_shape);
+    println!("Insertion (Bulk, Sorted): {:?}", bulk_insert_duration);
+    println!("Union: {:?}", union_duration);
+    println!("Intersection: {:?}", intersection_duration);
+    println!(
+        "Difference: {:?} ({} keys left)",
+        difference_duration,
+        difference_tree.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn keys<V>(map: &TreapMap<u32, V>) -> Vec<u32> {
+        map.keys().copied().collect()
+    }
+
+    fn random_set(rng: &mut impl Rng) -> BTreeSet<u32> {
+        let len = rng.gen_range(0..100);
+        (0..len).map(|_| rng.gen_range(0..200)).collect()
+    }
+
+    #[test]
+    fn set_algebra_matches_btree_set() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let a = random_set(&mut rng);
+            let b = random_set(&mut rng);
+            let build = |set: &BTreeSet<u32>| TreapSet::from_sorted_iter(set.iter().copied());
+
+            let treap = build(&a);
+            assert_eq!(keys(&treap), a.iter().copied().collect::<Vec<_>>());
+            assert_eq!(treap.len(), a.len());
+
+            let union = build(&a).union(build(&b));
+            assert_eq!(keys(&union), a.union(&b).copied().collect::<Vec<_>>());
+            assert_eq!(union.len(), a.union(&b).count());
+            let intersection = build(&a).intersection(build(&b));
+            assert_eq!(
+                keys(&intersection),
+                a.intersection(&b).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(intersection.len(), a.intersection(&b).count());
+            let difference = build(&a).difference(build(&b));
+            assert_eq!(
+                keys(&difference),
+                a.difference(&b).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(difference.len(), a.difference(&b).count());
+        }
+    }
+
+    #[test]
+    fn union_and_intersection_keep_values_from_self() {
+        let left: BTreeMap<u32, &str> = [(1, "l1"), (2, "l2"), (4, "l4")].into();
+        let right: BTreeMap<u32, &str> = [(2, "r2"), (3, "r3"), (4, "r4")].into();
+        let build = |map: &BTreeMap<u32, &'static str>| {
+            TreapMap::from_sorted_iter(map.iter().map(|(&key, &value)| (key, value)))
+        };
+        let union = build(&left).union(build(&right));
+        let values: Vec<_> = (1..=4).map(|key| union.search(&key).copied()).collect();
+        assert_eq!(values, [Some("l1"), Some("l2"), Some("r3"), Some("l4")]);
+        let intersection = build(&left).intersection(build(&right));
+        assert_eq!(keys(&intersection), [2, 4]);
+        assert_eq!(intersection.search(&2), Some(&"l2"));
+        assert_eq!(intersection.search(&4), Some(&"l4"));
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    fn from_sorted_iter_rejects_unsorted_input() {
+        TreapSet::from_sorted_iter([1, 3, 2]);
+    }
+}
@@ -0,0 +1,273 @@
+// Shape statistics and ASCII / Graphviz rendering shared by the search tree binaries.
+
+use std::cmp::max;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Read-only view of a binary tree node.
+pub trait TreeView {
+    #[allow(dead_code)]
+    fn label(&self) -> String;
+    fn left_child(&self) -> Option<&Self>;
+    fn right_child(&self) -> Option<&Self>;
+}
+
+/// A tree whose shape can be measured and drawn.
+pub trait TreeShape {
+    type Node: TreeView;
+
+    fn root_node(&self) -> Option<&Self::Node>;
+
+    /// Rotations performed since the tree was created, 0 for trees that never rotate.
+    fn rotations(&self) -> usize {
+        0
+    }
+
+    fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::collect(self.root_node());
+        stats.rotations = self.rotations();
+        stats
+    }
+
+    #[allow(dead_code)]
+    fn render_ascii(&self) -> String {
+        render_ascii(self.root_node())
+    }
+
+    #[allow(dead_code)]
+    fn render_dot(&self, name: &str) -> String {
+        render_dot(self.root_node(), name)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TreeStats {
+    pub node_count: usize,
+    /// number of levels, 0 for an empty tree
+    pub height: usize,
+    /// depth of the deepest node, the root has depth 0
+    pub max_depth: usize,
+    pub average_depth: f64,
+    pub rotations: usize,
+    /// number of nodes per balance factor (right subtree height minus left subtree height)
+    pub balance_histogram: BTreeMap<isize, usize>,
+}
+
+impl TreeStats {
+    pub fn collect<N: TreeView>(root: Option<&N>) -> Self {
+        let mut stats = TreeStats::default();
+        let mut depth_sum = 0;
+        // post-order walk: a node is expanded first and finished once both subtrees are done,
+        // `heights` holds the heights of the finished subtrees
+        let mut heights: Vec<usize> = Vec::new();
+        let mut stack: Vec<(&N, usize, bool)> = root.map(|node| (node, 0, false)).into_iter().collect();
+
+        while let Some((node, depth, expanded)) = stack.pop() {
+            if expanded {
+                let right_height = node.right_child().map_or(0, |_| heights.pop().unwrap());
+                let left_height = node.left_child().map_or(0, |_| heights.pop().unwrap());
+                let balance = right_height as isize - left_height as isize;
+                *stats.balance_histogram.entry(balance).or_insert(0) += 1;
+                heights.push(1 + max(left_height, right_height));
+            } else {
+                stats.node_count += 1;
+                stats.max_depth = max(stats.max_depth, depth);
+                depth_sum += depth;
+                stack.push((node, depth, true));
+                if let Some(right) = node.right_child() {
+                    stack.push((right, depth + 1, false));
+                }
+                if let Some(left) = node.left_child() {
+                    stack.push((left, depth + 1, false));
+                }
+            }
+        }
+
+        stats.height = heights.pop().unwrap_or(0);
+        if stats.node_count > 0 {
+            stats.average_depth = depth_sum as f64 / stats.node_count as f64;
+        }
+        stats
+    }
+}
+
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "nodes: {}, height: {}, max depth: {}, average depth: {:.2}, rotations: {}",
+            self.node_count, self.height, self.max_depth, self.average_depth, self.rotations
+        )?;
+        write!(f, "balance histogram:")?;
+        for (balance, count) in &self.balance_histogram {
+            write!(f, " {:+}: {}", balance, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Draws the tree sideways, one node per line, children tagged with L / R.
+#[allow(dead_code)]
+pub fn render_ascii<N: TreeView>(root: Option<&N>) -> String {
+    let mut out = String::new();
+    let root = match root {
+        Some(root) => root,
+        None => {
+            out.push_str("(empty)\n");
+            return out;
+        }
+    };
+
+    let mut stack = vec![(root, String::new(), String::new())];
+    while let Some((node, prefix, connector)) = stack.pop() {
+        out.push_str(&prefix);
+        out.push_str(&connector);
+        out.push_str(&node.label());
+        out.push('\n');
+
+        let child_prefix = match connector.chars().next() {
+            None => prefix,
+            Some('└') => prefix + "    ",
+            Some(_) => prefix + "│   ",
+        };
+        let children: Vec<(&N, &str)> = node
+            .left_child()
+            .map(|left| (left, "L: "))
+            .into_iter()
+            .chain(node.right_child().map(|right| (right, "R: ")))
+            .collect();
+        for (i, (child, side)) in children.iter().enumerate().rev() {
+            let branch = if i == children.len() - 1 { "└── " } else { "├── " };
+            stack.push((*child, child_prefix.clone(), format!("{}{}", branch, side)));
+        }
+    }
+    out
+}
+
+/// Emits the tree as a Graphviz `digraph`.
+#[allow(dead_code)]
+pub fn render_dot<N: TreeView>(root: Option<&N>, name: &str) -> String {
+    let mut out = format!("digraph {} {{\n    node [shape=circle];\n", name);
+    let mut next_id = 0;
+    let mut stack: Vec<(&N, usize)> = Vec::new();
+    if let Some(root) = root {
+        out.push_str(&format!("    n0 [label=\"{}\"];\n", escape_label(&root.label())));
+        stack.push((root, next_id));
+        next_id += 1;
+    }
+
+    while let Some((node, id)) = stack.pop() {
+        for (child, side) in [(node.left_child(), "L"), (node.right_child(), "R")] {
+            if let Some(child) = child {
+                out.push_str(&format!(
+                    "    n{} [label=\"{}\"];\n    n{} -> n{} [label=\"{}\"];\n",
+                    next_id,
+                    escape_label(&child.label()),
+                    id,
+                    next_id,
+                    side
+                ));
+                stack.push((child, next_id));
+                next_id += 1;
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[allow(dead_code)]
+fn escape_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed {
+        label: &'static str,
+        left: Option<Box<Fixed>>,
+        right: Option<Box<Fixed>>,
+    }
+
+    impl TreeView for Fixed {
+        fn label(&self) -> String {
+            self.label.to_string()
+        }
+
+        fn left_child(&self) -> Option<&Self> {
+            self.left.as_deref()
+        }
+
+        fn right_child(&self) -> Option<&Self> {
+            self.right.as_deref()
+        }
+    }
+
+    fn leaf(label: &'static str) -> Option<Box<Fixed>> {
+        Some(Box::new(Fixed { label, left: None, right: None }))
+    }
+
+    //     2
+    //    / \
+    //   1   4
+    //      / \
+    //     3   x"y
+    fn fixed_tree() -> Fixed {
+        Fixed {
+            label: "2",
+            left: leaf("1"),
+            right: Some(Box::new(Fixed { label: "4", left: leaf("3"), right: leaf("x\"y") })),
+        }
+    }
+
+    #[test]
+    fn stats_of_fixed_tree() {
+        let root = fixed_tree();
+        let stats = TreeStats::collect(Some(&root));
+        assert_eq!(stats.node_count, 5);
+        assert_eq!(stats.height, 3);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.average_depth, 1.2);
+        assert_eq!(stats.rotations, 0);
+        assert_eq!(stats.balance_histogram, BTreeMap::from([(0, 4), (1, 1)]));
+
+        assert_eq!(TreeStats::collect::<Fixed>(None), TreeStats::default());
+    }
+
+    #[test]
+    fn render_ascii_of_fixed_tree() {
+        let root = fixed_tree();
+        assert_eq!(
+            render_ascii(Some(&root)),
+            "2\n\
+             ├── L: 1\n\
+             └── R: 4\n\
+             \x20   ├── L: 3\n\
+             \x20   └── R: x\"y\n"
+        );
+        assert_eq!(render_ascii::<Fixed>(None), "(empty)\n");
+    }
+
+    #[test]
+    fn render_dot_of_fixed_tree() {
+        let root = fixed_tree();
+        assert_eq!(
+            render_dot(Some(&root), "t"),
+            "digraph t {\n\
+             \x20   node [shape=circle];\n\
+             \x20   n0 [label=\"2\"];\n\
+             \x20   n1 [label=\"1\"];\n\
+             \x20   n0 -> n1 [label=\"L\"];\n\
+             \x20   n2 [label=\"4\"];\n\
+             \x20   n0 -> n2 [label=\"R\"];\n\
+             \x20   n3 [label=\"3\"];\n\
+             \x20   n2 -> n3 [label=\"L\"];\n\
+             \x20   n4 [label=\"x\\\"y\"];\n\
+             \x20   n2 -> n4 [label=\"R\"];\n\
+             }\n"
+        );
+        assert_eq!(render_dot::<Fixed>(None, "t"), "digraph t {\n    node [shape=circle];\n}\n");
+    }
+}
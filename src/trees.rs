@@ -1,162 +1,778 @@
 use std::cmp::Ordering;
 use std::collections::Bound;
-use std::mem::swap;
+use std::env;
+use std::fmt::Debug;
+use std::mem::{replace, swap};
 use std::ops::{Deref, DerefMut, RangeBounds};
 
 extern crate rand; // 0.8.5
 use rand::Rng;
-pub struct EBSTNode {
-    key: u32,
-    left: Option<Box<EBSTNode>>,
-    right: Option<Box<EBSTNode>>,
-    depth: usize,
+
+mod tree_render;
+use tree_render::{TreeShape, TreeStats, TreeView};
+
+type EBSTLink<K, V> = Option<Box<EBSTNode<K, V>>>;
+type RBSTLink<K, V> = Option<Box<RBSTNode<K, V>>>;
+
+/// Read access to the nodes of a binary search tree, shared by the iterators and shape queries.
+pub trait BSTNode {
+    type Key: Ord;
+    type Value;
+
+    fn key(&self) -> &Self::Key;
+    fn value(&self) -> &Self::Value;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
 }
 
-impl EBSTNode {
-    pub fn new(key: u32, depth: usize) -> Self {
-        EBSTNode { key, left: None, right: None, depth }
+/// Number of nodes on the longest root-to-leaf path, 0 for an empty tree.
+pub fn bst_height<N: BSTNode>(root: Option<&N>) -> usize {
+    let mut height = 0;
+    let mut stack: Vec<(&N, usize)> = root.map(|node| (node, 1)).into_iter().collect();
+    while let Some((node, depth)) = stack.pop() {
+        height = height.max(depth);
+        stack.extend(node.left().map(|child| (child, depth + 1)));
+        stack.extend(node.right().map(|child| (child, depth + 1)));
     }
+    height
+}
 
-    pub fn insert(&mut self, key: u32, depth: usize) {
-        if key < self.key {
-            if let Some(ref mut left) = self.left {
-                left.insert(key, depth + 1);
-            } else {
-                self.left = Some(Box::new(EBSTNode::new(key, depth + 1)));
-            }
-        } else if key > self.key {
-            if let Some(ref mut right) = self.right {
-                right.insert(key, depth + 1);
+/// In-order iterator over the nodes of a binary search tree within a key range.
+pub struct BSTIter<'a, N: BSTNode> {
+    stack: Vec<&'a N>,
+    end: Bound<&'a N::Key>,
+}
+
+impl<'a, N: BSTNode> BSTIter<'a, N> {
+    pub fn new(root: Option<&'a N>, r: impl RangeBounds<&'a N::Key>) -> Self {
+        let mut stack = Vec::new();
+        let mut current = root;
+        while let Some(node) = current {
+            let after_start = match r.start_bound() {
+                Bound::Included(key) => node.key() >= *key,
+                Bound::Excluded(key) => node.key() > *key,
+                Bound::Unbounded => true,
+            };
+            if after_start {
+                stack.push(node);
+                current = node.left();
             } else {
-                self.right = Some(Box::new(EBSTNode::new(key, depth + 1)));
+                current = node.right();
             }
         }
+        Self {
+            stack,
+            end: r.end_bound().cloned(),
+        }
     }
+}
 
-    pub fn search(&self, key: u32) -> Option<usize> {
-        if key == self.key {
-            Some(self.depth)
-        } else if key < self.key {
-            self.left.as_ref().and_then(|left| left.search(key))
-        } else {
-            self.right.as_ref().and_then(|right| right.search(key))
+impl<'a, N: BSTNode> Iterator for BSTIter<'a, N> {
+    type Item = (&'a N::Key, &'a N::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let before_end = match self.end {
+            Bound::Included(key) => node.key() <= key,
+            Bound::Excluded(key) => node.key() < key,
+            Bound::Unbounded => true,
+        };
+        if !before_end {
+            self.stack.clear();
+            return None;
         }
+        let mut current = node.right();
+        while let Some(child) = current {
+            self.stack.push(child);
+            current = child.left();
+        }
+        Some((node.key(), node.value()))
+    }
+}
+
+pub struct EBSTNode<K, V> {
+    key: K,
+    value: V,
+    left: EBSTLink<K, V>,
+    right: EBSTLink<K, V>,
+}
+
+impl<K: Ord, V> EBSTNode<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        EBSTNode { key, value, left: None, right: None }
+    }
+}
+
+impl<K: Ord, V> BSTNode for EBSTNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/// Elementary (unbalanced) binary search tree.
+pub struct EBSTree<K: Ord, V> {
+    root: EBSTLink<K, V>,
+    len: usize,
+}
+
+impl<K: Ord, V> EBSTree<K, V> {
+    pub fn new() -> Self {
+        EBSTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        bst_height(self.root.as_deref())
     }
 
-    pub fn remove(&mut self, key: u32) -> Option<u32> {
-        if key < self.key {
-            if let Some(ref mut left) = self.left {
-                return left.remove(key);
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            match key.cmp(&node.key) {
+                Ordering::Less => link = &mut node.left,
+                Ordering::Greater => link = &mut node.right,
+                Ordering::Equal => return Some(replace(&mut node.value, value)),
             }
-        } else if key > self.key {
-            if let Some(ref mut right) = self.right {
-                return right.remove(key);
+        }
+        *link = Some(Box::new(EBSTNode::new(key, value)));
+        self.len += 1;
+        None
+    }
+
+    pub fn search(&self, key: &K) -> Option<&V> {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Less => current = node.left.as_deref(),
+                Ordering::Greater => current = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
             }
-        } else {
-            if self.left.is_none() {
-                return self.right.take().map(|right_node| {
-                    *self = *right_node;
-                    self.key
-                });
+        }
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let mut link = &mut self.root;
+        loop {
+            let ordering = key.cmp(&link.as_ref()?.key);
+            match ordering {
+                Ordering::Less => link = &mut link.as_mut().unwrap().left,
+                Ordering::Greater => link = &mut link.as_mut().unwrap().right,
+                Ordering::Equal => break,
             }
-            if self.right.is_none() {
-                return self.left.take().map(|left_node| {
-                    *self = *left_node;
-                    self.key
-                });
+        }
+
+        let mut node = link.take().unwrap();
+        *link = match (node.left.take(), node.right.take()) {
+            (None, right) => right,
+            (left, None) => left,
+            (left, right) => {
+                let mut right = right;
+                let mut min_larger_node = Self::take_min(&mut right);
+                min_larger_node.left = left;
+                min_larger_node.right = right;
+                Some(min_larger_node)
             }
+        };
+        self.len -= 1;
+        Some(node.value)
+    }
 
-            let min_larger_node = self.right.as_mut().unwrap().find_min();
-            self.key = min_larger_node.key;
-            return self.right.as_mut().unwrap().remove(self.key);
+    fn take_min(mut link: &mut EBSTLink<K, V>) -> Box<EBSTNode<K, V>> {
+        while link.as_ref().unwrap().left.is_some() {
+            link = &mut link.as_mut().unwrap().left;
         }
-        None
+        let mut node = link.take().unwrap();
+        *link = node.right.take();
+        node
     }
 
-    fn find_min(&mut self) -> &Self {
-        if let Some(ref mut left) = self.left {
-            left.find_min()
-        } else {
-            self
+    pub fn iter(&self) -> BSTIter<'_, EBSTNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), ..)
+    }
+
+    pub fn range<'a, 's: 'a>(&'s self, r: impl RangeBounds<&'a K>) -> BSTIter<'a, EBSTNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), r)
+    }
+}
+
+impl<K: Ord + Debug, V> TreeView for EBSTNode<K, V> {
+    fn label(&self) -> String {
+        format!("{:?}", self.key)
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K: Ord + Debug, V> TreeShape for EBSTree<K, V> {
+    type Node = EBSTNode<K, V>;
+
+    fn root_node(&self) -> Option<&Self::Node> {
+        self.root.as_deref()
+    }
+}
+
+impl<K: Ord, V> Default for EBSTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sorted inserts leave an unbalanced tree as one long path, so the nodes are freed
+/// iteratively rather than by the recursive default drop.
+impl<K: Ord, V> Drop for EBSTree<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<EBSTNode<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
         }
     }
 }
 
-pub struct RBSTNode {
-    key: u32,
-    left: Option<Box<RBSTNode>>,
-    right: Option<Box<RBSTNode>>,
+pub struct RBSTNode<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    left: RBSTLink<K, V>,
+    right: RBSTLink<K, V>,
 }
 
-impl RBSTNode {
-    pub fn new(key: u32) -> Self {
-        RBSTNode { key, left: None, right: None }
+impl<K: Ord, V> RBSTNode<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        RBSTNode { key, value, size: 1, left: None, right: None }
     }
 
-    pub fn insert(&mut self, key: u32) {
-        let mut rng = rand::thread_rng();
-        if rng.gen_bool(0.5) {
-            if let Some(ref mut left) = self.left {
-                left.insert(key);
-            } else {
-                self.left = Some(Box::new(RBSTNode::new(key)));
+    fn update(&mut self) {
+        self.size = 1 + RBSTree::size(&self.left) + RBSTree::size(&self.right);
+    }
+}
+
+impl<K: Ord, V> BSTNode for RBSTNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/// Randomized binary search tree: a new key becomes the root of a subtree of size n with
+/// probability 1/(n+1), which keeps the shape random whatever the insertion order.
+pub struct RBSTree<K: Ord, V> {
+    root: RBSTLink<K, V>,
+}
+
+impl<K: Ord, V> RBSTree<K, V> {
+    pub fn new() -> Self {
+        RBSTree { root: None }
+    }
+
+    fn size(link: &RBSTLink<K, V>) -> usize {
+        link.as_ref().map_or(0, |node| node.size)
+    }
+
+    pub fn len(&self) -> usize {
+        Self::size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> usize {
+        bst_height(self.root.as_deref())
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.search_mut(&key) {
+            return Some(replace(old, value));
+        }
+        self.root = Some(Self::insert_node(self.root.take(), key, value));
+        None
+    }
+
+    fn insert_node(link: RBSTLink<K, V>, key: K, value: V) -> Box<RBSTNode<K, V>> {
+        let size = Self::size(&link);
+        match link {
+            Some(mut node) if rand::thread_rng().gen_range(0..=size) != 0 => {
+                if key < node.key {
+                    node.left = Some(Self::insert_node(node.left.take(), key, value));
+                } else {
+                    node.right = Some(Self::insert_node(node.right.take(), key, value));
+                }
+                node.update();
+                node
             }
-        } else {
-            if let Some(ref mut right) = self.right {
-                right.insert(key);
-            } else {
-                self.right = Some(Box::new(RBSTNode::new(key)));
+            link => {
+                let (left, right) = Self::split(link, &key);
+                let mut node = Box::new(RBSTNode::new(key, value));
+                node.left = left;
+                node.right = right;
+                node.update();
+                node
             }
         }
     }
 
-    pub fn search(&self, key: u32) -> bool {
-        if key == self.key {
-            true
-        } else if key < self.key {
-            self.left.as_ref().map_or(false, |left| left.search(key))
-        } else {
-            self.right.as_ref().map_or(false, |right| right.search(key))
+    /// splits a subtree into keys less than and greater than `key`, which must not be present
+    fn split(link: RBSTLink<K, V>, key: &K) -> (RBSTLink<K, V>, RBSTLink<K, V>) {
+        match link {
+            None => (None, None),
+            Some(mut node) => {
+                if node.key < *key {
+                    let (left, right) = Self::split(node.right.take(), key);
+                    node.right = left;
+                    node.update();
+                    (Some(node), right)
+                } else {
+                    let (left, right) = Self::split(node.left.take(), key);
+                    node.left = right;
+                    node.update();
+                    (left, Some(node))
+                }
+            }
         }
     }
 
-    pub fn remove(&mut self, key: u32) -> Option<u32> {
-        if key < self.key {
-            if let Some(ref mut left) = self.left {
-                return left.remove(key);
+    /// joins two subtrees, every key of `left` must be less than every key of `right`
+    fn join(left: RBSTLink<K, V>, right: RBSTLink<K, V>) -> RBSTLink<K, V> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(mut left), Some(mut right)) => {
+                if rand::thread_rng().gen_range(0..left.size + right.size) < left.size {
+                    left.right = Self::join(left.right.take(), Some(right));
+                    left.update();
+                    Some(left)
+                } else {
+                    right.left = Self::join(Some(left), right.left.take());
+                    right.update();
+                    Some(right)
+                }
             }
-        } else if key > self.key {
-            if let Some(ref mut right) = self.right {
-                return right.remove(key);
+        }
+    }
+
+    pub fn search(&self, key: &K) -> Option<&V> {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Less => current = node.left.as_deref(),
+                Ordering::Greater => current = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
             }
-        } else {
-            if self.left.is_none() {
-                return self.right.take().map(|right_node| {
-                    *self = *right_node;
-                    self.key
-                });
+        }
+        None
+    }
+
+    fn search_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut current = self.root.as_deref_mut();
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Less => current = node.left.as_deref_mut(),
+                Ordering::Greater => current = node.right.as_deref_mut(),
+                Ordering::Equal => return Some(&mut node.value),
             }
-            if self.right.is_none() {
-                return self.left.take().map(|left_node| {
-                    *self = *left_node;
-                    self.key
-                });
+        }
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        Self::remove_node(&mut self.root, key)
+    }
+
+    fn remove_node(link: &mut RBSTLink<K, V>, key: &K) -> Option<V> {
+        let ordering = key.cmp(&link.as_ref()?.key);
+        let node = link.as_mut().unwrap();
+        let removed = match ordering {
+            Ordering::Less => Self::remove_node(&mut node.left, key),
+            Ordering::Greater => Self::remove_node(&mut node.right, key),
+            Ordering::Equal => {
+                let mut node = link.take().unwrap();
+                *link = Self::join(node.left.take(), node.right.take());
+                return Some(node.value);
             }
+        };
+        if removed.is_some() {
+            node.size -= 1;
+        }
+        removed
+    }
+
+    pub fn iter(&self) -> BSTIter<'_, RBSTNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), ..)
+    }
+
+    pub fn range<'a, 's: 'a>(&'s self, r: impl RangeBounds<&'a K>) -> BSTIter<'a, RBSTNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), r)
+    }
+}
+
+impl<K: Ord + Debug, V> TreeView for RBSTNode<K, V> {
+    fn label(&self) -> String {
+        format!("{:?}", self.key)
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K: Ord + Debug, V> TreeShape for RBSTree<K, V> {
+    type Node = RBSTNode<K, V>;
+
+    fn root_node(&self) -> Option<&Self::Node> {
+        self.root.as_deref()
+    }
+}
+
+impl<K: Ord, V> Default for RBSTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type SplayLink<K, V> = Option<Box<SplayNode<K, V>>>;
+
+pub struct SplayNode<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    left: SplayLink<K, V>,
+    right: SplayLink<K, V>,
+}
+
+impl<K: Ord, V> SplayNode<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        SplayNode { key, value, size: 1, left: None, right: None }
+    }
 
-            let min_larger_node = self.right.as_mut().unwrap().find_min();
-            self.key = min_larger_node.key;
-            return self.right.as_mut().unwrap().remove(self.key);
+    fn update(&mut self) {
+        self.size = 1 + SplayTreeMap::size(&self.left) + SplayTreeMap::size(&self.right);
+    }
+}
+
+impl<K: Ord, V> BSTNode for SplayNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/// Self-adjusting binary search tree: every access splays the touched key to the root
+/// (top-down), so recently and frequently used keys stay close to the root.
+pub struct SplayTreeMap<K: Ord, V> {
+    root: SplayLink<K, V>,
+    rotations: usize,
+}
+
+impl<K: Ord, V> SplayTreeMap<K, V> {
+    pub fn new() -> Self {
+        SplayTreeMap { root: None, rotations: 0 }
+    }
+
+    fn size(link: &SplayLink<K, V>) -> usize {
+        link.as_ref().map_or(0, |node| node.size)
+    }
+
+    pub fn len(&self) -> usize {
+        Self::size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> usize {
+        bst_height(self.root.as_deref())
+    }
+
+    /// Top-down splay: brings the node `target` points at (or the last node on its search path)
+    /// to the root. `target` compares the wanted key with a node key.
+    fn splay<F>(mut root: Box<SplayNode<K, V>>, target: F, rotations: &mut usize) -> Box<SplayNode<K, V>>
+    where
+        F: Fn(&K) -> Ordering,
+    {
+        // nodes smaller than the target, each waiting for its right child
+        let mut left_nodes = Vec::new();
+        // nodes greater than the target, each waiting for its left child
+        let mut right_nodes = Vec::new();
+
+        loop {
+            match target(&root.key) {
+                Ordering::Less => {
+                    let mut left = match root.left.take() {
+                        Some(left) => left,
+                        None => break,
+                    };
+                    if target(&left.key) == Ordering::Less {
+                        // zig-zig: rotate right before linking
+                        root.left = left.right.take();
+                        root.update();
+                        left.right = Some(root);
+                        *rotations += 1;
+                        root = left;
+                        left = match root.left.take() {
+                            Some(left) => left,
+                            None => break,
+                        };
+                    }
+                    right_nodes.push(root);
+                    root = left;
+                }
+                Ordering::Greater => {
+                    let mut right = match root.right.take() {
+                        Some(right) => right,
+                        None => break,
+                    };
+                    if target(&right.key) == Ordering::Greater {
+                        // zag-zag: rotate left before linking
+                        root.right = right.left.take();
+                        root.update();
+                        right.left = Some(root);
+                        *rotations += 1;
+                        root = right;
+                        right = match root.right.take() {
+                            Some(right) => right,
+                            None => break,
+                        };
+                    }
+                    left_nodes.push(root);
+                    root = right;
+                }
+                Ordering::Equal => break,
+            }
+        }
+
+        let mut left_tree = root.left.take();
+        for mut node in left_nodes.into_iter().rev() {
+            node.right = left_tree;
+            node.update();
+            left_tree = Some(node);
+        }
+        let mut right_tree = root.right.take();
+        for mut node in right_nodes.into_iter().rev() {
+            node.left = right_tree;
+            node.update();
+            right_tree = Some(node);
         }
+        root.left = left_tree;
+        root.right = right_tree;
+        root.update();
+        root
+    }
+
+    fn splay_key(&mut self, key: &K) {
+        if let Some(root) = self.root.take() {
+            self.root = Some(Self::splay(root, |node_key| key.cmp(node_key), &mut self.rotations));
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.splay_key(&key);
+        let mut node = Box::new(SplayNode::new(key, value));
+        match self.root.take() {
+            None => {}
+            Some(mut root) => match node.key.cmp(&root.key) {
+                Ordering::Equal => {
+                    let old = replace(&mut root.value, node.value);
+                    self.root = Some(root);
+                    return Some(old);
+                }
+                Ordering::Less => {
+                    node.left = root.left.take();
+                    root.update();
+                    node.right = Some(root);
+                }
+                Ordering::Greater => {
+                    node.right = root.right.take();
+                    root.update();
+                    node.left = Some(root);
+                }
+            },
+        }
+        node.update();
+        self.root = Some(node);
         None
     }
 
-    fn find_min(&mut self) -> &Self {
-        if let Some(ref mut left) = self.left {
-            left.find_min()
-        } else {
-            self
+    /// Looks the key up and splays it (or its last visited neighbour) to the root.
+    pub fn search(&mut self, key: &K) -> Option<&V> {
+        self.splay_key(key);
+        self.root
+            .as_ref()
+            .filter(|root| root.key == *key)
+            .map(|root| &root.value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.splay_key(key);
+        match self.root.take() {
+            Some(mut root) if root.key == *key => {
+                self.root = Self::join_links(root.left.take(), root.right.take(), &mut self.rotations);
+                Some(root.value)
+            }
+            root => {
+                self.root = root;
+                None
+            }
+        }
+    }
+
+    /// Moves every key greater than or equal to `key` into the returned tree.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        self.splay_key(key);
+        let mut other = SplayTreeMap::new();
+        if let Some(mut root) = self.root.take() {
+            if root.key < *key {
+                other.root = root.right.take();
+                root.update();
+                self.root = Some(root);
+            } else {
+                self.root = root.left.take();
+                root.update();
+                other.root = Some(root);
+            }
+        }
+        other
+    }
+
+    /// Joins two trees, every key of `left` must be less than every key of `right`.
+    pub fn join(mut left: Self, mut right: Self) -> Self {
+        let mut rotations = left.rotations + right.rotations;
+        let root = Self::join_links(left.root.take(), right.root.take(), &mut rotations);
+        SplayTreeMap { root, rotations }
+    }
+
+    fn join_links(left: SplayLink<K, V>, right: SplayLink<K, V>, rotations: &mut usize) -> SplayLink<K, V> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(left), Some(right)) => {
+                // splaying the maximum leaves the root without a right subtree
+                let mut root = Self::splay(left, |_| Ordering::Greater, rotations);
+                assert!(root.key < right.key);
+                root.right = Some(right);
+                root.update();
+                Some(root)
+            }
         }
     }
+
+    pub fn iter(&self) -> BSTIter<'_, SplayNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), ..)
+    }
+
+    pub fn range<'a, 's: 'a>(&'s self, r: impl RangeBounds<&'a K>) -> BSTIter<'a, SplayNode<K, V>> {
+        BSTIter::new(self.root.as_deref(), r)
+    }
+
+    pub fn sort(&self) -> Vec<&K> {
+        self.iter().map(|(key, _)| key).collect()
+    }
+}
+
+impl<K: Ord, V> Default for SplayTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sequential access leaves a splay tree as one long path, so the nodes are freed iteratively
+/// rather than by the recursive default drop.
+impl<K: Ord, V> Drop for SplayTreeMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<SplayNode<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<K: Ord + Debug, V> TreeView for SplayNode<K, V> {
+    fn label(&self) -> String {
+        format!("{:?}", self.key)
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K: Ord + Debug, V> TreeShape for SplayTreeMap<K, V> {
+    type Node = SplayNode<K, V>;
+
+    fn root_node(&self) -> Option<&Self::Node> {
+        self.root.as_deref()
+    }
+
+    fn rotations(&self) -> usize {
+        self.rotations
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -757,6 +1373,31 @@ impl<T: Ord, V> TreapMap<T, V> {
 
 }
 
+impl<P: Payload> TreeView for TreapNodeInner<P>
+where
+    P::Key: Debug,
+{
+    fn label(&self) -> String {
+        format!("{:?}", self.key())
+    }
+
+    fn left_child(&self) -> Option<&Self> {
+        self.left.as_ref().as_ref()
+    }
+
+    fn right_child(&self) -> Option<&Self> {
+        self.right.as_ref().as_ref()
+    }
+}
+
+impl<T: Ord + Debug, V> TreeShape for TreapMap<T, V> {
+    type Node = TreapNodeInner<TreapValue<T, V>>;
+
+    fn root_node(&self) -> Option<&Self::Node> {
+        self.root.as_ref()
+    }
+}
+
 impl<T: Ord, V> Default for TreapMap<T, V> {
     fn default() -> Self {
         Self::new()
@@ -812,43 +1453,21 @@ impl<T: Ord> TreapSet<T> {
 
 
 
-impl EBSTNode {
-    pub fn sort(&self) -> Vec<u32> {
-        let mut sorted = Vec::new();
-        self.in_order_traversal(&mut sorted);
-        sorted
-    }
-
-    fn in_order_traversal(&self, sorted: &mut Vec<u32>) {
-        if let Some(ref left) = self.left {
-            left.in_order_traversal(sorted);
-        }
-        sorted.push(self.key);
-        if let Some(ref right) = self.right {
-            right.in_order_traversal(sorted);
-        }
+impl<K: Ord, V> EBSTree<K, V> {
+    pub fn sort(&self) -> Vec<&K> {
+        self.iter().map(|(key, _)| key).collect()
     }
 }
 
-impl RBSTNode {
-    pub fn sort(&self) -> Vec<u32> {
-        let mut sorted = Vec::new();
-        self.in_order_traversal(&mut sorted);
-        sorted
-    }
-
-    fn in_order_traversal(&self, sorted: &mut Vec<u32>) {
-        if let Some(ref left) = self.left {
-            left.in_order_traversal(sorted);
-        }
-        sorted.push(self.key);
-        if let Some(ref right) = self.right {
-            right.in_order_traversal(sorted);
-        }
+impl<K: Ord, V> RBSTree<K, V> {
+    pub fn sort(&self) -> Vec<&K> {
+        self.iter().map(|(key, _)| key).collect()
     }
 }
 
 
+use rand::seq::SliceRandom;
+use std::hint::black_box;
 use std::time::{Instant, Duration};
 
 fn main() {
@@ -857,8 +1476,9 @@ fn main() {
 
     // Random Order Trees
     let mut treap = TreapSet::new();
-    let mut ebst = EBSTNode::new(rng.gen_range(0..n), 0);
-    let mut rbst = RBSTNode::new(rng.gen_range(0..n));
+    let mut ebst = EBSTree::new();
+    let mut rbst = RBSTree::new();
+    let mut splay = SplayTreeMap::new();
 
     let start_treap_insert = Instant::now();
     for _ in 0..n {
@@ -870,21 +1490,29 @@ fn main() {
     let start_ebst_insert = Instant::now();
     for _ in 0..n {
         let value = rng.gen_range(0..n);
-        ebst.insert(value, 0);
+        ebst.insert(value, ());
     }
     let ebst_insert_duration = start_ebst_insert.elapsed();
 
     let start_rbst_insert = Instant::now();
     for _ in 0..n {
         let value = rng.gen_range(0..n);
-        rbst.insert(value);
+        rbst.insert(value, ());
     }
     let rbst_insert_duration = start_rbst_insert.elapsed();
 
+    let start_splay_insert = Instant::now();
+    for _ in 0..n {
+        let value = rng.gen_range(0..n);
+        splay.insert(value, ());
+    }
+    let splay_insert_duration = start_splay_insert.elapsed();
+
     // Ascending Order Trees
     let mut ascending_treap = TreapSet::new();
-    let mut ascending_ebst = EBSTNode::new(0, 0);
-    let mut ascending_rbst = RBSTNode::new(0);
+    let mut ascending_ebst = EBSTree::new();
+    let mut ascending_rbst = RBSTree::new();
+    let mut ascending_splay = SplayTreeMap::new();
 
     let start_ascending_treap_insert = Instant::now();
     for value in 0..n {
@@ -894,16 +1522,22 @@ fn main() {
 
     let start_ascending_ebst_insert = Instant::now();
     for value in 0..n {
-        ascending_ebst.insert(value, 0);
+        ascending_ebst.insert(value, ());
     }
     let ascending_ebst_insert_duration = start_ascending_ebst_insert.elapsed();
 
     let start_ascending_rbst_insert = Instant::now();
     for value in 0..n {
-        ascending_rbst.insert(value);
+        ascending_rbst.insert(value, ());
     }
     let ascending_rbst_insert_duration = start_ascending_rbst_insert.elapsed();
 
+    let start_ascending_splay_insert = Instant::now();
+    for value in 0..n {
+        ascending_splay.insert(value, ());
+    }
+    let ascending_splay_insert_duration = start_ascending_splay_insert.elapsed();
+
     // Search Operation
     let start_treap_search = Instant::now();
     for _ in 0..n / 10 {
@@ -915,17 +1549,24 @@ fn main() {
     let start_ebst_search = Instant::now();
     for _ in 0..n / 10 {
         let value = rng.gen_range(0..n);
-        ebst.search(value);
+        ebst.search(&value);
     }
     let ebst_search_duration = start_ebst_search.elapsed();
 
     let start_rbst_search = Instant::now();
     for _ in 0..n / 10 {
         let value = rng.gen_range(0..n);
-        rbst.search(value);
+        rbst.search(&value);
     }
     let rbst_search_duration = start_rbst_search.elapsed();
 
+    let start_splay_search = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        splay.search(&value);
+    }
+    let splay_search_duration = start_splay_search.elapsed();
+
     // Delete Operation
     let start_treap_delete = Instant::now();
     for _ in 0..n / 10 {
@@ -937,17 +1578,24 @@ fn main() {
     let start_ebst_delete = Instant::now();
     for _ in 0..n / 10 {
         let value = rng.gen_range(0..n);
-        ebst.remove(value);
+        ebst.remove(&value);
     }
     let ebst_delete_duration = start_ebst_delete.elapsed();
 
     let start_rbst_delete = Instant::now();
     for _ in 0..n / 10 {
         let value = rng.gen_range(0..n);
-        rbst.remove(value);
+        rbst.remove(&value);
     }
     let rbst_delete_duration = start_rbst_delete.elapsed();
 
+    let start_splay_delete = Instant::now();
+    for _ in 0..n / 10 {
+        let value = rng.gen_range(0..n);
+        splay.remove(&value);
+    }
+    let splay_delete_duration = start_splay_delete.elapsed();
+
     // Sorting Operation
     let start_treap_sort = Instant::now();
     let _ = treap.sort();
@@ -961,23 +1609,343 @@ fn main() {
     let _ = rbst.sort();
     let rbst_sort_duration = start_rbst_sort.elapsed();
 
+    let start_splay_sort = Instant::now();
+    let _ = splay.sort();
+    let splay_sort_duration = start_splay_sort.elapsed();
+
     // Print Results
-    print_comparison_table(
-        treap_insert_duration, ascending_treap_insert_duration, treap_search_duration, treap_delete_duration, treap_sort_duration,
-        ebst_insert_duration, ascending_ebst_insert_duration, ebst_search_duration, ebst_delete_duration, ebst_sort_duration,
-        rbst_insert_duration, ascending_rbst_insert_duration, rbst_search_duration, rbst_delete_duration, rbst_sort_duration
-    );
-}
-
-fn print_comparison_table(
-    treap_insert: Duration, treap_asc_insert: Duration, treap_search: Duration, treap_delete: Duration, treap_sort: Duration,
-    ebst_insert: Duration, ebst_asc_insert: Duration, ebst_search: Duration, ebst_delete: Duration, ebst_sort: Duration,
-    rbst_insert: Duration, rbst_asc_insert: Duration, rbst_search: Duration, rbst_delete: Duration, rbst_sort: Duration
-) {
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Operation", "Treap", "EBST", "RBST");
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Random Insert", format!("{:?}", treap_insert), format!("{:?}", ebst_insert), format!("{:?}", rbst_insert));
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Ascending Insert", format!("{:?}", treap_asc_insert), format!("{:?}", ebst_asc_insert), format!("{:?}", rbst_asc_insert));
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Search", format!("{:?}", treap_search), format!("{:?}", ebst_search), format!("{:?}", rbst_search));
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Delete", format!("{:?}", treap_delete), format!("{:?}", ebst_delete), format!("{:?}", rbst_delete));
-    println!("{:<30} | {:<15} | {:<15} | {:<15}", "Sort", format!("{:?}", treap_sort), format!("{:?}", ebst_sort), format!("{:?}", rbst_sort));
+    print_comparison_table("Operation", &[
+        ("Random Insert", [treap_insert_duration, ebst_insert_duration, rbst_insert_duration, splay_insert_duration]),
+        ("Ascending Insert", [ascending_treap_insert_duration, ascending_ebst_insert_duration, ascending_rbst_insert_duration, ascending_splay_insert_duration]),
+        ("Search", [treap_search_duration, ebst_search_duration, rbst_search_duration, splay_search_duration]),
+        ("Delete", [treap_delete_duration, ebst_delete_duration, rbst_delete_duration, splay_delete_duration]),
+        ("Sort", [treap_sort_duration, ebst_sort_duration, rbst_sort_duration, splay_sort_duration]),
+    ]);
+
+    // Access Patterns: the same shuffled keys in every tree, then n * 10 lookups per workload
+    let mut keys: Vec<u32> = (0..n).collect();
+    keys.shuffle(&mut rng);
+    let mut access_treap = TreapSet::new();
+    let mut access_ebst = EBSTree::new();
+    let mut access_rbst = RBSTree::new();
+    let mut access_splay = SplayTreeMap::new();
+    for &key in &keys {
+        access_treap.insert(key);
+        access_ebst.insert(key, ());
+        access_rbst.insert(key, ());
+        access_splay.insert(key, ());
+    }
+
+    let lookups = n as usize * 10;
+    let workloads = [
+        ("Zipf Lookup (s = 1.0)", zipf_workload(&keys, lookups, 1.0, &mut rng)),
+        ("Zipf Lookup (s = 1.5)", zipf_workload(&keys, lookups, 1.5, &mut rng)),
+        ("Sequential Access", sequential_workload(n, lookups)),
+        ("Working Set (64 keys)", working_set_workload(&keys, lookups, 64, 1_000, &mut rng)),
+        ("Repeated Key (x100)", repeated_key_workload(&keys, lookups, 100, &mut rng)),
+    ];
+    let mut access_rows = Vec::new();
+    for (name, workload) in &workloads {
+        access_rows.push((*name, [
+            time_lookups(workload, |key| access_treap.search(key).is_some()),
+            time_lookups(workload, |key| access_ebst.search(key).is_some()),
+            time_lookups(workload, |key| access_rbst.search(key).is_some()),
+            time_lookups(workload, |key| access_splay.search(key).is_some()),
+        ]));
+    }
+    println!();
+    print_comparison_table("Access Pattern", &access_rows);
+
+    // Tree Shapes
+    print_shape_table(&[
+        ("Treap", treap.stats()),
+        ("EBST", ebst.stats()),
+        ("RBST", rbst.stats()),
+        ("Splay", splay.stats()),
+        ("Treap (Ascending)", ascending_treap.stats()),
+        ("EBST (Ascending)", ascending_ebst.stats()),
+        ("RBST (Ascending)", ascending_rbst.stats()),
+        ("Splay (Ascending)", ascending_splay.stats()),
+        ("Splay (After Access Patterns)", access_splay.stats()),
+    ]);
+
+    // Small trees are drawn as ASCII art, or as Graphviz DOT when run with `dot`
+    let as_dot = env::args().any(|arg| arg == "dot");
+    let mut small_treap = TreapSet::new();
+    let mut small_ebst = EBSTree::new();
+    let mut small_rbst = RBSTree::new();
+    let mut small_splay = SplayTreeMap::new();
+    for _ in 0..15 {
+        let value = rng.gen_range(0..100);
+        small_treap.insert(value);
+        small_ebst.insert(value, ());
+        small_rbst.insert(value, ());
+        small_splay.insert(value, ());
+    }
+    if as_dot {
+        println!("{}", small_treap.render_dot("treap"));
+        println!("{}", small_ebst.render_dot("ebst"));
+        println!("{}", small_rbst.render_dot("rbst"));
+        println!("{}", small_splay.render_dot("splay"));
+    } else {
+        println!("\nTreap:\n{}", small_treap.render_ascii());
+        println!("EBST:\n{}", small_ebst.render_ascii());
+        println!("RBST:\n{}", small_rbst.render_ascii());
+        println!("Splay:\n{}", small_splay.render_ascii());
+    }
+}
+
+fn time_lookups<F: FnMut(&u32) -> bool>(workload: &[u32], mut lookup: F) -> Duration {
+    let start = Instant::now();
+    for key in workload {
+        black_box(lookup(key));
+    }
+    start.elapsed()
+}
+
+// The i-th most popular key (in shuffled order) is drawn with probability proportional to 1 / i^s
+fn zipf_workload(keys: &[u32], count: usize, s: f64, rng: &mut impl Rng) -> Vec<u32> {
+    let mut cumulative = Vec::with_capacity(keys.len());
+    let mut total = 0.0;
+    for rank in 1..=keys.len() {
+        total += 1.0 / (rank as f64).powf(s);
+        cumulative.push(total);
+    }
+    (0..count)
+        .map(|_| {
+            let r = rng.gen::<f64>() * total;
+            let rank = cumulative.partition_point(|&c| c < r).min(keys.len() - 1);
+            keys[rank]
+        })
+        .collect()
+}
+
+fn sequential_workload(n: u32, count: usize) -> Vec<u32> {
+    (0..n).cycle().take(count).collect()
+}
+
+// Lookups stay within a small random set of keys that is replaced every `phase` accesses
+fn working_set_workload(keys: &[u32], count: usize, set_size: usize, phase: usize, rng: &mut impl Rng) -> Vec<u32> {
+    let mut workload = Vec::with_capacity(count);
+    while workload.len() < count {
+        let set: Vec<u32> = keys.choose_multiple(rng, set_size).cloned().collect();
+        for _ in 0..phase.min(count - workload.len()) {
+            workload.push(*set.choose(rng).unwrap());
+        }
+    }
+    workload
+}
+
+fn repeated_key_workload(keys: &[u32], count: usize, repeats: usize, rng: &mut impl Rng) -> Vec<u32> {
+    let mut workload = Vec::with_capacity(count);
+    while workload.len() < count {
+        let key = *keys.choose(rng).unwrap();
+        let times = repeats.min(count - workload.len());
+        workload.resize(workload.len() + times, key);
+    }
+    workload
+}
+
+fn print_comparison_table(title: &str, rows: &[(&str, [Duration; 4])]) {
+    println!("{:<30} | {:<15} | {:<15} | {:<15} | {:<15}", title, "Treap", "EBST", "RBST", "Splay");
+    for (name, [treap, ebst, rbst, splay]) in rows {
+        println!("{:<30} | {:<15} | {:<15} | {:<15} | {:<15}", name, format!("{:?}", treap), format!("{:?}", ebst), format!("{:?}", rbst), format!("{:?}", splay));
+    }
+}
+
+fn print_shape_table(trees: &[(&str, TreeStats)]) {
+    println!();
+    println!("{:<30} | {:<8} | {:<8} | {:<10} | {:<10}", "Shape", "Nodes", "Height", "Avg Depth", "Rotations");
+    for (name, stats) in trees {
+        println!("{:<30} | {:<8} | {:<8} | {:<10.2} | {:<10}", name, stats.node_count, stats.height, stats.average_depth, stats.rotations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Checks that keys are in strictly increasing order and returns the number of nodes.
+    fn check_order<N: BSTNode>(root: Option<&N>) -> usize {
+        let keys: Vec<_> = BSTIter::new(root, ..).map(|(key, _)| key).collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        keys.len()
+    }
+
+    /// Checks every stored subtree size against a count of its nodes.
+    fn check_rbst_sizes<K, V>(link: &RBSTLink<K, V>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let size = 1 + check_rbst_sizes(&node.left) + check_rbst_sizes(&node.right);
+                assert_eq!(node.size, size);
+                size
+            }
+        }
+    }
+
+    fn check_splay_sizes<K, V>(link: &SplayLink<K, V>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let size = 1 + check_splay_sizes(&node.left) + check_splay_sizes(&node.right);
+                assert_eq!(node.size, size);
+                size
+            }
+        }
+    }
+
+    #[test]
+    fn ebst_matches_btree_map() {
+        let mut rng = rand::thread_rng();
+        let mut tree = EBSTree::new();
+        let mut expected = BTreeMap::new();
+        for i in 0..5_000 {
+            let key = rng.gen_range(0..500);
+            if rng.gen_bool(0.6) {
+                assert_eq!(tree.insert(key, i), expected.insert(key, i));
+            } else {
+                assert_eq!(tree.remove(&key), expected.remove(&key));
+            }
+            assert_eq!(tree.search(&key), expected.get(&key));
+            assert_eq!(tree.len(), expected.len());
+        }
+        assert_eq!(check_order(tree.root.as_deref()), expected.len());
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.range(&100..&200).eq(expected.range(100..200)));
+    }
+
+    #[test]
+    fn rbst_matches_btree_map() {
+        let mut rng = rand::thread_rng();
+        let mut tree = RBSTree::new();
+        let mut expected = BTreeMap::new();
+        for i in 0..5_000 {
+            let key = rng.gen_range(0..500);
+            if rng.gen_bool(0.6) {
+                assert_eq!(tree.insert(key, i), expected.insert(key, i));
+            } else {
+                assert_eq!(tree.remove(&key), expected.remove(&key));
+            }
+            assert_eq!(tree.search(&key), expected.get(&key));
+            if i % 500 == 0 {
+                assert_eq!(check_rbst_sizes(&tree.root), expected.len());
+            }
+        }
+        assert_eq!(check_rbst_sizes(&tree.root), tree.len());
+        assert_eq!(check_order(tree.root.as_deref()), expected.len());
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.range(&100..=&200).eq(expected.range(100..=200)));
+        assert!(tree.range(..&50).eq(expected.range(..50)));
+    }
+
+    #[test]
+    fn rbst_inserts_at_the_root_with_probability_one_over_n_plus_one() {
+        let trials = 4_000;
+        let mut at_root = 0;
+        for _ in 0..trials {
+            let mut tree = RBSTree::new();
+            for key in [1, 3, 5] {
+                tree.insert(key, ());
+            }
+            tree.insert(4, ());
+            assert_eq!(check_rbst_sizes(&tree.root), 4);
+            at_root += (tree.root.as_ref().unwrap().key == 4) as usize;
+        }
+        // expected trials / 4, with a margin of about seven standard deviations
+        assert!((800..1_200).contains(&at_root), "{} of {}", at_root, trials);
+    }
+
+    #[test]
+    fn rbst_stays_shallow_on_sorted_input() {
+        let mut tree = RBSTree::new();
+        for key in 0..10_000 {
+            tree.insert(key, ());
+        }
+        assert_eq!(check_rbst_sizes(&tree.root), 10_000);
+        // an unbalanced tree would be 10 000 deep; random trees average about 3 ln n
+        assert!(tree.height() < 100, "height {}", tree.height());
+        for key in (0..10_000).step_by(2) {
+            assert_eq!(tree.remove(&key), Some(()));
+        }
+        assert_eq!(check_rbst_sizes(&tree.root), 5_000);
+        assert!(tree.iter().map(|(key, _)| *key).eq((1..10_000).step_by(2)));
+    }
+
+    #[test]
+    fn splay_tree_matches_btree_map() {
+        let mut rng = rand::thread_rng();
+        let mut tree = SplayTreeMap::new();
+        let mut expected = BTreeMap::new();
+        for i in 0..5_000 {
+            let key = rng.gen_range(0..500);
+            match rng.gen_range(0..3) {
+                0 => assert_eq!(tree.insert(key, i), expected.insert(key, i)),
+                1 => assert_eq!(tree.remove(&key), expected.remove(&key)),
+                _ => {
+                    assert_eq!(tree.search(&key), expected.get(&key));
+                    // a hit is splayed to the root
+                    if expected.contains_key(&key) {
+                        assert_eq!(tree.root.as_ref().unwrap().key, key);
+                    }
+                }
+            }
+            assert_eq!(tree.len(), expected.len());
+        }
+        assert_eq!(check_splay_sizes(&tree.root), expected.len());
+        assert_eq!(check_order(tree.root.as_deref()), expected.len());
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.range(&100..&300).eq(expected.range(100..300)));
+    }
+
+    #[test]
+    fn splay_tree_split_off_and_join_match_btree_map() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let mut tree = SplayTreeMap::new();
+            let mut expected = BTreeMap::new();
+            for i in 0..rng.gen_range(0..100) {
+                let key = rng.gen_range(0..200);
+                tree.insert(key, i);
+                expected.insert(key, i);
+            }
+            let at = rng.gen_range(0..=200);
+            let upper = tree.split_off(&at);
+            let expected_upper = expected.split_off(&at);
+            assert_eq!(check_splay_sizes(&tree.root), expected.len());
+            assert_eq!(check_splay_sizes(&upper.root), expected_upper.len());
+            assert!(tree.iter().eq(expected.iter()));
+            assert!(upper.iter().eq(expected_upper.iter()));
+
+            let joined = SplayTreeMap::join(tree, upper);
+            expected.extend(expected_upper);
+            assert_eq!(check_splay_sizes(&joined.root), expected.len());
+            assert!(joined.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn drops_degenerate_splay_tree() {
+        let mut tree = SplayTreeMap::new();
+        for key in 0..100_000 {
+            tree.insert(key, key);
+        }
+        // each insert of a new maximum leaves the old root as the new root's left child
+        assert_eq!(tree.height(), 100_000);
+        drop(tree);
+    }
+
+    #[test]
+    fn drops_degenerate_ebs_tree() {
+        // the path ascending inserts make, built directly as inserting it takes quadratic time
+        let mut tree = EBSTree::new();
+        for key in (0..200_000).rev() {
+            let mut node = Box::new(EBSTNode::new(key, key));
+            node.right = tree.root.take();
+            tree.root = Some(node);
+        }
+        assert_eq!(tree.height(), 200_000);
+        drop(tree);
+    }
 }
@@ -34,133 +34,552 @@ where
     (result, end - start)
 }
 
-use std::collections::HashMap;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::iter::Copied;
+use std::slice;
+use std::str::{Bytes, Chars};
 use std::time::{Instant, Duration};
 
+mod aho_corasick;
+use aho_corasick::{AhoCorasick, MatchKind};
+mod codec;
+mod double_array_trie;
+use double_array_trie::DoubleArrayTrie;
+mod radix_tree;
+use radix_tree::RadixTree;
+mod ternary_search_tree;
+use ternary_search_tree::TernarySearchTree;
+
+/// Unit a trie branches on: `char` to key on text, `u8` to key on raw bytes.
+pub trait Symbol: Copy + Ord + Hash {
+    /// Owned key spelled by a sequence of symbols.
+    type Key: Clone + Default + Ord;
+
+    fn push(key: &mut Self::Key, symbol: Self);
+}
+
+impl Symbol for char {
+    type Key = String;
+
+    fn push(key: &mut String, symbol: char) {
+        key.push(symbol);
+    }
+}
+
+impl Symbol for u8 {
+    type Key = Vec<u8>;
+
+    fn push(key: &mut Vec<u8>, symbol: u8) {
+        key.push(symbol);
+    }
+}
+
+/// Borrowed key that can be split into symbols of type `S`. Keys are cheap to copy,
+/// so a method can walk one several times without collecting it.
+pub trait IntoSymbols<S>: Copy {
+    type Symbols: Iterator<Item = S>;
+
+    fn symbols(self) -> Self::Symbols;
+}
+
+impl<'a> IntoSymbols<char> for &'a str {
+    type Symbols = Chars<'a>;
+
+    fn symbols(self) -> Chars<'a> {
+        self.chars()
+    }
+}
+
+impl<'a> IntoSymbols<u8> for &'a str {
+    type Symbols = Bytes<'a>;
+
+    fn symbols(self) -> Bytes<'a> {
+        self.bytes()
+    }
+}
+
+impl<'a> IntoSymbols<char> for &'a String {
+    type Symbols = Chars<'a>;
+
+    fn symbols(self) -> Chars<'a> {
+        self.chars()
+    }
+}
+
+impl<'a> IntoSymbols<u8> for &'a String {
+    type Symbols = Bytes<'a>;
+
+    fn symbols(self) -> Bytes<'a> {
+        self.bytes()
+    }
+}
+
+impl<'a, S: Copy> IntoSymbols<S> for &'a [S] {
+    type Symbols = Copied<slice::Iter<'a, S>>;
+
+    fn symbols(self) -> Self::Symbols {
+        self.iter().copied()
+    }
+}
+
+impl<'a, S: Copy> IntoSymbols<S> for &'a Vec<S> {
+    type Symbols = Copied<slice::Iter<'a, S>>;
+
+    fn symbols(self) -> Self::Symbols {
+        self.iter().copied()
+    }
+}
+
+impl<'a, S: Copy, const N: usize> IntoSymbols<S> for &'a [S; N] {
+    type Symbols = Copied<slice::Iter<'a, S>>;
+
+    fn symbols(self) -> Self::Symbols {
+        self.iter().copied()
+    }
+}
+
+fn spell<S: Symbol>(symbols: impl Iterator<Item = S>) -> S::Key {
+    let mut key = S::Key::default();
+    for symbol in symbols {
+        S::push(&mut key, symbol);
+    }
+    key
+}
+
 // Extended TrieNode to support both Trie and AssociativeArray
-struct TrieNode<T> {
-    children: HashMap<char, TrieNode<T>>,
+struct TrieNode<T, S> {
+    children: HashMap<S, TrieNode<T, S>>,
     is_end_of_word: bool,
     value: Option<T>,
+    // words ending in this subtree, this node included
+    count: usize,
+    // ranking score of the word ending here: its insert count in a Trie,
+    // the caller's score in an AssociativeArray
+    score: u64,
+    // upper bound on the scores in this subtree: raised on insert, not lowered on delete
+    best: u64,
 }
 
-impl<T> TrieNode<T> {
+impl<T, S: Symbol> TrieNode<T, S> {
     fn new() -> Self {
         TrieNode {
             children: HashMap::new(),
             is_end_of_word: false,
             value: None,
+            count: 0,
+            score: 0,
+            best: 0,
         }
     }
-}
 
-impl TrieNode<()> {
-    fn default() -> Self {
-        TrieNode {
-            children: HashMap::new(),
-            is_end_of_word: false,
-            value: None,
+    /// Node reached by spelling `prefix` from this one.
+    fn find(&self, prefix: impl Iterator<Item = S>) -> Option<&Self> {
+        let mut node = self;
+        for symbol in prefix {
+            node = node.children.get(&symbol)?;
         }
+        Some(node)
     }
-}
 
-// Trie Implementation
-pub struct Trie {
-    root: TrieNode<()>,
-}
+    /// Node reached by spelling `key`, creating any missing nodes on the way. Every node
+    /// on the path counts one more word if `is_new`, and has its score bound raised to `score`.
+    fn path_mut(&mut self, key: impl Iterator<Item = S>, is_new: bool, score: u64) -> &mut Self {
+        let mut node = self;
+        node.count += is_new as usize;
+        node.best = node.best.max(score);
+        for symbol in key {
+            node = node.children.entry(symbol).or_insert_with(TrieNode::new);
+            node.count += is_new as usize;
+            node.best = node.best.max(score);
+        }
+        node
+    }
 
-impl Trie {
-    pub fn new() -> Self {
-        Trie {
-            root: TrieNode::default(),
+    /// Removes the word spelled by `key`, returning its value, or `None` if it isn't stored.
+    /// Walks down once, decrementing counts; the first child that only holds this word is
+    /// cut off with its whole branch.
+    fn remove(&mut self, key: impl IntoSymbols<S>) -> Option<Option<T>> {
+        if !self.find(key.symbols())?.is_end_of_word {
+            return None;
         }
+        let mut node = self;
+        node.count -= 1;
+        let mut symbols = key.symbols();
+        while let Some(symbol) = symbols.next() {
+            if node.children[&symbol].count == 1 {
+                let mut branch = node.children.remove(&symbol).expect("word is stored");
+                for symbol in symbols {
+                    branch = branch.children.remove(&symbol).expect("word is stored");
+                }
+                return Some(branch.value);
+            }
+            node = node.children.get_mut(&symbol).expect("word is stored");
+            node.count -= 1;
+        }
+        node.is_end_of_word = false;
+        node.score = 0;
+        Some(node.value.take())
     }
 
-    pub fn insert(&mut self, word: &str) {
-        let mut node = &mut self.root;
-        for ch in word.chars() {
-            node = node.children.entry(ch).or_insert_with(TrieNode::default);
+    /// Words stored in this subtree in lexicographic order, `prefix` being the path to this node.
+    fn words(&self, prefix: S::Key) -> Words<'_, T, S> {
+        Words {
+            stack: vec![(prefix, self)],
         }
-        node.is_end_of_word = true;
     }
 
-    pub fn search(&self, word: &str) -> bool {
-        let mut node = &self.root;
-        for ch in word.chars() {
-            match node.children.get(&ch) {
-                Some(n) => node = n,
-                None => return false,
+    /// Longest stored word that is a prefix of `text`, and its node.
+    fn longest_prefix_of(&self, text: impl Iterator<Item = S>) -> Option<(S::Key, &Self)> {
+        let mut node = self;
+        let mut key = S::Key::default();
+        let mut longest = node.is_end_of_word.then(|| (key.clone(), node));
+        for symbol in text {
+            match node.children.get(&symbol) {
+                Some(child) => node = child,
+                None => break,
+            }
+            S::push(&mut key, symbol);
+            if node.is_end_of_word {
+                longest = Some((key.clone(), node));
             }
         }
-        node.is_end_of_word
+        longest
     }
+}
+
+/// Depth-first walk visiting children in symbol order. For `char`s, code point order is also
+/// the byte order of UTF-8, so the words come out sorted like `str`s.
+struct Words<'a, T, S: Symbol> {
+    stack: Vec<(S::Key, &'a TrieNode<T, S>)>,
+}
+
+impl<'a, T, S: Symbol> Iterator for Words<'a, T, S> {
+    type Item = (S::Key, &'a TrieNode<T, S>);
 
-    pub fn starts_with(&self, prefix: &str) -> bool {
-        let mut node = &self.root;
-        for ch in prefix.chars() {
-            match node.children.get(&ch) {
-                Some(n) => node = n,
-                None => return false,
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            let mut children: Vec<_> = node.children.iter().collect();
+            // largest first, so the smallest is popped next
+            children.sort_unstable_by(|a, b| b.0.cmp(a.0));
+            for (&symbol, child) in children {
+                let mut child_key = key.clone();
+                S::push(&mut child_key, symbol);
+                self.stack.push((child_key, child));
+            }
+            if node.is_end_of_word {
+                return Some((key, node));
             }
         }
-        true
+        None
     }
+}
 
-    pub fn delete(&mut self, word: &str) -> bool {
-        Trie::delete_recursive(&mut self.root, word, 0).is_some()
+/// Entry of the best-first search behind `top_k_with_prefix`: either a whole subtree, ranked
+/// by its score bound, or a single word, ranked by its own score. On equal scores subtrees
+/// come first, so every word of that score is in the heap before any is reported, and words
+/// come out in lexicographic order.
+struct Candidate<'a, T, S: Symbol> {
+    score: u64,
+    is_word: bool,
+    key: S::Key,
+    node: &'a TrieNode<T, S>,
+}
+
+impl<T, S: Symbol> Ord for Candidate<'_, T, S> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.score
+            .cmp(&other.score)
+            .then(other.is_word.cmp(&self.is_word))
+            .then_with(|| other.key.cmp(&self.key))
     }
+}
 
-    fn delete_recursive(node: &mut TrieNode<()>, word: &str, depth: usize) -> Option<bool> {
-        if depth == word.len() {
-            if !node.is_end_of_word {
-                return None;
-            }
-            node.is_end_of_word = false;
-            return Some(node.children.is_empty());
+impl<T, S: Symbol> PartialOrd for Candidate<'_, T, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, S: Symbol> PartialEq for Candidate<'_, T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl<T, S: Symbol> Eq for Candidate<'_, T, S> {}
+
+/// Edit distance used by fuzzy search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditDistance {
+    /// Insertions, deletions and substitutions of one symbol.
+    Levenshtein,
+    /// Levenshtein plus swaps of two adjacent symbols, in the restricted form (optimal string
+    /// alignment) where a swapped pair isn't edited again.
+    Damerau,
+}
+
+/// Walk behind `fuzzy_search`. Each node gets the row of the edit distance table between the
+/// path to it and every prefix of `word`; the row of a child follows from its parent's (and,
+/// for swaps, its grandparent's), and a subtree is skipped once no row below it can get back
+/// under `max_distance`.
+struct Fuzzy<'w, S> {
+    word: &'w [S],
+    max_distance: usize,
+    metric: EditDistance,
+}
+
+impl<S: Symbol> Fuzzy<'_, S> {
+    fn visit<'a, T>(
+        &self,
+        node: &'a TrieNode<T, S>,
+        path: &mut Vec<S>,
+        row: &[usize],
+        parent_row: &[usize],
+        found: &mut Vec<(S::Key, usize, &'a TrieNode<T, S>)>,
+    ) {
+        let distance = row[self.word.len()];
+        if node.is_end_of_word && distance <= self.max_distance {
+            found.push((spell(path.iter().copied()), distance, node));
+        }
+        // rows below are bounded by this row, or by a swap from the parent row
+        let lowest = row.iter().min().copied().unwrap_or(0);
+        let lowest_swap = match self.metric {
+            EditDistance::Levenshtein => usize::MAX,
+            EditDistance::Damerau => parent_row.iter().min().map_or(usize::MAX, |d| d + 1),
+        };
+        if lowest.min(lowest_swap) > self.max_distance {
+            return;
         }
 
-        let ch = word.chars().nth(depth)?;
-        if let Some(child) = node.children.get_mut(&ch) {
-            if let Some(delete_child) = Trie::delete_recursive(child, word, depth + 1) {
-                if delete_child {
-                    node.children.remove(&ch);
+        let mut children: Vec<_> = node.children.iter().collect();
+        children.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (&symbol, child) in children {
+            let mut next = Vec::with_capacity(row.len());
+            next.push(row[0] + 1);
+            for j in 1..row.len() {
+                let substitution = row[j - 1] + (self.word[j - 1] != symbol) as usize;
+                let mut d = substitution.min(row[j] + 1).min(next[j - 1] + 1);
+                if self.metric == EditDistance::Damerau
+                    && j > 1
+                    && path.last() == Some(&self.word[j - 1])
+                    && self.word[j - 2] == symbol
+                {
+                    d = d.min(parent_row[j - 2] + 1);
                 }
-                return Some(node.children.is_empty() && !node.is_end_of_word);
+                next.push(d);
             }
+            path.push(symbol);
+            self.visit(child, path, &next, row, found);
+            path.pop();
         }
-        None
     }
 }
 
-// AssociativeArray using the extended TrieNode
-pub struct AssociativeArray<T> {
-    root: TrieNode<T>,
+// Trie Implementation
+/// Set of words, keyed on `char`s by default or on any other `Symbol`, such as `u8`
+/// to store byte strings.
+pub struct Trie<S = char> {
+    root: TrieNode<(), S>,
 }
 
-impl<T> AssociativeArray<T> {
+impl Trie {
     pub fn new() -> Self {
-        AssociativeArray {
-            root: TrieNode::new(),
+        Trie::default()
+    }
+}
+
+impl<S: Symbol> Trie<S> {
+    /// Adds `word`, or counts one more occurrence of it if it is already stored.
+    pub fn insert(&mut self, word: impl IntoSymbols<S>) {
+        let is_new = !self.search(word);
+        let node = self.root.path_mut(word.symbols(), is_new, 0);
+        node.is_end_of_word = true;
+        node.score += 1;
+    }
+
+    /// Number of times `word` was inserted since it was last deleted.
+    pub fn frequency(&self, word: impl IntoSymbols<S>) -> u64 {
+        self.root
+            .find(word.symbols())
+            .filter(|node| node.is_end_of_word)
+            .map_or(0, |node| node.score)
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    pub fn search(&self, word: impl IntoSymbols<S>) -> bool {
+        self.root
+            .find(word.symbols())
+            .is_some_and(|node| node.is_end_of_word)
+    }
+
+    pub fn starts_with(&self, prefix: impl IntoSymbols<S>) -> bool {
+        self.root.find(prefix.symbols()).is_some()
+    }
+
+    /// Number of stored words starting with `prefix`, read off the node it leads to.
+    pub fn count_with_prefix(&self, prefix: impl IntoSymbols<S>) -> usize {
+        self.root
+            .find(prefix.symbols())
+            .map_or(0, |node| node.count)
+    }
+
+    /// Stored words starting with `prefix`, in lexicographic order.
+    pub fn iter_prefix(&self, prefix: impl IntoSymbols<S>) -> impl Iterator<Item = S::Key> + '_ {
+        let words = self
+            .root
+            .find(prefix.symbols())
+            .map(|node| node.words(spell(prefix.symbols())));
+        words.into_iter().flatten().map(|(word, _)| word)
+    }
+
+    pub fn keys_with_prefix(&self, prefix: impl IntoSymbols<S>) -> Vec<S::Key> {
+        self.iter_prefix(prefix).collect()
+    }
+
+    /// Longest stored word that `text` starts with.
+    pub fn longest_prefix_of(&self, text: impl IntoSymbols<S>) -> Option<S::Key> {
+        let (word, _) = self.root.longest_prefix_of(text.symbols())?;
+        Some(word)
+    }
+
+    pub fn delete(&mut self, word: impl IntoSymbols<S>) -> bool {
+        self.root.remove(word).is_some()
+    }
+
+    /// Stored words within `max_distance` Levenshtein edits of `word`, with their distance,
+    /// in lexicographic order.
+    pub fn fuzzy_search(
+        &self,
+        word: impl IntoSymbols<S>,
+        max_distance: usize,
+    ) -> Vec<(S::Key, usize)> {
+        self.fuzzy_search_with(word, max_distance, EditDistance::Levenshtein)
+    }
+
+    pub fn fuzzy_search_with(
+        &self,
+        word: impl IntoSymbols<S>,
+        max_distance: usize,
+        metric: EditDistance,
+    ) -> Vec<(S::Key, usize)> {
+        self.fuzzy(word, max_distance, metric)
+            .into_iter()
+            .map(|(key, distance, _)| (key, distance))
+            .collect()
+    }
+
+    fn fuzzy(
+        &self,
+        word: impl IntoSymbols<S>,
+        max_distance: usize,
+        metric: EditDistance,
+    ) -> Vec<(S::Key, usize, &TrieNode<(), S>)> {
+        let word: Vec<S> = word.symbols().collect();
+        let search = Fuzzy {
+            word: &word,
+            max_distance,
+            metric,
+        };
+        let first_row: Vec<usize> = (0..=word.len()).collect();
+        let mut found = Vec::new();
+        search.visit(&self.root, &mut Vec::new(), &first_row, &[], &mut found);
+        found
+    }
+
+    /// Up to `k` spelling suggestions for `word`: the stored words closest to it by
+    /// Damerau distance, the most frequent first among equally close ones. The search
+    /// radius grows one edit at a time until `k` words are found, staying below the length of
+    /// `word`, where any word of that length would qualify. Remaining ties are in lexicographic
+    /// order.
+    pub fn suggest(&self, word: impl IntoSymbols<S>, k: usize) -> Vec<S::Key> {
+        let len = word.symbols().count();
+        let mut found = Vec::new();
+        for max_distance in 0..len.max(1) {
+            found = self.fuzzy(word, max_distance, EditDistance::Damerau);
+            if found.len() >= k {
+                break;
+            }
         }
+        found.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.score.cmp(&a.2.score)));
+        found.into_iter().take(k).map(|(key, _, _)| key).collect()
     }
+}
 
-    pub fn insert(&mut self, key: &str, value: T) {
-        let mut node = &mut self.root;
-        for ch in key.chars() {
-            node = node.children.entry(ch).or_insert_with(TrieNode::new);
+impl<S: Symbol> Default for Trie<S> {
+    fn default() -> Self {
+        Trie {
+            root: TrieNode::new(),
         }
+    }
+}
+
+// AssociativeArray using the extended TrieNode
+pub struct AssociativeArray<T, S = char> {
+    root: TrieNode<T, S>,
+}
+
+impl<T> AssociativeArray<T> {
+    pub fn new() -> Self {
+        AssociativeArray::default()
+    }
+}
+
+impl<T, S: Symbol> AssociativeArray<T, S> {
+    /// Inserts `value` under `key`, keeping the key's score if it was already present.
+    pub fn insert(&mut self, key: impl IntoSymbols<S>, value: T) {
+        self.insert_scored(key, value, None);
+    }
+
+    /// Inserts `value` under `key` with the score `top_k_with_prefix` ranks it by.
+    pub fn insert_with_score(&mut self, key: impl IntoSymbols<S>, value: T, score: u64) {
+        self.insert_scored(key, value, Some(score));
+    }
+
+    fn insert_scored(&mut self, key: impl IntoSymbols<S>, value: T, score: Option<u64>) {
+        let is_new = self.get(key).is_none();
+        let node = self
+            .root
+            .path_mut(key.symbols(), is_new, score.unwrap_or(0));
         node.is_end_of_word = true;
         node.value = Some(value);
+        if let Some(score) = score {
+            node.score = score;
+        }
     }
 
-    pub fn get(&self, key: &str) -> Option<&T> {
-        let mut node = &self.root;
-        for ch in key.chars() {
-            match node.children.get(&ch) {
-                Some(n) => node = n,
-                None => return None,
-            }
+    /// Changes the score of `key`, returning false if it is absent.
+    pub fn set_score(&mut self, key: impl IntoSymbols<S>, score: u64) -> bool {
+        if self.get(key).is_none() {
+            return false;
         }
+        self.root.path_mut(key.symbols(), false, score).score = score;
+        true
+    }
+
+    pub fn score(&self, key: impl IntoSymbols<S>) -> Option<u64> {
+        let node = self.root.find(key.symbols())?;
+        node.is_end_of_word.then_some(node.score)
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    pub fn get(&self, key: impl IntoSymbols<S>) -> Option<&T> {
+        let node = self.root.find(key.symbols())?;
         if node.is_end_of_word {
             node.value.as_ref()
         } else {
@@ -168,41 +587,98 @@ impl<T> AssociativeArray<T> {
         }
     }
 
-    pub fn starts_with(&self, prefix: &str) -> bool {
-        let mut node = &self.root;
-        for ch in prefix.chars() {
-            match node.children.get(&ch) {
-                Some(n) => node = n,
-                None => return false,
-            }
-        }
-        true
+    pub fn starts_with(&self, prefix: impl IntoSymbols<S>) -> bool {
+        self.root.find(prefix.symbols()).is_some()
     }
 
-    pub fn delete(&mut self, key: &str) -> bool {
-        AssociativeArray::delete_recursive(&mut self.root, key, 0).is_some()
+    /// Number of stored keys starting with `prefix`, read off the node it leads to.
+    pub fn count_with_prefix(&self, prefix: impl IntoSymbols<S>) -> usize {
+        self.root
+            .find(prefix.symbols())
+            .map_or(0, |node| node.count)
     }
 
-    fn delete_recursive(node: &mut TrieNode<T>, key: &str, depth: usize) -> Option<bool> {
-        if depth == key.len() {
-            if !node.is_end_of_word {
-                return None;
-            }
-            node.is_end_of_word = false;
-            node.value = None;
-            return Some(node.children.is_empty());
+    /// Entries whose key starts with `prefix`, in lexicographic key order.
+    pub fn iter_prefix(
+        &self,
+        prefix: impl IntoSymbols<S>,
+    ) -> impl Iterator<Item = (S::Key, &T)> + '_ {
+        let words = self
+            .root
+            .find(prefix.symbols())
+            .map(|node| node.words(spell(prefix.symbols())));
+        words
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, node)| Some((key, node.value.as_ref()?)))
+    }
+
+    pub fn keys_with_prefix(&self, prefix: impl IntoSymbols<S>) -> Vec<S::Key> {
+        self.iter_prefix(prefix).map(|(key, _)| key).collect()
+    }
+
+    /// Entry with the longest key that `text` starts with.
+    pub fn longest_prefix_of(&self, text: impl IntoSymbols<S>) -> Option<(S::Key, &T)> {
+        let (key, node) = self.root.longest_prefix_of(text.symbols())?;
+        Some((key, node.value.as_ref()?))
+    }
+
+    /// The `k` entries under `prefix` with the highest scores, best first, ties in key order.
+    /// Subtrees are expanded best bound first, so only the branches that can still
+    /// make the top `k` are visited.
+    pub fn top_k_with_prefix(&self, prefix: impl IntoSymbols<S>, k: usize) -> Vec<(S::Key, &T)> {
+        let mut top = Vec::new();
+        let mut heap = BinaryHeap::new();
+        if let Some(node) = self.root.find(prefix.symbols()) {
+            heap.push(Candidate {
+                score: node.best,
+                is_word: false,
+                key: spell(prefix.symbols()),
+                node,
+            });
         }
 
-        let ch = key.chars().nth(depth)?;
-        if let Some(child) = node.children.get_mut(&ch) {
-            if let Some(delete_child) = AssociativeArray::delete_recursive(child, key, depth + 1) {
-                if delete_child {
-                    node.children.remove(&ch);
+        while top.len() < k {
+            let Some(candidate) = heap.pop() else { break };
+            let node = candidate.node;
+            if candidate.is_word {
+                if let Some(value) = &node.value {
+                    top.push((candidate.key, value));
                 }
-                return Some(node.children.is_empty() && !node.is_end_of_word);
+                continue;
+            }
+            if node.is_end_of_word {
+                heap.push(Candidate {
+                    score: node.score,
+                    is_word: true,
+                    key: candidate.key.clone(),
+                    node,
+                });
+            }
+            for (&symbol, child) in &node.children {
+                let mut key = candidate.key.clone();
+                S::push(&mut key, symbol);
+                heap.push(Candidate {
+                    score: child.best,
+                    is_word: false,
+                    key,
+                    node: child,
+                });
             }
         }
-        None
+        top
+    }
+
+    pub fn delete(&mut self, key: impl IntoSymbols<S>) -> bool {
+        self.root.remove(key).is_some()
+    }
+}
+
+impl<T, S: Symbol> Default for AssociativeArray<T, S> {
+    fn default() -> Self {
+        AssociativeArray {
+            root: TrieNode::new(),
+        }
     }
 }
 
@@ -237,18 +713,26 @@ fn main() {
     let mut assoc_array_numbers = AssociativeArray::new();
     let (_, assoc_array_memory_usage) = measure_memory(|| {
         for (i, word) in str_words.iter().enumerate() {
-            assoc_array_numbers.insert(word, i);
+            assoc_array_numbers.insert(*word, i);
+        }
+    });
+
+    let mut radix_tree = RadixTree::new();
+    let (_, radix_tree_memory_usage) = measure_memory(|| {
+        for (i, word) in str_words.iter().enumerate() {
+            radix_tree.insert(word, i);
         }
     });
 
     println!("\nMemory Usage");
     println!("Trie: {} bytes", trie_memory_usage);
     println!("AssociativeArray: {} bytes", assoc_array_memory_usage);
+    println!("RadixTree: {} bytes ({} edges)", radix_tree_memory_usage, radix_tree.edge_count());
 
     let words: Vec<String> = (0..1_000_000).map(|i| format!("word{}", i)).collect();
 
-    println!("\nOperation  | Trie Time | AssociativeArray Time");
-    println!("-----------|-----------|---------------------");
+    println!("\nOperation  | Trie Time | AssociativeArray Time | RadixTree Time");
+    println!("-----------|-----------|-----------------------|---------------");
 
     // Insert
     let trie_insert_time = measure_time(|| {
@@ -263,7 +747,13 @@ fn main() {
         }
     });
 
-    println!("Insert     | {:9.6}s | {:19.6}s", trie_insert_time.as_secs_f64(), assoc_array_insert_time.as_secs_f64());
+    let radix_tree_insert_time = measure_time(|| {
+        for (i, word) in words.iter().enumerate() {
+            radix_tree.insert(word, i);
+        }
+    });
+
+    println!("Insert     | {:9.6}s | {:21.6}s | {:13.6}s", trie_insert_time.as_secs_f64(), assoc_array_insert_time.as_secs_f64(), radix_tree_insert_time.as_secs_f64());
 
     // Search
     let trie_search_time = measure_time(|| {
@@ -278,7 +768,13 @@ fn main() {
         }
     });
 
-    println!("Search     | {:9.6}s | {:19.6}s", trie_search_time.as_secs_f64(), assoc_array_search_time.as_secs_f64());
+    let radix_tree_search_time = measure_time(|| {
+        for word in &words {
+            radix_tree.get(word);
+        }
+    });
+
+    println!("Search     | {:9.6}s | {:21.6}s | {:13.6}s", trie_search_time.as_secs_f64(), assoc_array_search_time.as_secs_f64(), radix_tree_search_time.as_secs_f64());
 
     // Starts With
     let trie_starts_with_time = measure_time(|| {
@@ -293,7 +789,13 @@ fn main() {
         }
     });
 
-    println!("StartsWith | {:9.6}s | {:19.6}s", trie_starts_with_time.as_secs_f64(), assoc_array_starts_with_time.as_secs_f64());
+    let radix_tree_starts_with_time = measure_time(|| {
+        for word in &words {
+            radix_tree.starts_with(&word[0..3]);
+        }
+    });
+
+    println!("StartsWith | {:9.6}s | {:21.6}s | {:13.6}s", trie_starts_with_time.as_secs_f64(), assoc_array_starts_with_time.as_secs_f64(), radix_tree_starts_with_time.as_secs_f64());
 
     // Delete
     let trie_delete_time = measure_time(|| {
@@ -308,7 +810,46 @@ fn main() {
         }
     });
 
-    println!("Delete     | {:9.6}s | {:19.6}s", trie_delete_time.as_secs_f64(), assoc_array_delete_time.as_secs_f64());
+    let radix_tree_delete_time = measure_time(|| {
+        for word in &words {
+            radix_tree.delete(word);
+        }
+    });
+
+    println!("Delete     | {:9.6}s | {:21.6}s | {:13.6}s", trie_delete_time.as_secs_f64(), assoc_array_delete_time.as_secs_f64(), radix_tree_delete_time.as_secs_f64());
+
+    // Static Dictionaries: built once from a sorted word list, then saved and loaded as bytes
+    let mut sorted_words = string_words.clone();
+    sorted_words.sort_unstable();
+
+    let build_start = Instant::now();
+    let (tst, tst_memory_usage) = measure_memory(|| {
+        TernarySearchTree::from_sorted(sorted_words.iter().enumerate().map(|(i, word)| (word, i as u32)).collect())
+    });
+    let tst_build_time = build_start.elapsed();
+    let build_start = Instant::now();
+    let (double_array, double_array_memory_usage) = measure_memory(|| DoubleArrayTrie::new(&sorted_words));
+    let double_array_build_time = build_start.elapsed();
+
+    let tst_search_time = measure_time(|| {
+        for word in &string_words {
+            tst.get(word);
+        }
+    });
+    let double_array_search_time = measure_time(|| {
+        for word in &string_words {
+            double_array.get(word);
+        }
+    });
+    let tst_bytes = tst.to_bytes();
+    let double_array_bytes = double_array.to_bytes();
+    let loaded = DoubleArrayTrie::from_bytes(&double_array_bytes).expect("bytes written by to_bytes");
+
+    println!("\nStatic Dictionaries ({} words)", sorted_words.len());
+    println!("TernarySearchTree: built in {:.6}s, {} bytes in memory, {} bytes saved, search {:.6}s", tst_build_time.as_secs_f64(), tst_memory_usage, tst_bytes.len(), tst_search_time.as_secs_f64());
+    println!("DoubleArrayTrie:   built in {:.6}s, {} bytes in memory, {} bytes saved, search {:.6}s", double_array_build_time.as_secs_f64(), double_array_memory_usage, double_array_bytes.len(), double_array_search_time.as_secs_f64());
+    println!("Words in the loaded DoubleArrayTrie starting with 'word9999': {}", loaded.prefix_search("word9999").len());
+    println!("Dictionary words 'word12345x' starts with: {:?}", loaded.common_prefix_search("word12345x"));
 
     // Proof of Advantages: Dictionary Example
     let mut dictionary = AssociativeArray::new();
@@ -331,6 +872,56 @@ fn main() {
     println!("Max Connections: {:?}", config_str.get("max_connections"));
     println!("Timeout: {:?}", config_str.get("timeout"));
     println!("Hostname: {:?}", config_str.get("hostname"));
+
+    // Autocomplete Example: queries ranked by how often they were searched
+    let mut search_box = AssociativeArray::new();
+    search_box.insert_with_score("rust", "/rust", 900);
+    search_box.insert_with_score("rust analyzer", "/rust-analyzer", 850);
+    search_box.insert_with_score("rust book", "/book", 700);
+    search_box.insert_with_score("rustup", "/rustup", 400);
+    search_box.insert_with_score("ruby", "/ruby", 600);
+
+    println!("\nAutocomplete Example");
+    println!("Completions of 'rust': {:?}", search_box.keys_with_prefix("rust"));
+    println!("Queries starting with 'ru': {}", search_box.count_with_prefix("ru"));
+    println!("Top 2 for 'ru': {:?}", search_box.top_k_with_prefix("ru", 2));
+    println!("Longest query 'rustup update' starts with: {:?}", search_box.longest_prefix_of("rustup update"));
+
+    // Byte-level Example: dispatching commands of a binary protocol
+    let mut commands: AssociativeArray<&str, u8> = AssociativeArray::default();
+    commands.insert(b"\x01GET", "get");
+    commands.insert(b"\x01GETEX", "get with expiry");
+    commands.insert(b"\x02SET", "set");
+
+    println!("\nByte-level Example");
+    println!("Command for b\"\\x01GETEX key\": {:?}", commands.longest_prefix_of(b"\x01GETEX key").map(|(_, name)| name));
+    println!("Commands starting with 0x01: {}", commands.count_with_prefix(&[0x01]));
+
+    // Spelling Example: suggestions ranked by edit distance, then by how often a word was seen
+    let mut vocabulary = Trie::new();
+    for word in "the cat sat on the mat then the hat fell on the cat".split(' ') {
+        vocabulary.insert(word);
+    }
+
+    println!("\nSpelling Example");
+    println!("Within 1 edit of 'mat': {:?}", vocabulary.fuzzy_search("mat", 1));
+    println!("Within 1 edit of 'hte', counting swaps: {:?}", vocabulary.fuzzy_search_with("hte", 1, EditDistance::Damerau));
+    println!("Suggestions for 'cas': {:?}", vocabulary.suggest("cas", 3));
+
+    // Log Scanning Example: many patterns in one pass over a log arriving in chunks
+    let patterns = ["ERROR", "WARN", "timeout", "time", "disk full"];
+    let scanner = AhoCorasick::with_match_kind(patterns, MatchKind::LeftmostLongest);
+    let mut stream = scanner.stream();
+    let mut hits = Vec::new();
+    for chunk in ["12:00 ERROR disk f", "ull\n12:01 WARN connection time", "out\n"] {
+        hits.extend(stream.feed(chunk.as_bytes()));
+    }
+    hits.extend(stream.finish());
+
+    println!("\nLog Scanning Example");
+    for hit in hits {
+        println!("{:?} at {}..{}", patterns[hit.pattern], hit.start, hit.end);
+    }
 }
 
 // Measure time function
@@ -342,3 +933,318 @@ where
     f();
     start.elapsed()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn prefix_queries_on_trie() {
+        let mut trie = Trie::new();
+        for word in ["apple", "app", "application", "apt", "banana", "app"] {
+            trie.insert(word);
+        }
+        assert_eq!(trie.len(), 5);
+        assert_eq!(
+            trie.keys_with_prefix("app"),
+            vec!["app", "apple", "application"]
+        );
+        assert_eq!(
+            trie.keys_with_prefix("ap").len(),
+            trie.count_with_prefix("ap")
+        );
+        assert_eq!(trie.count_with_prefix(""), 5);
+        assert_eq!(trie.count_with_prefix("c"), 0);
+        assert_eq!(trie.iter_prefix("").last(), Some("banana".to_string()));
+
+        assert_eq!(
+            trie.longest_prefix_of("applesauce").as_deref(),
+            Some("apple")
+        );
+        assert_eq!(trie.longest_prefix_of("appl").as_deref(), Some("app"));
+        assert_eq!(trie.longest_prefix_of("ap"), None);
+
+        assert!(trie.delete("app"));
+        assert!(!trie.delete("app"));
+        assert_eq!(trie.count_with_prefix("app"), 2);
+        assert_eq!(trie.longest_prefix_of("appl"), None);
+    }
+
+    #[test]
+    fn prefix_queries_on_associative_array() {
+        let mut array = AssociativeArray::new();
+        let words = ["tea", "ten", "to", "inn", "in", "tenant"];
+        for (i, word) in words.iter().enumerate() {
+            array.insert(*word, i);
+        }
+        array.insert("tea", 10);
+        assert_eq!(array.len(), 6);
+        assert_eq!(
+            array.iter_prefix("te").collect::<Vec<_>>(),
+            vec![
+                ("tea".to_string(), &10),
+                ("ten".to_string(), &1),
+                ("tenant".to_string(), &5)
+            ]
+        );
+        assert_eq!(array.count_with_prefix("t"), 4);
+        assert_eq!(
+            array.longest_prefix_of("tenants"),
+            Some(("tenant".to_string(), &5))
+        );
+
+        let mut sorted = words.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(array.keys_with_prefix(""), sorted);
+
+        assert!(array.delete("ten"));
+        assert_eq!(array.count_with_prefix("te"), 2);
+        assert_eq!(array.count_with_prefix(""), 5);
+    }
+
+    #[test]
+    fn top_k_by_score() {
+        let mut array = AssociativeArray::new();
+        for (word, score) in [
+            ("car", 5),
+            ("cart", 9),
+            ("care", 9),
+            ("cat", 1),
+            ("dog", 100),
+        ] {
+            array.insert_with_score(word, score, score);
+        }
+        let top = |array: &AssociativeArray<u64>, prefix: &str, k: usize| {
+            array
+                .top_k_with_prefix(prefix, k)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(top(&array, "ca", 3), vec!["care", "cart", "car"]);
+        assert_eq!(top(&array, "ca", 10).len(), 4);
+        assert_eq!(top(&array, "x", 3), Vec::<String>::new());
+
+        // a stale bound left by a deleted or lowered score must not change the ranking
+        assert!(array.set_score("cart", 0));
+        array.delete("care");
+        assert_eq!(top(&array, "ca", 2), vec!["car", "cat"]);
+        assert_eq!(array.score("cart"), Some(0));
+        assert!(!array.set_score("care", 1));
+
+        // insert keeps the score, insert_with_score replaces it
+        array.insert("car", 0);
+        assert_eq!(array.score("car"), Some(5));
+        array.insert_with_score("cat", 0, 50);
+        assert_eq!(top(&array, "", 2), vec!["dog", "cat"]);
+    }
+
+    #[test]
+    fn deletes_non_ascii_words() {
+        let words = [
+            "привет",
+            "при",
+            "приветствие",
+            "мир",
+            "👍",
+            "👍🏽",
+            "e\u{301}",
+            "\u{e9}",
+            "e",
+        ];
+        let mut trie = Trie::new();
+        let mut array = AssociativeArray::new();
+        for (i, word) in words.iter().enumerate() {
+            trie.insert(*word);
+            array.insert(*word, i);
+        }
+        assert_eq!(trie.len(), words.len());
+        assert_eq!(trie.count_with_prefix("при"), 3);
+        // a combining accent is its own char, so "e\u{301}" extends "e" but "\u{e9}" doesn't
+        assert_eq!(trie.count_with_prefix("e"), 2);
+        assert_eq!(trie.longest_prefix_of("👍🏽👍").as_deref(), Some("👍🏽"));
+
+        // a word in the middle of a chain keeps the words above and below it
+        assert!(trie.delete("привет"));
+        assert!(array.delete("привет"));
+        assert!(!trie.delete("привет"));
+        assert!(!trie.delete("прив"));
+        assert_eq!(trie.keys_with_prefix("при"), vec!["при", "приветствие"]);
+        assert_eq!(array.get("приветствие"), Some(&2));
+
+        // a leaf goes with the branch that only led to it
+        assert!(trie.delete("приветствие"));
+        assert!(array.delete("приветствие"));
+        assert!(!trie.starts_with("прив"));
+        assert!(trie.search("при"));
+        assert_eq!(array.get("при"), Some(&1));
+
+        assert!(trie.delete("👍"));
+        assert!(array.delete("👍🏽"));
+        assert!(trie.search("👍🏽"));
+        assert_eq!(array.get("👍"), Some(&4));
+        assert!(trie.delete("e"));
+        assert!(array.delete("e\u{301}"));
+        assert!(trie.search("e\u{301}"));
+        assert!(array.starts_with("e"));
+        assert_eq!(array.get("e"), Some(&8));
+        assert_eq!(array.get("\u{e9}"), Some(&7));
+
+        assert_eq!(trie.len(), words.len() - 4);
+        assert_eq!(trie.count_with_prefix(""), trie.iter_prefix("").count());
+        assert_eq!(array.len(), words.len() - 4);
+        assert_eq!(array.count_with_prefix(""), array.iter_prefix("").count());
+    }
+
+    #[test]
+    fn byte_trie() {
+        let mut trie: Trie<u8> = Trie::default();
+        trie.insert(b"GET");
+        trie.insert(b"GETRANGE");
+        trie.insert(&[0xff, 0x00, 0xfe][..]);
+        trie.insert(&vec![0xff]);
+        assert_eq!(trie.len(), 4);
+        assert!(trie.search(b"GET"));
+        assert!(!trie.search(b"GE"));
+        assert_eq!(
+            trie.longest_prefix_of(b"GETRANGEX"),
+            Some(b"GETRANGE".to_vec())
+        );
+        assert_eq!(
+            trie.keys_with_prefix(&[0xff]),
+            vec![vec![0xff], vec![0xff, 0x00, 0xfe]]
+        );
+        assert!(trie.delete(&[0xff, 0x00, 0xfe]));
+        assert_eq!(trie.count_with_prefix(&[0xff]), 1);
+
+        let mut array: AssociativeArray<&str, u8> = AssociativeArray::default();
+        array.insert_with_score(b"PING", "ping", 3);
+        array.insert_with_score(b"PUBLISH", "publish", 5);
+        assert_eq!(
+            array.top_k_with_prefix(b"P", 1),
+            vec![(b"PUBLISH".to_vec(), &"publish")]
+        );
+        assert!(array.delete(b"PUBLISH"));
+        assert_eq!(array.get(b"PUBLISH"), None);
+        assert_eq!(array.len(), 1);
+    }
+
+    #[test]
+    fn fuzzy_search_matches_brute_force() {
+        fn distance(a: &[char], b: &[char], metric: EditDistance) -> usize {
+            let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in 0..=a.len() {
+                for j in 0..=b.len() {
+                    table[i][j] = if i == 0 || j == 0 {
+                        i + j
+                    } else {
+                        let substitution = table[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as usize;
+                        let mut d = substitution
+                            .min(table[i - 1][j] + 1)
+                            .min(table[i][j - 1] + 1);
+                        if metric == EditDistance::Damerau
+                            && i > 1
+                            && j > 1
+                            && a[i - 1] == b[j - 2]
+                            && a[i - 2] == b[j - 1]
+                        {
+                            d = d.min(table[i - 2][j - 2] + 1);
+                        }
+                        d
+                    };
+                }
+            }
+            table[a.len()][b.len()]
+        }
+
+        let mut rng = rand::thread_rng();
+        let random_word = |rng: &mut rand::rngs::ThreadRng| -> String {
+            let len = rng.gen_range(0..7);
+            (0..len).map(|_| rng.gen_range('a'..'e')).collect()
+        };
+        let mut trie = Trie::new();
+        let mut words = Vec::new();
+        for _ in 0..300 {
+            let word = random_word(&mut rng);
+            trie.insert(&word);
+            words.push(word);
+        }
+        words.sort_unstable();
+        words.dedup();
+
+        for _ in 0..100 {
+            let query = random_word(&mut rng);
+            let query_chars: Vec<char> = query.chars().collect();
+            for metric in [EditDistance::Levenshtein, EditDistance::Damerau] {
+                for max_distance in 0..3 {
+                    let expected: Vec<(String, usize)> = words
+                        .iter()
+                        .map(|word| {
+                            let chars: Vec<char> = word.chars().collect();
+                            (word.clone(), distance(&chars, &query_chars, metric))
+                        })
+                        .filter(|&(_, d)| d <= max_distance)
+                        .collect();
+                    assert_eq!(
+                        trie.fuzzy_search_with(&query, max_distance, metric),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spelling_suggestions() {
+        let mut trie = Trie::new();
+        for word in [
+            "form", "from", "from", "farm", "forum", "for", "for", "for", "fern",
+        ] {
+            trie.insert(word);
+        }
+        assert_eq!(trie.len(), 6);
+        assert_eq!(trie.frequency("for"), 3);
+        assert_eq!(trie.frequency("fo"), 0);
+
+        // a swap is two Levenshtein edits but one Damerau edit
+        assert_eq!(trie.fuzzy_search("fomr", 1), vec![("for".to_string(), 1)]);
+        assert_eq!(
+            trie.fuzzy_search_with("fomr", 1, EditDistance::Damerau),
+            vec![("for".to_string(), 1), ("form".to_string(), 1)]
+        );
+
+        // closest first, then most frequent, then alphabetical
+        assert_eq!(trie.suggest("fomr", 3), vec!["for", "form", "from"]);
+        assert_eq!(trie.suggest("frm", 4), vec!["from", "farm", "form", "for"]);
+        assert_eq!(trie.suggest("fxrm", 10).len(), 6);
+        assert!(trie.suggest("xyz", 1).is_empty());
+
+        // Unicode words are compared char by char
+        trie.insert("fôrm");
+        assert_eq!(trie.fuzzy_search("form", 1).len(), 5);
+        assert!(trie.delete("for"));
+        assert_eq!(trie.frequency("for"), 0);
+        trie.insert("for");
+        assert_eq!(trie.frequency("for"), 1);
+    }
+
+    #[test]
+    fn byte_and_char_tries_agree_on_text() {
+        let words = ["café", "cafe", "caf\u{e9}s", "日本", "日本語", "🦀", "🦀🦀"];
+        let mut chars = Trie::new();
+        let mut bytes: Trie<u8> = Trie::default();
+        for word in words {
+            chars.insert(word);
+            bytes.insert(word);
+        }
+        for prefix in ["", "caf", "café", "日", "🦀"] {
+            let as_bytes: Vec<Vec<u8>> =
+                chars.iter_prefix(prefix).map(String::into_bytes).collect();
+            assert_eq!(bytes.keys_with_prefix(prefix), as_bytes);
+        }
+        // the byte trie can also stop inside a multi-byte char
+        assert_eq!(bytes.count_with_prefix(&"日".as_bytes()[..1]), 2);
+        assert_eq!(chars.count_with_prefix("日"), 2);
+    }
+}